@@ -19,7 +19,7 @@ use winit_core::event_loop::pump_events::PumpStatus;
 use winit_core::event_loop::{
     ActiveEventLoop as RootActiveEventLoop, ControlFlow, DeviceEvents, EventLoopProvider,
     EventLoopProxy as CoreEventLoopProxy, EventLoopProxyProvider,
-    OwnedDisplayHandle as CoreOwnedDisplayHandle,
+    OwnedDisplayHandle as CoreOwnedDisplayHandle, proxy_channel,
 };
 use winit_core::monitor::{Fullscreen, MonitorHandle as CoreMonitorHandle};
 use winit_core::window::{
@@ -282,6 +282,7 @@ impl EventLoop {
         }
 
         if self.window_target.event_loop_proxy.wake_up.swap(false, Ordering::Relaxed) {
+            proxy_channel::dispatch_handlers();
             app.proxy_wake_up(&self.window_target);
         }
 
@@ -384,6 +385,8 @@ impl EventLoop {
                                     _ => event::ButtonSource::Unknown(0),
                                 },
                                 is_macos_activation_click: false,
+                                timestamp: None,
+                                click_count: 1,
                             };
                             app.window_event(&self.window_target, GLOBAL_WINDOW, event);
                         },
@@ -401,6 +404,8 @@ impl EventLoop {
                                     android_activity::input::ToolType::Mouse => continue,
                                     _ => event::PointerSource::Unknown,
                                 },
+                                timestamp: None,
+                                history: Vec::new(),
                             };
                             app.window_event(&self.window_target, GLOBAL_WINDOW, event);
                         },
@@ -428,6 +433,8 @@ impl EventLoop {
                                         _ => event::ButtonSource::Unknown(0),
                                     },
                                     is_macos_activation_click: false,
+                                    timestamp: None,
+                                    click_count: 1,
                                 };
                                 app.window_event(&self.window_target, GLOBAL_WINDOW, event);
                             }
@@ -495,6 +502,7 @@ impl EventLoop {
                                 key_without_modifiers: keycodes::to_logical(key_char, keycode),
                             },
                             is_synthetic: false,
+                            timestamp: None,
                         };
 
                         app.window_event(&self.window_target, GLOBAL_WINDOW, event);
@@ -579,7 +587,7 @@ impl EventLoop {
         } else {
             let control_flow_timeout = match self.control_flow() {
                 ControlFlow::Wait => None,
-                ControlFlow::Poll => Some(Duration::ZERO),
+                ControlFlow::Poll | ControlFlow::Animate => Some(Duration::ZERO),
                 ControlFlow::WaitUntil(wait_deadline) => {
                     Some(wait_deadline.saturating_duration_since(start))
                 },
@@ -620,7 +628,7 @@ impl EventLoop {
             }
 
             self.cause = match self.control_flow() {
-                ControlFlow::Poll => StartCause::Poll,
+                ControlFlow::Poll | ControlFlow::Animate => StartCause::Poll,
                 ControlFlow::Wait => StartCause::WaitCancelled { start, requested_resume: None },
                 ControlFlow::WaitUntil(deadline) => {
                     if Instant::now() < deadline {
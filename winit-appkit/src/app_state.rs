@@ -17,7 +17,7 @@ use winit_common::event_handler::EventHandler;
 use winit_core::application::ApplicationHandler;
 use winit_core::data_transfer::DataTransferId;
 use winit_core::event::{StartCause, WindowEvent};
-use winit_core::event_loop::{ControlFlow, DndAction};
+use winit_core::event_loop::{ControlFlow, DndAction, proxy_channel};
 use winit_core::window::WindowId;
 
 use super::event_loop::{ActiveEventLoop, notify_windows_of_exit, stop_app_immediately};
@@ -76,7 +76,10 @@ impl AppState {
         activate_ignoring_other_apps: bool,
     ) -> Option<Rc<Self>> {
         let event_loop_proxy = Arc::new(EventLoopProxy::new(mtm, move || {
-            Self::get(mtm).with_handler(|app, event_loop| app.proxy_wake_up(event_loop));
+            Self::get(mtm).with_handler(|app, event_loop| {
+                proxy_channel::dispatch_handlers();
+                app.proxy_wake_up(event_loop)
+            });
         }));
 
         let this = Rc::new(Self {
@@ -353,7 +356,7 @@ impl AppState {
 
         let start = self.start_time.get().unwrap();
         let cause = match self.control_flow() {
-            ControlFlow::Poll => StartCause::Poll,
+            ControlFlow::Poll | ControlFlow::Animate => StartCause::Poll,
             ControlFlow::Wait => StartCause::WaitCancelled { start, requested_resume: None },
             ControlFlow::WaitUntil(requested_resume) => {
                 if Instant::now() >= requested_resume {
@@ -399,7 +402,7 @@ impl AppState {
         let wait_timeout = self.wait_timeout.get(); // configured by pump_events
         let app_timeout = match self.control_flow() {
             ControlFlow::Wait => None,
-            ControlFlow::Poll => Some(Instant::now()),
+            ControlFlow::Poll | ControlFlow::Animate => Some(Instant::now()),
             ControlFlow::WaitUntil(instant) => Some(instant),
         };
         self.waker.borrow_mut().start_at(min_timeout(wait_timeout, app_timeout));
@@ -20,7 +20,7 @@ use objc2_foundation::{
 use tracing::{debug_span, trace_span};
 use winit_core::event::{
     DeviceEvent, ElementState, Ime, KeyEvent, Modifiers, MouseButton, MouseScrollDelta,
-    PointerKind, PointerSource, TouchPhase, WindowEvent,
+    PointerKind, PointerSource, ScrollSource, TouchPhase, WindowEvent,
 };
 use winit_core::keyboard::{Key, KeyCode, KeyLocation, ModifiersState, NamedKey};
 use winit_core::window::ImeCapabilities;
@@ -312,7 +312,7 @@ define_class!(
             };
 
             // Send WindowEvent for updating marked text
-            self.queue_event(WindowEvent::Ime(Ime::Preedit(string, cursor_range)));
+            self.queue_event(WindowEvent::Ime(Ime::Preedit(string, cursor_range, Vec::new())));
         }
 
         #[unsafe(method(unmarkText))]
@@ -323,7 +323,7 @@ define_class!(
             let input_context = self.inputContext().expect("input context");
             input_context.discardMarkedText();
 
-            self.queue_event(WindowEvent::Ime(Ime::Preedit(String::new(), None)));
+            self.queue_event(WindowEvent::Ime(Ime::Preedit(String::new(), None, Vec::new())));
             if self.is_ime_enabled() {
                 // Leave the Preedit self.ivars()
                 self.ivars().ime_state.set(ImeState::Ground);
@@ -392,7 +392,7 @@ define_class!(
 
             // Commit only if we have marked text.
             if self.hasMarkedText() && self.is_ime_enabled() && !is_control {
-                self.queue_event(WindowEvent::Ime(Ime::Preedit(String::new(), None)));
+                self.queue_event(WindowEvent::Ime(Ime::Preedit(String::new(), None, Vec::new())));
                 self.queue_event(WindowEvent::Ime(Ime::Commit(string)));
                 self.ivars().ime_state.set(ImeState::Committed);
             }
@@ -494,6 +494,7 @@ define_class!(
                     device_id: None,
                     event: key_event,
                     is_synthetic: false,
+                    timestamp: None,
                 });
             }
         }
@@ -511,6 +512,7 @@ define_class!(
                     device_id: None,
                     event: create_key_event(&event, false, false),
                     is_synthetic: false,
+                    timestamp: None,
                 });
             }
         }
@@ -562,6 +564,7 @@ define_class!(
                 device_id: None,
                 event,
                 is_synthetic: false,
+                timestamp: None,
             });
         }
 
@@ -706,7 +709,15 @@ define_class!(
             self.ivars().app_state.maybe_queue_with_handler(move |app, event_loop| {
                 app.device_event(event_loop, None, DeviceEvent::MouseWheel { delta })
             });
-            self.queue_event(WindowEvent::MouseWheel { device_id: None, delta, phase });
+            self.queue_event(WindowEvent::MouseWheel {
+                device_id: None,
+                delta,
+                phase,
+                timestamp: None,
+                source: ScrollSource::Unknown,
+                value120: None,
+                stopped: false,
+            });
         }
 
         #[unsafe(method(magnifyWithEvent:))]
@@ -728,6 +739,7 @@ define_class!(
                 device_id: None,
                 delta: event.magnification(),
                 phase,
+                timestamp: None,
             });
         }
 
@@ -759,6 +771,7 @@ define_class!(
                 device_id: None,
                 delta: event.rotation(),
                 phase,
+                timestamp: None,
             });
         }
 
@@ -1079,6 +1092,7 @@ impl WinitView {
                             device_id: None,
                             event,
                             is_synthetic: false,
+                            timestamp: None,
                         });
                     }
                     if phys_mod.contains(ModLocationMask::RIGHT) {
@@ -1088,6 +1102,7 @@ impl WinitView {
                             device_id: None,
                             event,
                             is_synthetic: false,
+                            timestamp: None,
                         });
                     }
                     *phys_mod = ModLocationMask::empty();
@@ -1119,6 +1134,7 @@ impl WinitView {
                         device_id: None,
                         event,
                         is_synthetic: false,
+                        timestamp: None,
                     });
                 }
 
@@ -1183,6 +1199,8 @@ impl WinitView {
             position,
             button: button.into(),
             is_macos_activation_click,
+            timestamp: None,
+            click_count: 1,
         });
     }
 
@@ -1209,6 +1227,8 @@ impl WinitView {
             primary: true,
             position: view_point.to_physical(self.scale_factor()),
             source: PointerSource::Mouse,
+            timestamp: None,
+            history: Vec::new(),
         });
     }
 
@@ -0,0 +1,219 @@
+//! The timers and the file descriptors of the application, watched by a [`calloop`] event loop.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::os::fd::BorrowedFd;
+use std::rc::Rc;
+use std::time::Duration;
+
+use calloop::generic::Generic;
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{Interest, LoopHandle, Mode, PostAction, RegistrationToken};
+use winit_core::error::{NotSupportedError, OsError, RequestError};
+use winit_core::event_loop::{FdReadiness, FdRegistration, FdSourceId, TimerId, TimerSchedule};
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// The timers, with whether they repeat.
+    timers: HashMap<TimerId, (RegistrationToken, bool)>,
+    /// The timers which fired since they were last taken.
+    fired_timers: VecDeque<TimerId>,
+    /// The readiness of the file descriptors since it was last taken.
+    ready_fds: VecDeque<(FdSourceId, FdReadiness)>,
+}
+
+/// The timers and the file descriptors of the application.
+///
+/// The sources are dispatched by the event loop, which then reports them to the application with
+/// [`Self::take_fired_timer()`] and [`Self::take_ready_fd()`]. The registrations may be changed
+/// meanwhile.
+pub struct AppSources<Data: 'static> {
+    handle: LoopHandle<'static, Data>,
+    /// Called with the data of the event loop when a source is dispatched.
+    on_dispatch: fn(&mut Data),
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl<Data: 'static> AppSources<Data> {
+    pub fn new(handle: LoopHandle<'static, Data>, on_dispatch: fn(&mut Data)) -> Self {
+        Self { handle, on_dispatch, inner: Default::default() }
+    }
+
+    /// Add a timer. A zero interval would fire on every iteration of the event loop, it is
+    /// rejected.
+    pub fn add_timer(&self, schedule: TimerSchedule) -> Result<TimerId, RequestError> {
+        let timer_id = TimerId::new_unique();
+        let (timer, interval) = match schedule {
+            TimerSchedule::Deadline(deadline) => (Timer::from_deadline(deadline), None),
+            TimerSchedule::Interval(Duration::ZERO) => {
+                return Err(NotSupportedError::new("timers with a zero interval").into());
+            },
+            TimerSchedule::Interval(interval) => (Timer::from_duration(interval), Some(interval)),
+        };
+
+        let inner = Rc::clone(&self.inner);
+        let on_dispatch = self.on_dispatch;
+        let token = self
+            .handle
+            .insert_source(timer, move |_, _, data| {
+                inner.borrow_mut().fired_timers.push_back(timer_id);
+                on_dispatch(data);
+                interval.map_or(TimeoutAction::Drop, TimeoutAction::ToDuration)
+            })
+            .map_err(|err| OsError::new(line!(), file!(), err.error))?;
+        self.inner.borrow_mut().timers.insert(timer_id, (token, interval.is_some()));
+        Ok(timer_id)
+    }
+
+    pub fn cancel_timer(&self, timer_id: TimerId) {
+        let mut inner = self.inner.borrow_mut();
+        if let Some((token, _)) = inner.timers.remove(&timer_id) {
+            inner.fired_timers.retain(|&fired| fired != timer_id);
+            self.handle.remove(token);
+        }
+    }
+
+    /// Watch a file descriptor of the application.
+    pub fn register_fd(
+        &self,
+        fd: BorrowedFd<'_>,
+        interest: FdReadiness,
+    ) -> Result<FdRegistration, RequestError> {
+        let fd = fd.try_clone_to_owned().map_err(|err| OsError::new(line!(), file!(), err))?;
+        let source_id = FdSourceId::new_unique();
+        let interest = Interest {
+            readable: interest.contains(FdReadiness::READABLE),
+            writable: interest.contains(FdReadiness::WRITABLE),
+        };
+
+        let inner = Rc::clone(&self.inner);
+        let on_dispatch = self.on_dispatch;
+        let source = Generic::new(fd, interest, Mode::Level);
+        let token = self
+            .handle
+            .insert_source(source, move |readiness, _, data| {
+                let mut fd_readiness = FdReadiness::empty();
+                fd_readiness.set(FdReadiness::READABLE, readiness.readable);
+                fd_readiness.set(FdReadiness::WRITABLE, readiness.writable);
+                fd_readiness.set(FdReadiness::ERROR, readiness.error);
+                inner.borrow_mut().ready_fds.push_back((source_id, fd_readiness));
+                on_dispatch(data);
+                Ok(PostAction::Continue)
+            })
+            .map_err(|err| OsError::new(line!(), file!(), err.error))?;
+
+        let handle = self.handle.clone();
+        let inner = Rc::clone(&self.inner);
+        Ok(FdRegistration::new(source_id, move || {
+            handle.remove(token);
+            inner.borrow_mut().ready_fds.retain(|&(ready_id, _)| ready_id != source_id);
+        }))
+    }
+
+    /// Whether timers fired or file descriptors became ready since they were last taken.
+    pub fn has_pending(&self) -> bool {
+        let inner = self.inner.borrow();
+        !inner.fired_timers.is_empty() || !inner.ready_fds.is_empty()
+    }
+
+    /// Take the next fired timer, forgetting it when it won't fire anymore.
+    pub fn take_fired_timer(&self) -> Option<TimerId> {
+        let mut inner = self.inner.borrow_mut();
+        let timer_id = inner.fired_timers.pop_front()?;
+        if let Some(&(_, false)) = inner.timers.get(&timer_id) {
+            inner.timers.remove(&timer_id);
+        }
+        Some(timer_id)
+    }
+
+    /// Take the next readiness of a file descriptor.
+    pub fn take_ready_fd(&self) -> Option<(FdSourceId, FdReadiness)> {
+        self.inner.borrow_mut().ready_fds.pop_front()
+    }
+}
+
+impl<Data: 'static> std::fmt::Debug for AppSources<Data> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppSources").field("inner", &self.inner).finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::os::fd::AsFd;
+    use std::os::unix::net::UnixStream;
+    use std::time::Instant;
+
+    use calloop::EventLoop;
+
+    use super::*;
+
+    fn dispatch(event_loop: &mut EventLoop<'static, usize>, dispatched: &mut usize) {
+        event_loop.dispatch(Some(Duration::from_millis(50)), dispatched).unwrap();
+    }
+
+    #[test]
+    fn timers() {
+        let mut event_loop = EventLoop::<usize>::try_new().unwrap();
+        let sources = AppSources::new(event_loop.handle(), |dispatched| *dispatched += 1);
+        let mut dispatched = 0;
+
+        assert!(matches!(
+            sources.add_timer(TimerSchedule::Interval(Duration::ZERO)),
+            Err(RequestError::NotSupported(_))
+        ));
+
+        let once = sources.add_timer(TimerSchedule::Deadline(Instant::now())).unwrap();
+        let repeating =
+            sources.add_timer(TimerSchedule::Interval(Duration::from_millis(1))).unwrap();
+        let cancelled = sources.add_timer(TimerSchedule::Deadline(Instant::now())).unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        dispatch(&mut event_loop, &mut dispatched);
+        assert_eq!(dispatched, 3);
+
+        sources.cancel_timer(cancelled);
+        let mut fired = Vec::new();
+        while let Some(timer_id) = sources.take_fired_timer() {
+            fired.push(timer_id);
+        }
+        fired.sort_by_key(|&timer_id| timer_id.into_raw());
+        assert_eq!(fired, [once, repeating]);
+        assert!(!sources.has_pending());
+
+        // Only the interval timer fires again.
+        std::thread::sleep(Duration::from_millis(2));
+        dispatch(&mut event_loop, &mut dispatched);
+        assert_eq!(sources.take_fired_timer(), Some(repeating));
+        assert_eq!(sources.take_fired_timer(), None);
+
+        sources.cancel_timer(repeating);
+        dispatch(&mut event_loop, &mut dispatched);
+        assert!(!sources.has_pending());
+    }
+
+    #[test]
+    fn file_descriptors() {
+        let mut event_loop = EventLoop::<usize>::try_new().unwrap();
+        let sources = AppSources::new(event_loop.handle(), |dispatched| *dispatched += 1);
+        let mut dispatched = 0;
+
+        let (mut writer, reader) = UnixStream::pair().unwrap();
+        let registration = sources.register_fd(reader.as_fd(), FdReadiness::READABLE).unwrap();
+        dispatch(&mut event_loop, &mut dispatched);
+        assert!(!sources.has_pending());
+
+        writer.write_all(b"x").unwrap();
+        dispatch(&mut event_loop, &mut dispatched);
+        assert_eq!(dispatched, 1);
+        assert_eq!(sources.take_ready_fd(), Some((registration.id(), FdReadiness::READABLE)));
+
+        // Dropping the registration forgets its readiness.
+        dispatch(&mut event_loop, &mut dispatched);
+        assert!(sources.has_pending());
+        drop(registration);
+        assert!(!sources.has_pending());
+        dispatch(&mut event_loop, &mut dispatched);
+        assert!(!sources.has_pending());
+    }
+}
@@ -0,0 +1,212 @@
+//! Counting of the successive clicks reported with [`WindowEvent::PointerButton`].
+
+use std::time::{Duration, Instant};
+
+use dpi::PhysicalPosition;
+use winit_core::event::{ButtonSource, ElementState, PointerSource, WindowEvent};
+use winit_core::event_loop::SystemSettings;
+use winit_core::window::WindowId;
+
+/// The double click time used when the system doesn't provide one.
+pub const DEFAULT_DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
+/// The double click distance used when the system doesn't provide one.
+pub const DEFAULT_DOUBLE_CLICK_DISTANCE: u32 = 5;
+
+#[derive(Debug)]
+struct Click {
+    window_id: WindowId,
+    button: ButtonSource,
+    position: PhysicalPosition<f64>,
+    time: Instant,
+    /// The double click distance when the button was pressed.
+    distance: f64,
+    count: u32,
+    /// Whether the pointer travelled further than the double click distance since the press.
+    travelled: bool,
+}
+
+/// Computes the `click_count` of the button events from the double click settings.
+#[derive(Debug, Default)]
+pub struct ClickCounter {
+    last: Option<Click>,
+}
+
+impl ClickCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `click_count` of a [`WindowEvent::PointerButton`], other events being ignored.
+    pub fn button(
+        &mut self,
+        window_id: WindowId,
+        event: &mut WindowEvent,
+        settings: &SystemSettings,
+    ) {
+        let WindowEvent::PointerButton { state, position, button, timestamp, click_count, .. } =
+            event
+        else {
+            return;
+        };
+
+        let last = self
+            .last
+            .as_ref()
+            .filter(|last| last.window_id == window_id && same_button(&last.button, button));
+
+        if *state == ElementState::Released {
+            *click_count = last.map_or(1, |last| last.count);
+            return;
+        }
+
+        let time = timestamp.map_or_else(Instant::now, |timestamp| timestamp.to_instant());
+        let double_click_time = settings.double_click_time.unwrap_or(DEFAULT_DOUBLE_CLICK_TIME);
+        let distance =
+            settings.double_click_distance.unwrap_or(DEFAULT_DOUBLE_CLICK_DISTANCE) as f64;
+
+        *click_count = match last {
+            Some(last)
+                if !last.travelled
+                    && time.saturating_duration_since(last.time) <= double_click_time
+                    && travel(last.position, *position) <= distance =>
+            {
+                last.count.saturating_add(1)
+            },
+            _ => 1,
+        };
+
+        self.last = Some(Click {
+            window_id,
+            button: button.clone(),
+            position: *position,
+            time,
+            distance,
+            count: *click_count,
+            travelled: false,
+        });
+    }
+
+    /// Stop counting the clicks when the pointer of the last one travelled too far away with a
+    /// [`WindowEvent::PointerMoved`], other events being ignored.
+    pub fn moved(&mut self, window_id: WindowId, event: &WindowEvent) {
+        let WindowEvent::PointerMoved { position, source, .. } = event else {
+            return;
+        };
+
+        if let Some(last) = &mut self.last {
+            if last.window_id == window_id
+                && same_pointer(&last.button, source)
+                && travel(last.position, *position) > last.distance
+            {
+                last.travelled = true;
+            }
+        }
+    }
+}
+
+fn travel(a: PhysicalPosition<f64>, b: PhysicalPosition<f64>) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+/// Whether the buttons are the same, any finger touching counting as the same button.
+fn same_button(a: &ButtonSource, b: &ButtonSource) -> bool {
+    match (a, b) {
+        (ButtonSource::Mouse(a), ButtonSource::Mouse(b)) => a == b,
+        (ButtonSource::Touch { .. }, ButtonSource::Touch { .. }) => true,
+        (
+            ButtonSource::TabletTool { kind: a_kind, button: a_button, .. },
+            ButtonSource::TabletTool { kind: b_kind, button: b_button, .. },
+        ) => a_kind == b_kind && a_button == b_button,
+        (ButtonSource::Unknown(a), ButtonSource::Unknown(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Whether the pointer is the one which pressed the button.
+fn same_pointer(button: &ButtonSource, source: &PointerSource) -> bool {
+    match (button, source) {
+        (ButtonSource::Mouse(_) | ButtonSource::Unknown(_), PointerSource::Mouse) => true,
+        (ButtonSource::Touch { finger_id: a, .. }, PointerSource::Touch { finger_id: b, .. }) => {
+            a == b
+        },
+        (ButtonSource::TabletTool { kind: a, .. }, PointerSource::TabletTool { kind: b, .. }) => {
+            a == b
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit_core::event::{EventTimestamp, MouseButton};
+
+    use super::*;
+
+    fn button(state: ElementState, button: MouseButton, x: f64, millis: u64) -> WindowEvent {
+        // Place the events within the last ten seconds.
+        let time = Duration::from_millis(millis);
+        let age = Duration::from_secs(10) - time;
+        WindowEvent::PointerButton {
+            device_id: None,
+            state,
+            position: (x, 0.).into(),
+            primary: true,
+            button: button.into(),
+            is_macos_activation_click: false,
+            timestamp: Some(EventTimestamp::new(time, age)),
+            click_count: 0,
+        }
+    }
+
+    fn moved(x: f64) -> WindowEvent {
+        WindowEvent::PointerMoved {
+            device_id: None,
+            position: (x, 0.).into(),
+            primary: true,
+            source: PointerSource::Mouse,
+            timestamp: None,
+            history: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn count_clicks() {
+        let window = WindowId::from_raw(1);
+        let settings = SystemSettings {
+            double_click_time: Some(Duration::from_millis(300)),
+            double_click_distance: Some(4),
+            ..Default::default()
+        };
+        let mut counter = ClickCounter::new();
+        let click = |counter: &mut ClickCounter, state, mouse_button, x, millis| {
+            let mut event = button(state, mouse_button, x, millis);
+            counter.button(window, &mut event, &settings);
+            match event {
+                WindowEvent::PointerButton { click_count, .. } => click_count,
+                _ => unreachable!(),
+            }
+        };
+
+        let pressed = ElementState::Pressed;
+        let released = ElementState::Released;
+        assert_eq!(click(&mut counter, pressed, MouseButton::Left, 0., 1000), 1);
+        assert_eq!(click(&mut counter, released, MouseButton::Left, 0., 1050), 1);
+        assert_eq!(click(&mut counter, pressed, MouseButton::Left, 2., 1200), 2);
+        assert_eq!(click(&mut counter, released, MouseButton::Left, 2., 1250), 2);
+        assert_eq!(click(&mut counter, pressed, MouseButton::Left, 3., 1400), 3);
+        // Too late.
+        assert_eq!(click(&mut counter, pressed, MouseButton::Left, 3., 1800), 1);
+        // Too far.
+        assert_eq!(click(&mut counter, pressed, MouseButton::Left, 10., 1900), 1);
+        // Another button.
+        assert_eq!(click(&mut counter, pressed, MouseButton::Right, 10., 2000), 1);
+        assert_eq!(click(&mut counter, released, MouseButton::Left, 10., 2010), 1);
+        assert_eq!(click(&mut counter, pressed, MouseButton::Right, 10., 2100), 2);
+
+        // The pointer travelled away and came back.
+        counter.moved(window, &moved(20.));
+        counter.moved(window, &moved(10.));
+        assert_eq!(click(&mut counter, pressed, MouseButton::Right, 10., 2200), 1);
+    }
+}
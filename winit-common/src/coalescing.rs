@@ -0,0 +1,353 @@
+//! Merging of the pointer events, see [`ActiveEventLoop::set_pointer_coalescing()`].
+
+use std::mem;
+
+use winit_core::application::ApplicationHandler;
+use winit_core::event::{
+    DeviceEvent, DeviceId, MouseScrollDelta, PointerSample, PointerSource, StartCause, TouchPhase,
+    WindowEvent,
+};
+use winit_core::event_loop::{
+    ActiveEventLoop, FdReadiness, FdSourceId, PointerCoalescing, TimerId,
+};
+use winit_core::window::WindowId;
+
+/// The pointer events waiting for a later one to be merged with.
+#[derive(Debug, Default)]
+pub struct PointerCoalescer {
+    pending: Vec<(WindowId, WindowEvent)>,
+}
+
+impl PointerCoalescer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the event to merge it with the next ones of the same pointer, giving it back when it
+    /// must be delivered right away.
+    pub fn push(
+        &mut self,
+        mode: PointerCoalescing,
+        window_id: WindowId,
+        mut event: WindowEvent,
+    ) -> Option<WindowEvent> {
+        match (mode, &mut event) {
+            (PointerCoalescing::Disabled, _) => return Some(event),
+            (PointerCoalescing::Latest, WindowEvent::PointerMoved { history, .. }) => {
+                history.clear()
+            },
+            (PointerCoalescing::History, WindowEvent::PointerMoved { .. }) => (),
+            (_, WindowEvent::MouseWheel { phase: TouchPhase::Moved, stopped: false, .. }) => (),
+            _ => return Some(event),
+        }
+
+        // Only merge with the latest pending event of the pointer, to keep its events in order.
+        let latest = self.pending.iter().rposition(|(pending_id, pending)| {
+            *pending_id == window_id && same_pointer(pending, &event)
+        });
+        if let Some(index) = latest.filter(|&index| mergeable(&self.pending[index].1, &event)) {
+            let (_, previous) = self.pending.remove(index);
+            merge(mode, previous, &mut event);
+        }
+
+        self.pending.push((window_id, event));
+        None
+    }
+
+    /// Take the pending events, in the order of their latest sample.
+    pub fn drain(&mut self) -> impl Iterator<Item = (WindowId, WindowEvent)> + '_ {
+        self.pending.drain(..)
+    }
+}
+
+/// Whether the events come from the same pointer, scrolling being done by the mouse.
+fn same_pointer(a: &WindowEvent, b: &WindowEvent) -> bool {
+    fn pointer(event: &WindowEvent) -> (Option<DeviceId>, Option<&PointerSource>) {
+        match event {
+            WindowEvent::PointerMoved { device_id, source, .. } => (*device_id, Some(source)),
+            WindowEvent::MouseWheel { device_id, .. } => (*device_id, None),
+            _ => unreachable!("coalescing events without a pointer"),
+        }
+    }
+
+    let ((a_device, a), (b_device, b)) = (pointer(a), pointer(b));
+    a_device == b_device
+        && match (a, b) {
+            (Some(a), Some(b)) => same_source(a, b),
+            (Some(source), None) | (None, Some(source)) => *source == PointerSource::Mouse,
+            (None, None) => true,
+        }
+}
+
+fn same_source(a: &PointerSource, b: &PointerSource) -> bool {
+    match (a, b) {
+        (PointerSource::Mouse, PointerSource::Mouse) => true,
+        (PointerSource::Touch { finger_id: a, .. }, PointerSource::Touch { finger_id: b, .. }) => {
+            a == b
+        },
+        (PointerSource::TabletTool { kind: a, .. }, PointerSource::TabletTool { kind: b, .. }) => {
+            a == b
+        },
+        (PointerSource::Unknown, PointerSource::Unknown) => true,
+        _ => false,
+    }
+}
+
+/// Whether the new event of a pointer can be merged with its previous one.
+fn mergeable(previous: &WindowEvent, event: &WindowEvent) -> bool {
+    match (previous, event) {
+        (WindowEvent::PointerMoved { .. }, WindowEvent::PointerMoved { .. }) => true,
+        (
+            WindowEvent::MouseWheel { delta: previous_delta, source: previous_source, .. },
+            WindowEvent::MouseWheel { delta, source, .. },
+        ) => {
+            previous_source == source
+                && mem::discriminant(previous_delta) == mem::discriminant(delta)
+        },
+        _ => false,
+    }
+}
+
+/// Merge the previous event of the same pointer into the new one.
+fn merge(mode: PointerCoalescing, previous: WindowEvent, event: &mut WindowEvent) {
+    match (previous, event) {
+        (
+            WindowEvent::PointerMoved { position, source, timestamp, history: mut merged, .. },
+            WindowEvent::PointerMoved { history, .. },
+        ) => {
+            if mode == PointerCoalescing::History {
+                merged.push(PointerSample { position, source, timestamp });
+                merged.append(history);
+                *history = merged;
+            }
+        },
+        (
+            WindowEvent::MouseWheel { delta: previous, value120: previous_value120, .. },
+            WindowEvent::MouseWheel { delta, value120, .. },
+        ) => {
+            match (previous, delta) {
+                (
+                    MouseScrollDelta::LineDelta(previous_x, previous_y),
+                    MouseScrollDelta::LineDelta(x, y),
+                ) => {
+                    *x += previous_x;
+                    *y += previous_y;
+                },
+                (MouseScrollDelta::PixelDelta(previous), MouseScrollDelta::PixelDelta(delta)) => {
+                    delta.x += previous.x;
+                    delta.y += previous.y;
+                },
+                _ => unreachable!("merging scroll deltas of different kinds"),
+            }
+
+            if let (Some((previous_x, previous_y)), Some((x, y))) = (previous_value120, value120) {
+                *x += previous_x;
+                *y += previous_y;
+            }
+        },
+        _ => unreachable!("merging events of different pointers"),
+    }
+}
+
+/// An [`ApplicationHandler`] coalescing the pointer events it receives before forwarding them.
+///
+/// The pending events are delivered before any other event, and by [`flush()`], which the event
+/// loop calls once it has dispatched the events of the windowing system.
+///
+/// [`flush()`]: Self::flush
+#[derive(Debug)]
+pub struct CoalescingHandler<A> {
+    mode: PointerCoalescing,
+    coalescer: PointerCoalescer,
+    app: A,
+}
+
+impl<A: ApplicationHandler> CoalescingHandler<A> {
+    pub fn new(mode: PointerCoalescing, app: A) -> Self {
+        Self { mode, coalescer: PointerCoalescer::new(), app }
+    }
+
+    /// Deliver the pending pointer events.
+    pub fn flush(&mut self, event_loop: &dyn ActiveEventLoop) {
+        for (window_id, event) in self.coalescer.drain() {
+            self.app.window_event(event_loop, window_id, event);
+        }
+    }
+}
+
+#[deny(clippy::missing_trait_methods)]
+impl<A: ApplicationHandler> ApplicationHandler for CoalescingHandler<A> {
+    fn new_events(&mut self, event_loop: &dyn ActiveEventLoop, cause: StartCause) {
+        self.flush(event_loop);
+        self.app.new_events(event_loop, cause);
+    }
+
+    fn resumed(&mut self, event_loop: &dyn ActiveEventLoop) {
+        self.flush(event_loop);
+        self.app.resumed(event_loop);
+    }
+
+    fn can_create_surfaces(&mut self, event_loop: &dyn ActiveEventLoop) {
+        self.flush(event_loop);
+        self.app.can_create_surfaces(event_loop);
+    }
+
+    fn proxy_wake_up(&mut self, event_loop: &dyn ActiveEventLoop) {
+        self.flush(event_loop);
+        self.app.proxy_wake_up(event_loop);
+    }
+
+    fn timer_fired(&mut self, event_loop: &dyn ActiveEventLoop, timer_id: TimerId) {
+        self.flush(event_loop);
+        self.app.timer_fired(event_loop, timer_id);
+    }
+
+    fn fd_ready(
+        &mut self,
+        event_loop: &dyn ActiveEventLoop,
+        source_id: FdSourceId,
+        readiness: FdReadiness,
+    ) {
+        self.flush(event_loop);
+        self.app.fd_ready(event_loop, source_id, readiness);
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &dyn ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if let Some(event) = self.coalescer.push(self.mode, window_id, event) {
+            self.flush(event_loop);
+            self.app.window_event(event_loop, window_id, event);
+        }
+    }
+
+    fn device_event(
+        &mut self,
+        event_loop: &dyn ActiveEventLoop,
+        device_id: Option<DeviceId>,
+        event: DeviceEvent,
+    ) {
+        // Raw device events interleave with the pointer events without being ordered with them.
+        self.app.device_event(event_loop, device_id, event);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &dyn ActiveEventLoop) {
+        self.flush(event_loop);
+        self.app.about_to_wait(event_loop);
+    }
+
+    fn suspended(&mut self, event_loop: &dyn ActiveEventLoop) {
+        self.flush(event_loop);
+        self.app.suspended(event_loop);
+    }
+
+    fn destroy_surfaces(&mut self, event_loop: &dyn ActiveEventLoop) {
+        self.flush(event_loop);
+        self.app.destroy_surfaces(event_loop);
+    }
+
+    fn memory_warning(&mut self, event_loop: &dyn ActiveEventLoop) {
+        self.flush(event_loop);
+        self.app.memory_warning(event_loop);
+    }
+
+    fn system_settings_changed(&mut self, event_loop: &dyn ActiveEventLoop) {
+        self.flush(event_loop);
+        self.app.system_settings_changed(event_loop);
+    }
+
+    fn connection_lost(&mut self, event_loop: &dyn ActiveEventLoop) {
+        self.flush(event_loop);
+        self.app.connection_lost(event_loop);
+    }
+
+    fn macos_handler(
+        &mut self,
+    ) -> Option<&mut dyn winit_core::application::macos::ApplicationHandlerExtMacOS> {
+        self.app.macos_handler()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit_core::event::ScrollSource;
+    use winit_core::keyboard::ModifiersState;
+
+    use super::*;
+
+    fn moved(x: f64) -> WindowEvent {
+        WindowEvent::PointerMoved {
+            device_id: None,
+            position: (x, 0.).into(),
+            primary: true,
+            source: PointerSource::Mouse,
+            timestamp: None,
+            history: Vec::new(),
+        }
+    }
+
+    fn wheel(y: f32) -> WindowEvent {
+        WindowEvent::MouseWheel {
+            device_id: None,
+            delta: MouseScrollDelta::LineDelta(0., y),
+            phase: TouchPhase::Moved,
+            timestamp: None,
+            source: ScrollSource::Wheel,
+            value120: Some((0, y as i32 * 120)),
+            stopped: false,
+        }
+    }
+
+    #[test]
+    fn coalesce_pointer_events() {
+        let window = WindowId::from_raw(1);
+        let other = WindowId::from_raw(2);
+        let moved_after = |x: f64, history: &[f64]| {
+            let mut event = moved(x);
+            if let WindowEvent::PointerMoved { history: samples, .. } = &mut event {
+                *samples = history
+                    .iter()
+                    .map(|&x| PointerSample {
+                        position: (x, 0.).into(),
+                        source: PointerSource::Mouse,
+                        timestamp: None,
+                    })
+                    .collect();
+            }
+            event
+        };
+
+        let mut coalescer = PointerCoalescer::new();
+        assert_eq!(coalescer.push(PointerCoalescing::Disabled, window, moved(1.)), Some(moved(1.)));
+
+        let mode = PointerCoalescing::History;
+        assert_eq!(coalescer.push(mode, window, moved(1.)), None);
+        assert_eq!(coalescer.push(mode, other, moved(5.)), None);
+        assert_eq!(coalescer.push(mode, window, moved(2.)), None);
+        assert_eq!(coalescer.push(mode, window, wheel(1.)), None);
+        assert_eq!(coalescer.push(mode, window, wheel(2.)), None);
+        // The motions on both sides of the scrolling aren't merged, to keep them in order.
+        assert_eq!(coalescer.push(mode, window, moved_after(3., &[2.5])), None);
+        assert_eq!(coalescer.push(mode, window, moved(4.)), None);
+        let modifiers = WindowEvent::ModifiersChanged(ModifiersState::SHIFT.into());
+        assert_eq!(coalescer.push(mode, window, modifiers.clone()), Some(modifiers));
+
+        let pending: Vec<_> = coalescer.drain().collect();
+        assert_eq!(pending, [
+            (other, moved(5.)),
+            (window, moved_after(2., &[1.])),
+            (window, wheel(3.)),
+            (window, moved_after(4., &[2.5, 3.])),
+        ]);
+
+        // Only the latest position is kept, without the samples reported with it.
+        let mode = PointerCoalescing::Latest;
+        assert_eq!(coalescer.push(mode, window, moved_after(1., &[0.5])), None);
+        assert_eq!(coalescer.push(mode, window, moved_after(2., &[1.5])), None);
+        let pending: Vec<_> = coalescer.drain().collect();
+        assert_eq!(pending, [(window, moved(2.))]);
+    }
+}
@@ -0,0 +1,445 @@
+//! A connection to a message bus.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, BorrowedFd};
+use std::os::unix::net::UnixStream;
+use std::time::{Duration, Instant};
+
+use super::Error;
+use super::message::{Message, MessageType, NO_REPLY_EXPECTED, Value};
+
+const BUS_NAME: &str = "org.freedesktop.DBus";
+const BUS_PATH: &str = "/org/freedesktop/DBus";
+const BUS_INTERFACE: &str = "org.freedesktop.DBus";
+
+/// A connection to a message bus.
+///
+/// Setting the connection up doesn't wait for the bus, the authentication and the registration
+/// being pipelined with the first messages. [`Connection::call()`] blocks until its reply arrives;
+/// everything else received in the meantime is queued and handed out by
+/// [`Connection::try_recv()`].
+#[derive(Debug)]
+pub struct Connection {
+    stream: UnixStream,
+    read_buf: Vec<u8>,
+    queue: VecDeque<Message>,
+    next_serial: u32,
+    /// Whether the bus accepted the authentication.
+    authenticated: bool,
+    /// Serial of the pending `Hello` call.
+    hello_serial: Option<u32>,
+    unique_name: String,
+}
+
+impl Connection {
+    /// Connect to the session bus.
+    ///
+    /// Uses `DBUS_SESSION_BUS_ADDRESS`, falling back to `$XDG_RUNTIME_DIR/bus`.
+    pub fn session() -> Result<Self, Error> {
+        match std::env::var("DBUS_SESSION_BUS_ADDRESS") {
+            Ok(address) => Self::open(&address),
+            Err(_) => {
+                let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+                    .map_err(|_| Error::Address(String::from("no session bus address")))?;
+                Self::open(&format!("unix:path={runtime_dir}/bus"))
+            },
+        }
+    }
+
+    /// Connect to the bus at the given address, then authenticate and register on the bus
+    /// without waiting for its answers.
+    ///
+    /// A refused authentication is reported when reading the first message.
+    pub fn open(address: &str) -> Result<Self, Error> {
+        let stream = connect(address)?;
+        let mut connection = Self {
+            stream,
+            read_buf: Vec::new(),
+            queue: VecDeque::new(),
+            next_serial: 1,
+            authenticated: false,
+            hello_serial: None,
+            unique_name: String::new(),
+        };
+
+        // Run the `EXTERNAL` authentication, letting the bus use the credentials of the socket.
+        connection.stream.write_all(b"\0AUTH EXTERNAL\r\nDATA\r\nBEGIN\r\n")?;
+
+        let hello = Message::method_call(BUS_NAME, BUS_PATH, BUS_INTERFACE, "Hello");
+        connection.hello_serial = Some(connection.send(&hello)?);
+
+        Ok(connection)
+    }
+
+    /// The unique name assigned by the bus, empty until the bus answered.
+    pub fn unique_name(&self) -> &str {
+        &self.unique_name
+    }
+
+    /// Send a message, returning its serial.
+    pub fn send(&mut self, message: &Message) -> Result<u32, Error> {
+        let serial = self.next_serial;
+        self.next_serial = self.next_serial.checked_add(1).unwrap_or(1);
+        self.stream.write_all(&message.encode(serial))?;
+        Ok(serial)
+    }
+
+    /// Call a method and wait for its reply.
+    ///
+    /// Error replies are returned as [`Error::Method`].
+    pub fn call(&mut self, message: &Message, timeout: Duration) -> Result<Message, Error> {
+        let serial = self.send(message)?;
+        let deadline = Instant::now() + timeout;
+
+        let mut queue = VecDeque::new();
+        let result = loop {
+            let message = match self.read_message(Some(deadline)) {
+                Ok(message) => message,
+                Err(err) => break Err(err),
+            };
+
+            if message.reply_serial != Some(serial) {
+                queue.push_back(message);
+                continue;
+            }
+
+            break match message.message_type {
+                MessageType::Error => Err(method_error(&message)),
+                _ => Ok(message),
+            };
+        };
+
+        self.queue.append(&mut queue);
+        result
+    }
+
+    /// Subscribe to the messages matching the rule, without waiting for the bus.
+    pub fn add_match(&mut self, rule: &str) -> Result<(), Error> {
+        let mut message = Message::method_call(BUS_NAME, BUS_PATH, BUS_INTERFACE, "AddMatch")
+            .with_body(vec![Value::String(rule.to_owned())]);
+        message.flags |= NO_REPLY_EXPECTED;
+        self.send(&message)?;
+        Ok(())
+    }
+
+    /// Get a message that was already received, without blocking.
+    ///
+    /// Returns [`Error::Disconnected`] once the bus closed the connection.
+    pub fn try_recv(&mut self) -> Result<Option<Message>, Error> {
+        if let Some(message) = self.queue.pop_front() {
+            return Ok(Some(message));
+        }
+
+        self.stream.set_nonblocking(true)?;
+        let result = self.read_message(None);
+        self.stream.set_nonblocking(false)?;
+
+        match result {
+            Ok(message) => Ok(Some(message)),
+            Err(Error::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Read the next message, waiting until the deadline, if any.
+    fn read_message(&mut self, deadline: Option<Instant>) -> Result<Message, Error> {
+        loop {
+            if let Some(message) = self.take_message()? {
+                if self.hello_serial.is_none() || message.reply_serial != self.hello_serial {
+                    return Ok(message);
+                }
+
+                self.hello_serial = None;
+                if message.message_type == MessageType::Error {
+                    return Err(method_error(&message));
+                }
+                self.unique_name =
+                    message.body.first().and_then(Value::as_str).unwrap_or_default().into();
+                continue;
+            }
+
+            if let Some(deadline) = deadline {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                if timeout.is_zero() {
+                    return Err(Error::Timeout);
+                }
+                self.stream.set_read_timeout(Some(timeout))?;
+            }
+
+            let mut chunk = [0u8; 4096];
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(Error::Disconnected),
+                Ok(len) => self.read_buf.extend_from_slice(&chunk[..len]),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err)
+                    if deadline.is_some()
+                        && matches!(
+                            err.kind(),
+                            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                        ) =>
+                {
+                    return Err(Error::Timeout);
+                },
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Take the next complete message out of the read buffer.
+    fn take_message(&mut self) -> Result<Option<Message>, Error> {
+        if !self.authenticated {
+            self.read_auth_lines()?;
+            if !self.authenticated {
+                return Ok(None);
+            }
+        }
+
+        match Message::encoded_len(&self.read_buf)? {
+            Some(len) if self.read_buf.len() >= len => {
+                let message = Message::decode(&self.read_buf[..len]);
+                self.read_buf.drain(..len);
+                message.map(Some)
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Consume the answers of the bus to the authentication received so far.
+    fn read_auth_lines(&mut self) -> Result<(), Error> {
+        while let Some(end) = self.read_buf.windows(2).position(|window| window == b"\r\n") {
+            let line = self.read_buf.drain(..end + 2).collect::<Vec<_>>();
+            match &line[..end] {
+                b"DATA" => (),
+                line if line.starts_with(b"OK ") => {
+                    self.authenticated = true;
+                    return Ok(());
+                },
+                _ => return Err(Error::Auth),
+            }
+        }
+
+        if self.read_buf.len() > 1024 {
+            return Err(Error::Auth);
+        }
+
+        Ok(())
+    }
+}
+
+impl AsFd for Connection {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.stream.as_fd()
+    }
+}
+
+pub(super) fn method_error(message: &Message) -> Error {
+    Error::Method {
+        name: message.error_name.clone().unwrap_or_default(),
+        message: message.body.first().and_then(Value::as_str).unwrap_or_default().to_owned(),
+    }
+}
+
+/// Connect to the first usable entry of a server address list.
+fn connect(addresses: &str) -> Result<UnixStream, Error> {
+    let mut last_error = Error::Address(addresses.to_owned());
+    for address in addresses.split(';').filter(|address| !address.is_empty()) {
+        let Some(params) = address.strip_prefix("unix:") else {
+            continue;
+        };
+
+        for (key, value) in params.split(',').filter_map(|param| param.split_once('=')) {
+            let value = unescape(value)?;
+            let stream = match key {
+                "path" => UnixStream::connect(&value),
+                #[cfg(target_os = "linux")]
+                "abstract" => {
+                    use std::os::linux::net::SocketAddrExt;
+                    std::os::unix::net::SocketAddr::from_abstract_name(value.as_bytes())
+                        .and_then(|address| UnixStream::connect_addr(&address))
+                },
+                _ => continue,
+            };
+
+            match stream {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_error = err.into(),
+            }
+        }
+    }
+
+    Err(last_error)
+}
+
+/// Undo the `%xx` escaping of address values.
+fn unescape(value: &str) -> Result<String, Error> {
+    let invalid = || Error::Address(value.to_owned());
+
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut iter = value.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next().ok_or_else(invalid)?, iter.next().ok_or_else(invalid)?];
+            let hex = std::str::from_utf8(&hex).map_err(|_| invalid())?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::os::unix::net::UnixListener;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread::{self, JoinHandle};
+
+    use super::*;
+
+    /// A minimal stand-in for a message bus, serving a single client.
+    ///
+    /// Every method call other than the bus' own is passed to `handler`, which returns the replies
+    /// and signals to send back.
+    pub(crate) struct MockBus {
+        pub(crate) address: String,
+        path: PathBuf,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl MockBus {
+        pub(crate) fn new(
+            mut handler: impl FnMut(&Message) -> Vec<Message> + Send + 'static,
+        ) -> Self {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let path = std::env::temp_dir().join(format!(
+                "winit-dbus-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            let listener = UnixListener::bind(&path).unwrap();
+
+            let thread = thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+
+                let mut auth = Vec::new();
+                let mut byte = 0;
+                while !auth.ends_with(b"BEGIN\r\n") {
+                    stream.read_exact(std::slice::from_mut(&mut byte)).unwrap();
+                    auth.push(byte);
+                    if auth.ends_with(b"AUTH EXTERNAL\r\n") {
+                        stream.write_all(b"DATA\r\n").unwrap();
+                    } else if auth.ends_with(b"\nDATA\r\n") {
+                        stream.write_all(b"OK 1234deadbeef\r\n").unwrap();
+                    }
+                }
+
+                let mut serial = 1000;
+                let mut buf = Vec::new();
+                loop {
+                    let mut chunk = [0u8; 4096];
+                    match stream.read(&mut chunk) {
+                        Ok(0) | Err(_) => return,
+                        Ok(len) => buf.extend_from_slice(&chunk[..len]),
+                    }
+
+                    while let Some(len) = Message::encoded_len(&buf).unwrap() {
+                        if buf.len() < len {
+                            break;
+                        }
+                        let mut call = Message::decode(&buf[..len]).unwrap();
+                        buf.drain(..len);
+                        call.sender = Some(String::from(":1.42"));
+
+                        let replies = if call.destination.as_deref() == Some(BUS_NAME) {
+                            let reply = Message::method_return(&call);
+                            match call.member.as_deref() {
+                                Some("Hello") => {
+                                    vec![reply.with_body(vec![Value::String(":1.42".into())])]
+                                },
+                                _ if call.flags & NO_REPLY_EXPECTED != 0 => Vec::new(),
+                                _ => vec![reply],
+                            }
+                        } else {
+                            handler(&call)
+                        };
+
+                        for reply in replies {
+                            serial += 1;
+                            if stream.write_all(&reply.encode(serial)).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+            });
+
+            Self { address: format!("unix:path={}", path.display()), path, thread: Some(thread) }
+        }
+    }
+
+    impl Drop for MockBus {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+            if let Some(thread) = self.thread.take() {
+                if !thread::panicking() {
+                    thread.join().unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn call_and_signals() {
+        let bus = MockBus::new(|call| match call.member.as_deref() {
+            Some("Echo") => vec![
+                Message::signal("/test", "org.example.Test", "Before"),
+                Message::method_return(call).with_body(call.body.clone()),
+            ],
+            _ => vec![Message::error(call, "org.example.Error.Unknown", "unknown method")],
+        });
+
+        let mut connection = Connection::open(&bus.address).unwrap();
+        assert_eq!(connection.unique_name(), "");
+
+        let echo = Message::method_call("org.example", "/test", "org.example.Test", "Echo")
+            .with_body(vec![Value::String("hello".into()), Value::UInt32(3)]);
+        let reply = connection.call(&echo, Duration::from_secs(5)).unwrap();
+        assert_eq!(reply.body, echo.body);
+        assert_eq!(connection.unique_name(), ":1.42");
+
+        // The signal received while waiting for the reply is kept.
+        let signal = connection.try_recv().unwrap().unwrap();
+        assert!(signal.is_signal("org.example.Test", "Before"));
+
+        let missing = Message::method_call("org.example", "/test", "org.example.Test", "Missing");
+        match connection.call(&missing, Duration::from_secs(5)) {
+            Err(Error::Method { name, message }) => {
+                assert_eq!(name, "org.example.Error.Unknown");
+                assert_eq!(message, "unknown method");
+            },
+            result => panic!("unexpected result: {result:?}"),
+        }
+
+        assert!(connection.try_recv().unwrap().is_none());
+    }
+
+    #[test]
+    fn call_timeout() {
+        let bus = MockBus::new(|_| Vec::new());
+        let mut connection = Connection::open(&bus.address).unwrap();
+
+        let call = Message::method_call("org.example", "/test", "org.example.Test", "Ignored");
+        assert!(matches!(connection.call(&call, Duration::from_millis(50)), Err(Error::Timeout)));
+    }
+
+    #[test]
+    fn addresses() {
+        assert_eq!(unescape("/run/user/1000/bus").unwrap(), "/run/user/1000/bus");
+        assert_eq!(unescape("/tmp/a%2cb%3D").unwrap(), "/tmp/a,b=");
+        assert!(unescape("/tmp/%2").is_err());
+        assert!(matches!(connect("tcp:host=localhost,port=1"), Err(Error::Address(_))));
+    }
+}
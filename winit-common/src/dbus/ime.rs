@@ -0,0 +1,981 @@
+//! Input contexts of the IBus and Fcitx 5 input method frameworks.
+//!
+//! Both frameworks expose the same model over D-Bus: the client creates an input context, forwards
+//! the key presses to it, and gets the preedit and the committed text back as signals.
+
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::os::fd::{AsFd, BorrowedFd};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use winit_core::event::{Ime, PreeditStyle};
+use winit_core::window::{ImeHint, ImePurpose, ImeSurroundingText};
+
+use super::connection::method_error;
+use super::{Connection, Error, Message, MessageType, NO_REPLY_EXPECTED, Value};
+
+const IBUS_NAME: &str = "org.freedesktop.IBus";
+const IBUS_PATH: &str = "/org/freedesktop/IBus";
+const IBUS_INTERFACE: &str = "org.freedesktop.IBus";
+const IBUS_INPUT_CONTEXT: &str = "org.freedesktop.IBus.InputContext";
+
+const FCITX_NAME: &str = "org.fcitx.Fcitx5";
+const FCITX_PATH: &str = "/org/freedesktop/portal/inputmethod";
+const FCITX_INTERFACE: &str = "org.fcitx.Fcitx.InputMethod1";
+const FCITX_INPUT_CONTEXT: &str = "org.fcitx.Fcitx.InputContext1";
+
+/// How long to wait for the input method to decide whether it handles a key.
+const KEY_TIMEOUT: Duration = Duration::from_millis(500);
+
+// `IBusCapabilite` flags.
+const IBUS_CAP_PREEDIT_TEXT: u32 = 1 << 0;
+const IBUS_CAP_FOCUS: u32 = 1 << 3;
+const IBUS_CAP_SURROUNDING_TEXT: u32 = 1 << 5;
+
+const IBUS_RELEASE_MASK: u32 = 1 << 30;
+
+// `IBusAttrType` values.
+const IBUS_ATTR_TYPE_UNDERLINE: u32 = 1;
+const IBUS_ATTR_TYPE_BACKGROUND: u32 = 3;
+
+// `IBusInputPurpose` values, shared with GTK.
+const IBUS_PURPOSE_FREE_FORM: u32 = 0;
+const IBUS_PURPOSE_NUMBER: u32 = 3;
+const IBUS_PURPOSE_PHONE: u32 = 4;
+const IBUS_PURPOSE_URL: u32 = 5;
+const IBUS_PURPOSE_EMAIL: u32 = 6;
+const IBUS_PURPOSE_PASSWORD: u32 = 8;
+const IBUS_PURPOSE_PIN: u32 = 9;
+const IBUS_PURPOSE_TERMINAL: u32 = 10;
+
+// `IBusInputHints` flags, shared with GTK.
+const IBUS_HINT_SPELLCHECK: u32 = 1 << 0;
+const IBUS_HINT_WORD_COMPLETION: u32 = 1 << 2;
+const IBUS_HINT_LOWERCASE: u32 = 1 << 3;
+const IBUS_HINT_UPPERCASE_CHARS: u32 = 1 << 4;
+const IBUS_HINT_UPPERCASE_WORDS: u32 = 1 << 5;
+const IBUS_HINT_UPPERCASE_SENTENCES: u32 = 1 << 6;
+const IBUS_HINT_PRIVATE: u32 = 1 << 11;
+
+// Fcitx `CapabilityFlag` flags.
+const FCITX_CAP_PREEDIT: u64 = 1 << 1;
+const FCITX_CAP_PASSWORD: u64 = 1 << 3;
+const FCITX_CAP_FORMATTED_PREEDIT: u64 = 1 << 4;
+const FCITX_CAP_SURROUNDING_TEXT: u64 = 1 << 6;
+const FCITX_CAP_EMAIL: u64 = 1 << 7;
+const FCITX_CAP_DIGIT: u64 = 1 << 8;
+const FCITX_CAP_UPPERCASE: u64 = 1 << 9;
+const FCITX_CAP_LOWERCASE: u64 = 1 << 10;
+const FCITX_CAP_URL: u64 = 1 << 12;
+const FCITX_CAP_DIALABLE: u64 = 1 << 13;
+const FCITX_CAP_NUMBER: u64 = 1 << 14;
+const FCITX_CAP_SPELL_CHECK: u64 = 1 << 16;
+const FCITX_CAP_WORD_COMPLETION: u64 = 1 << 18;
+const FCITX_CAP_UPPERCASE_WORDS: u64 = 1 << 19;
+const FCITX_CAP_UPPERCASE_SENTENCES: u64 = 1 << 20;
+const FCITX_CAP_TERMINAL: u64 = 1 << 25;
+const FCITX_CAP_DATE: u64 = 1 << 26;
+const FCITX_CAP_TIME: u64 = 1 << 27;
+const FCITX_CAP_MULTILINE: u64 = 1 << 28;
+const FCITX_CAP_SENSITIVE: u64 = 1 << 29;
+
+// Fcitx `TextFormatFlag` flags.
+const FCITX_FORMAT_UNDERLINE: i32 = 1 << 3;
+const FCITX_FORMAT_HIGHLIGHT: i32 = 1 << 4;
+
+/// An input method framework reachable over D-Bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImeFramework {
+    IBus,
+    Fcitx5,
+}
+
+impl ImeFramework {
+    /// The framework the user selected for their applications.
+    ///
+    /// `GTK_IM_MODULE` is looked at first, then the `@im=` modifier of `XMODIFIERS`. Returns
+    /// `None` when neither names IBus or Fcitx, e.g. when `GTK_IM_MODULE=xim` asks for XIM.
+    pub fn from_env() -> Option<Self> {
+        let gtk_im_module = std::env::var("GTK_IM_MODULE").ok();
+        let xmodifiers = std::env::var("XMODIFIERS").ok();
+        Self::from_vars(gtk_im_module.as_deref(), xmodifiers.as_deref())
+    }
+
+    fn from_vars(gtk_im_module: Option<&str>, xmodifiers: Option<&str>) -> Option<Self> {
+        let from_name = |name: &str| match name {
+            "ibus" => Some(Self::IBus),
+            "fcitx" | "fcitx5" => Some(Self::Fcitx5),
+            _ => None,
+        };
+
+        match gtk_im_module.filter(|module| !module.is_empty()) {
+            Some(module) => from_name(module),
+            None => xmodifiers?
+                .split('@')
+                .find_map(|modifier| modifier.strip_prefix("im="))
+                .and_then(|name| from_name(name.trim())),
+        }
+    }
+}
+
+/// What the input method reported, returned by [`InputContext::dispatch()`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum InputContextEvent {
+    Ime(Ime),
+    /// The input method decided whether it consumed the key sent by
+    /// [`InputContext::process_key()`], or didn't answer in time.
+    KeyProcessed {
+        serial: u32,
+        consumed: bool,
+    },
+}
+
+/// An input context of an input method framework.
+///
+/// Nothing blocks: the input context is created, the keys are processed, and the preedit and the
+/// committed text arrive asynchronously, [`Self::dispatch()`] needs to be called whenever the file
+/// descriptor becomes readable and at the [`Self::key_deadline()`].
+#[derive(Debug)]
+pub struct InputContext {
+    connection: Connection,
+    framework: ImeFramework,
+    /// Object path of the input context, once created.
+    path: Option<String>,
+    /// Serial of the pending `CreateInputContext` call.
+    create_serial: Option<u32>,
+    /// The calls made before the input context was created, made once it is.
+    queued_calls: Vec<(&'static str, Vec<Value>)>,
+    /// The serials of the keys waiting for the answer of the input method, and when to give up.
+    pending_keys: VecDeque<(u32, Instant)>,
+    /// The surrounding text last sent and its cursor in bytes, to convert the deletions to bytes.
+    surrounding_text: Option<(String, usize)>,
+    /// The last preedit received from IBus, restored when it shows the preedit again.
+    preedit: Option<Ime>,
+}
+
+impl InputContext {
+    /// Connect to the framework and request an input context.
+    pub fn new(framework: ImeFramework) -> Result<Self, Error> {
+        let connection = match framework {
+            ImeFramework::IBus => Connection::open(&ibus_address()?)?,
+            ImeFramework::Fcitx5 => Connection::session()?,
+        };
+        Self::open(connection, framework)
+    }
+
+    /// Request an input context over an existing connection.
+    ///
+    /// The requests made until the input context is created are sent once it is, a failure to
+    /// create it is reported by [`Self::dispatch()`].
+    pub fn open(mut connection: Connection, framework: ImeFramework) -> Result<Self, Error> {
+        let program = std::env::current_exe()
+            .ok()
+            .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| String::from("winit"));
+
+        let create = match framework {
+            ImeFramework::IBus => {
+                Message::method_call(IBUS_NAME, IBUS_PATH, IBUS_INTERFACE, "CreateInputContext")
+                    .with_body(vec![Value::String(program)])
+            },
+            ImeFramework::Fcitx5 => {
+                let args = Value::Array {
+                    signature: String::from("(ss)"),
+                    items: vec![Value::Struct(vec![
+                        Value::String(String::from("program")),
+                        Value::String(program),
+                    ])],
+                };
+                Message::method_call(FCITX_NAME, FCITX_PATH, FCITX_INTERFACE, "CreateInputContext")
+                    .with_body(vec![args])
+            },
+        };
+        let create_serial = Some(connection.send(&create)?);
+
+        let mut context = Self {
+            connection,
+            framework,
+            path: None,
+            create_serial,
+            queued_calls: Vec::new(),
+            pending_keys: VecDeque::new(),
+            surrounding_text: None,
+            preedit: None,
+        };
+        if framework == ImeFramework::IBus {
+            // Fcitx has the content type among its capabilities, they are set along with it.
+            let capabilities = IBUS_CAP_PREEDIT_TEXT | IBUS_CAP_FOCUS | IBUS_CAP_SURROUNDING_TEXT;
+            context.send("SetCapabilities", vec![Value::UInt32(capabilities)])?;
+        }
+        context.set_content_type(ImeHint::NONE, ImePurpose::Normal)?;
+        Ok(context)
+    }
+
+    /// Whether the framework created the input context.
+    pub fn is_created(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// The framework the input context belongs to.
+    pub fn framework(&self) -> ImeFramework {
+        self.framework
+    }
+
+    pub fn focus_in(&mut self) -> Result<(), Error> {
+        self.send("FocusIn", Vec::new())
+    }
+
+    pub fn focus_out(&mut self) -> Result<(), Error> {
+        self.preedit = None;
+        self.send("FocusOut", Vec::new())
+    }
+
+    /// Discard the current composition.
+    pub fn reset(&mut self) -> Result<(), Error> {
+        self.preedit = None;
+        self.send("Reset", Vec::new())
+    }
+
+    /// Set the area the candidate window should not cover, in root window coordinates.
+    pub fn set_cursor_area(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> Result<(), Error> {
+        let member = match self.framework {
+            ImeFramework::IBus => "SetCursorLocation",
+            ImeFramework::Fcitx5 => "SetCursorRect",
+        };
+        let body =
+            vec![Value::Int32(x), Value::Int32(y), Value::Int32(width), Value::Int32(height)];
+        self.send(member, body)
+    }
+
+    pub fn set_content_type(&mut self, hint: ImeHint, purpose: ImePurpose) -> Result<(), Error> {
+        match self.framework {
+            ImeFramework::IBus => {
+                let (purpose, hints) = ibus_content_type(hint, purpose);
+                self.send("SetContentType", vec![Value::UInt32(purpose), Value::UInt32(hints)])
+            },
+            ImeFramework::Fcitx5 => {
+                let capabilities = FCITX_CAP_PREEDIT
+                    | FCITX_CAP_FORMATTED_PREEDIT
+                    | FCITX_CAP_SURROUNDING_TEXT
+                    | fcitx_content_type(hint, purpose);
+                self.send("SetCapability", vec![Value::UInt64(capabilities)])
+            },
+        }
+    }
+
+    pub fn set_surrounding_text(
+        &mut self,
+        surrounding_text: &ImeSurroundingText,
+    ) -> Result<(), Error> {
+        let text = surrounding_text.text();
+        let chars = |index: usize| text[..index].chars().count() as u32;
+        let cursor = Value::UInt32(chars(surrounding_text.cursor()));
+        let anchor = Value::UInt32(chars(surrounding_text.anchor()));
+
+        let text_value = match self.framework {
+            ImeFramework::IBus => ibus_text(text),
+            ImeFramework::Fcitx5 => Value::String(text.to_owned()),
+        };
+        self.send("SetSurroundingText", vec![text_value, cursor, anchor])?;
+        self.surrounding_text = Some((text.to_owned(), surrounding_text.cursor()));
+        Ok(())
+    }
+
+    /// Send a key to the input method, returning the serial of the
+    /// [`InputContextEvent::KeyProcessed`] telling whether it consumed it, or `None` when the
+    /// input context isn't created yet.
+    ///
+    /// `keysym` is the keysym of the key with the modifiers of `state` applied, `keycode` the X11
+    /// keycode, and `state` the X11 modifier mask.
+    pub fn process_key(
+        &mut self,
+        keysym: u32,
+        keycode: u32,
+        state: u32,
+        pressed: bool,
+        time: u32,
+    ) -> Result<Option<u32>, Error> {
+        let Some(path) = self.path.as_deref() else {
+            return Ok(None);
+        };
+
+        let body = match self.framework {
+            ImeFramework::IBus => {
+                let state = if pressed { state } else { state | IBUS_RELEASE_MASK };
+                // IBus uses the evdev keycodes.
+                let keycode = keycode.saturating_sub(8);
+                vec![Value::UInt32(keysym), Value::UInt32(keycode), Value::UInt32(state)]
+            },
+            ImeFramework::Fcitx5 => vec![
+                Value::UInt32(keysym),
+                Value::UInt32(keycode),
+                Value::UInt32(state),
+                Value::Bool(!pressed),
+                Value::UInt32(time),
+            ],
+        };
+
+        let call = self.method_call(path, "ProcessKeyEvent").with_body(body);
+        let serial = self.connection.send(&call)?;
+        self.pending_keys.push_back((serial, Instant::now() + KEY_TIMEOUT));
+        Ok(Some(serial))
+    }
+
+    /// When to [dispatch](Self::dispatch) to give up on the keys the input method didn't answer.
+    pub fn key_deadline(&self) -> Option<Instant> {
+        self.pending_keys.front().map(|&(_, deadline)| deadline)
+    }
+
+    /// Process the received messages without blocking, returning the resulting events.
+    ///
+    /// An empty [`Ime::Preedit`] precedes every [`Ime::Commit`].
+    pub fn dispatch(&mut self) -> Result<Vec<InputContextEvent>, Error> {
+        let mut events = Vec::new();
+        while let Some(message) = self.connection.try_recv()? {
+            match message.message_type {
+                MessageType::MethodReturn | MessageType::Error
+                    if self.create_serial.is_some()
+                        && message.reply_serial == self.create_serial =>
+                {
+                    self.created(&message)?;
+                },
+                MessageType::MethodReturn | MessageType::Error => {
+                    self.key_processed(&message, &mut events)
+                },
+                MessageType::Signal if message.path.is_some() && message.path == self.path => {
+                    let mut ime_events = Vec::new();
+                    match self.framework {
+                        ImeFramework::IBus => self.ibus_signal(&message, &mut ime_events),
+                        ImeFramework::Fcitx5 => self.fcitx_signal(&message, &mut ime_events),
+                    }
+                    events.extend(ime_events.into_iter().map(InputContextEvent::Ime));
+                },
+                _ => (),
+            }
+        }
+
+        // Give up on the keys the input method is too slow to answer, as if not consumed.
+        let now = Instant::now();
+        while let Some(&(serial, _)) = self.pending_keys.front().filter(|(_, at)| *at <= now) {
+            tracing::warn!("the input method didn't process the key in time");
+            self.pending_keys.pop_front();
+            events.push(InputContextEvent::KeyProcessed { serial, consumed: false });
+        }
+
+        Ok(events)
+    }
+
+    /// Start using the input context, sending the requests made meanwhile.
+    fn created(&mut self, reply: &Message) -> Result<(), Error> {
+        self.create_serial = None;
+        if reply.message_type == MessageType::Error {
+            return Err(method_error(reply));
+        }
+
+        let path = object_path(reply)?;
+        let interface = match self.framework {
+            ImeFramework::IBus => IBUS_INPUT_CONTEXT,
+            ImeFramework::Fcitx5 => FCITX_INPUT_CONTEXT,
+        };
+        self.connection
+            .add_match(&format!("type='signal',path='{path}',interface='{interface}'"))?;
+
+        self.path = Some(path);
+        for (member, body) in std::mem::take(&mut self.queued_calls) {
+            self.send(member, body)?;
+        }
+        Ok(())
+    }
+
+    fn key_processed(&mut self, reply: &Message, events: &mut Vec<InputContextEvent>) {
+        let Some(serial) = reply.reply_serial else {
+            return;
+        };
+        let Some(index) = self.pending_keys.iter().position(|&(pending, _)| pending == serial)
+        else {
+            // Answered too late.
+            return;
+        };
+        self.pending_keys.remove(index);
+
+        let consumed = match reply.message_type {
+            MessageType::Error => {
+                tracing::warn!(
+                    "the input method failed to process the key: {}",
+                    method_error(reply)
+                );
+                false
+            },
+            _ => reply.body.first().and_then(Value::as_bool).unwrap_or(false),
+        };
+        events.push(InputContextEvent::KeyProcessed { serial, consumed });
+    }
+
+    fn ibus_signal(&mut self, message: &Message, events: &mut Vec<Ime>) {
+        let member = message.member.as_deref().unwrap_or_default();
+        match (member, &message.body[..]) {
+            ("CommitText", [text, ..]) => {
+                self.commit(ibus_text_parts(text).map(|(text, _)| text).unwrap_or_default(), events)
+            },
+            ("UpdatePreeditText", [text, cursor, visible, ..]) => {
+                let Some((text, attributes)) = ibus_text_parts(text) else {
+                    return;
+                };
+                let cursor = char_to_byte(&text, cursor.as_u32().unwrap_or_default() as usize);
+                let styles = ibus_preedit_styles(&text, attributes);
+                self.preedit = Some(Ime::Preedit(text, Some((cursor, cursor)), styles));
+                if visible.as_bool().unwrap_or(true) {
+                    events.push(self.preedit_event());
+                } else {
+                    events.push(Ime::Preedit(String::new(), None, Vec::new()));
+                }
+            },
+            ("ShowPreeditText", _) => events.push(self.preedit_event()),
+            ("HidePreeditText", _) => events.push(Ime::Preedit(String::new(), None, Vec::new())),
+            ("DeleteSurroundingText", [offset, chars, ..]) => {
+                self.delete_surrounding(offset, chars, events)
+            },
+            _ => (),
+        }
+    }
+
+    fn fcitx_signal(&mut self, message: &Message, events: &mut Vec<Ime>) {
+        let member = message.member.as_deref().unwrap_or_default();
+        match (member, &message.body[..]) {
+            ("CommitString", [text, ..]) => {
+                self.commit(text.as_str().unwrap_or_default().to_owned(), events)
+            },
+            ("UpdateFormattedPreedit", [segments, cursor, ..]) => {
+                let mut text = String::new();
+                let mut styles = Vec::new();
+                for segment in segments.as_slice().unwrap_or_default() {
+                    let Some([segment, format]) = segment.as_slice() else {
+                        continue;
+                    };
+                    let segment = segment.as_str().unwrap_or_default();
+                    let style = fcitx_preedit_style(format.as_i32().unwrap_or_default());
+                    let start = text.len();
+                    text.push_str(segment);
+                    if !style.is_empty() && !segment.is_empty() {
+                        styles.push((start..text.len(), style));
+                    }
+                }
+
+                // A negative cursor hides it.
+                let cursor = cursor
+                    .as_i32()
+                    .and_then(|cursor| usize::try_from(cursor).ok())
+                    .filter(|&cursor| text.is_char_boundary(cursor));
+
+                events.push(Ime::Preedit(text, cursor.map(|cursor| (cursor, cursor)), styles));
+            },
+            ("DeleteSurroundingText", [offset, chars, ..]) => {
+                self.delete_surrounding(offset, chars, events)
+            },
+            _ => (),
+        }
+    }
+
+    fn commit(&mut self, text: String, events: &mut Vec<Ime>) {
+        self.preedit = None;
+        events.push(Ime::Preedit(String::new(), None, Vec::new()));
+        events.push(Ime::Commit(text));
+    }
+
+    fn preedit_event(&self) -> Ime {
+        self.preedit.clone().unwrap_or(Ime::Preedit(String::new(), None, Vec::new()))
+    }
+
+    /// Convert the deletion of `chars` characters starting `offset` characters from the cursor.
+    fn delete_surrounding(&mut self, offset: &Value, chars: &Value, events: &mut Vec<Ime>) {
+        let offset = offset.as_i32().unwrap_or_default();
+        let chars = chars.as_u32().unwrap_or_default() as usize;
+        if offset > 0 {
+            tracing::warn!("deleting text not touching the cursor is not supported");
+            return;
+        }
+
+        let chars_before = (offset.unsigned_abs() as usize).min(chars);
+        let chars_after = chars - chars_before;
+        let (before_bytes, after_bytes) = match &self.surrounding_text {
+            Some((text, cursor)) => {
+                let before = text[..*cursor].chars().rev().take(chars_before).map(char::len_utf8);
+                let after = text[*cursor..].chars().take(chars_after).map(char::len_utf8);
+                (before.sum(), after.sum())
+            },
+            // Without the text at hand, assume it's ASCII.
+            None => (chars_before, chars_after),
+        };
+
+        events.push(Ime::DeleteSurrounding { before_bytes, after_bytes });
+    }
+
+    fn method_call(&self, path: &str, member: &str) -> Message {
+        match self.framework {
+            ImeFramework::IBus => Message::method_call(IBUS_NAME, path, IBUS_INPUT_CONTEXT, member),
+            ImeFramework::Fcitx5 => {
+                Message::method_call(FCITX_NAME, path, FCITX_INPUT_CONTEXT, member)
+            },
+        }
+    }
+
+    /// Call a method of the input context without waiting for its reply, once it is created.
+    fn send(&mut self, member: &'static str, body: Vec<Value>) -> Result<(), Error> {
+        let Some(path) = self.path.as_deref() else {
+            self.queued_calls.push((member, body));
+            return Ok(());
+        };
+        let mut message = self.method_call(path, member).with_body(body);
+        message.flags |= NO_REPLY_EXPECTED;
+        self.connection.send(&message)?;
+        Ok(())
+    }
+}
+
+impl AsFd for InputContext {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.connection.as_fd()
+    }
+}
+
+fn object_path(reply: &Message) -> Result<String, Error> {
+    reply
+        .body
+        .first()
+        .and_then(Value::as_str)
+        .map(str::to_owned)
+        .ok_or(Error::Malformed("no input context path"))
+}
+
+/// The address of the IBus daemon, which runs its own bus.
+///
+/// Read from `IBUS_ADDRESS`, or from the file the daemon writes for the X display.
+fn ibus_address() -> Result<String, Error> {
+    if let Ok(address) = std::env::var("IBUS_ADDRESS") {
+        return Ok(address);
+    }
+
+    let no_address = || Error::Address(String::from("no IBus address"));
+
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .ok_or_else(no_address)?;
+    let machine_id = std::fs::read_to_string("/var/lib/dbus/machine-id")
+        .or_else(|_| std::fs::read_to_string("/etc/machine-id"))?;
+    let display = std::env::var("DISPLAY").map_err(|_| no_address())?;
+
+    let contents = std::fs::read_to_string(
+        config_dir.join("ibus/bus").join(ibus_address_file(machine_id.trim(), &display)),
+    )?;
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("IBUS_ADDRESS="))
+        .map(str::to_owned)
+        .ok_or_else(no_address)
+}
+
+/// The name of the file holding the IBus address, `<machine-id>-<host>-<display number>`.
+fn ibus_address_file(machine_id: &str, display: &str) -> String {
+    let (host, display) = display.rsplit_once(':').unwrap_or(("", display));
+    let host = if host.is_empty() { "unix" } else { host };
+    let number = display.split('.').next().unwrap_or_default();
+    format!("{machine_id}-{host}-{number}")
+}
+
+/// Wrap the text in an `IBusText` without attributes.
+fn ibus_text(text: &str) -> Value {
+    let no_properties = || Value::dict("s", "v", []);
+    let attributes = Value::Struct(vec![
+        Value::String(String::from("IBusAttrList")),
+        no_properties(),
+        Value::Array { signature: String::from("v"), items: Vec::new() },
+    ]);
+    Value::Variant(Box::new(Value::Struct(vec![
+        Value::String(String::from("IBusText")),
+        no_properties(),
+        Value::String(text.to_owned()),
+        Value::Variant(Box::new(attributes)),
+    ])))
+}
+
+/// The text and attributes of an `IBusText`.
+fn ibus_text_parts(value: &Value) -> Option<(String, &[Value])> {
+    let [_, _, text, attributes, ..] = value.as_slice()? else {
+        return None;
+    };
+    let attributes = match attributes.as_slice() {
+        Some([_, _, attributes, ..]) => attributes.as_slice().unwrap_or_default(),
+        _ => &[],
+    };
+    Some((text.as_str()?.to_owned(), attributes))
+}
+
+/// Convert the `IBusAttribute`s to non-overlapping styled segments.
+fn ibus_preedit_styles(text: &str, attributes: &[Value]) -> Vec<(Range<usize>, PreeditStyle)> {
+    let mut char_styles = vec![PreeditStyle::empty(); text.chars().count()];
+    for attribute in attributes {
+        let Some([_, _, kind, value, start, end, ..]) = attribute.as_slice() else {
+            continue;
+        };
+        let style = match (kind.as_u32(), value.as_u32()) {
+            (Some(IBUS_ATTR_TYPE_UNDERLINE), Some(underline)) if underline != 0 => {
+                PreeditStyle::UNDERLINE
+            },
+            (Some(IBUS_ATTR_TYPE_BACKGROUND), _) => PreeditStyle::HIGHLIGHT,
+            _ => continue,
+        };
+
+        let start = start.as_u32().unwrap_or_default() as usize;
+        let end = end.as_u32().unwrap_or_default() as usize;
+        for char_style in char_styles.iter_mut().take(end).skip(start) {
+            *char_style |= style;
+        }
+    }
+
+    let mut styles: Vec<(Range<usize>, PreeditStyle)> = Vec::new();
+    for ((start, char), style) in text.char_indices().zip(char_styles) {
+        let end = start + char.len_utf8();
+        match styles.last_mut() {
+            Some((range, last)) if range.end == start && *last == style => range.end = end,
+            _ if !style.is_empty() => styles.push((start..end, style)),
+            _ => (),
+        }
+    }
+    styles
+}
+
+fn fcitx_preedit_style(format: i32) -> PreeditStyle {
+    let mut style = PreeditStyle::empty();
+    if format & FCITX_FORMAT_UNDERLINE != 0 {
+        style |= PreeditStyle::UNDERLINE;
+    }
+    if format & FCITX_FORMAT_HIGHLIGHT != 0 {
+        style |= PreeditStyle::HIGHLIGHT;
+    }
+    style
+}
+
+/// The byte index of the character at `index`, clamped to the end of the text.
+fn char_to_byte(text: &str, index: usize) -> usize {
+    text.char_indices().nth(index).map_or(text.len(), |(byte, _)| byte)
+}
+
+fn ibus_content_type(hint: ImeHint, purpose: ImePurpose) -> (u32, u32) {
+    let purpose = match purpose {
+        ImePurpose::Password => IBUS_PURPOSE_PASSWORD,
+        ImePurpose::Terminal => IBUS_PURPOSE_TERMINAL,
+        ImePurpose::Number => IBUS_PURPOSE_NUMBER,
+        ImePurpose::Phone => IBUS_PURPOSE_PHONE,
+        ImePurpose::Url => IBUS_PURPOSE_URL,
+        ImePurpose::Email => IBUS_PURPOSE_EMAIL,
+        ImePurpose::Pin => IBUS_PURPOSE_PIN,
+        _ => IBUS_PURPOSE_FREE_FORM,
+    };
+
+    let mut hints = 0;
+    for (flag, ibus_flag) in [
+        (ImeHint::COMPLETION, IBUS_HINT_WORD_COMPLETION),
+        (ImeHint::SPELLCHECK, IBUS_HINT_SPELLCHECK),
+        (ImeHint::AUTO_CAPITALIZATION, IBUS_HINT_UPPERCASE_SENTENCES),
+        (ImeHint::LOWERCASE, IBUS_HINT_LOWERCASE),
+        (ImeHint::UPPERCASE, IBUS_HINT_UPPERCASE_CHARS),
+        (ImeHint::TITLECASE, IBUS_HINT_UPPERCASE_WORDS),
+        (ImeHint::SENSITIVE_DATA, IBUS_HINT_PRIVATE),
+    ] {
+        if hint.contains(flag) {
+            hints |= ibus_flag;
+        }
+    }
+
+    (purpose, hints)
+}
+
+/// The capabilities describing the content type.
+fn fcitx_content_type(hint: ImeHint, purpose: ImePurpose) -> u64 {
+    let mut capabilities = match purpose {
+        ImePurpose::Password => FCITX_CAP_PASSWORD,
+        ImePurpose::Terminal => FCITX_CAP_TERMINAL,
+        ImePurpose::Number => FCITX_CAP_NUMBER,
+        ImePurpose::Phone => FCITX_CAP_DIALABLE,
+        ImePurpose::Url => FCITX_CAP_URL,
+        ImePurpose::Email => FCITX_CAP_EMAIL,
+        ImePurpose::Pin => FCITX_CAP_PASSWORD | FCITX_CAP_DIGIT,
+        ImePurpose::Date => FCITX_CAP_DATE,
+        ImePurpose::Time => FCITX_CAP_TIME,
+        ImePurpose::DateTime => FCITX_CAP_DATE | FCITX_CAP_TIME,
+        _ => 0,
+    };
+
+    for (flag, fcitx_flag) in [
+        (ImeHint::COMPLETION, FCITX_CAP_WORD_COMPLETION),
+        (ImeHint::SPELLCHECK, FCITX_CAP_SPELL_CHECK),
+        (ImeHint::AUTO_CAPITALIZATION, FCITX_CAP_UPPERCASE_SENTENCES),
+        (ImeHint::LOWERCASE, FCITX_CAP_LOWERCASE),
+        (ImeHint::UPPERCASE, FCITX_CAP_UPPERCASE),
+        (ImeHint::TITLECASE, FCITX_CAP_UPPERCASE_WORDS),
+        (ImeHint::HIDDEN_TEXT, FCITX_CAP_PASSWORD),
+        (ImeHint::SENSITIVE_DATA, FCITX_CAP_SENSITIVE),
+        (ImeHint::MULTILINE, FCITX_CAP_MULTILINE),
+    ] {
+        if hint.contains(flag) {
+            capabilities |= fcitx_flag;
+        }
+    }
+
+    capabilities
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    use super::*;
+    use crate::dbus::connection::tests::MockBus;
+
+    const CONTEXT_PATH: &str = "/org/freedesktop/portal/inputcontext/1";
+
+    fn dispatch_until(context: &mut InputContext, count: usize) -> Vec<InputContextEvent> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut events = Vec::new();
+        while events.len() < count {
+            assert!(Instant::now() < deadline, "events not received: {events:?}");
+            events.extend(context.dispatch().unwrap());
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        events
+    }
+
+    fn wait_created(context: &mut InputContext) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !context.is_created() {
+            assert!(Instant::now() < deadline, "input context not created");
+            assert!(context.dispatch().unwrap().is_empty());
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn fcitx_input_context() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let bus_calls = Arc::clone(&calls);
+        let bus = MockBus::new(move |call| {
+            let member = call.member.clone().unwrap_or_default();
+            bus_calls.lock().unwrap().push((member.clone(), call.body.clone()));
+            match &*member {
+                "CreateInputContext" => {
+                    assert_eq!(call.destination.as_deref(), Some(FCITX_NAME));
+                    vec![Message::method_return(call).with_body(vec![
+                        Value::ObjectPath(CONTEXT_PATH.into()),
+                        Value::Array { signature: "y".into(), items: Vec::new() },
+                    ])]
+                },
+                "ProcessKeyEvent" => {
+                    let preedit = Value::Array {
+                        signature: "(si)".into(),
+                        items: vec![
+                            Value::Struct(vec![
+                                Value::String("你".into()),
+                                Value::Int32(FCITX_FORMAT_HIGHLIGHT),
+                            ]),
+                            Value::Struct(vec![
+                                Value::String("好".into()),
+                                Value::Int32(FCITX_FORMAT_UNDERLINE),
+                            ]),
+                        ],
+                    };
+                    vec![
+                        Message::signal(
+                            CONTEXT_PATH,
+                            FCITX_INPUT_CONTEXT,
+                            "UpdateFormattedPreedit",
+                        )
+                        .with_body(vec![preedit, Value::Int32(3)]),
+                        Message::signal(CONTEXT_PATH, FCITX_INPUT_CONTEXT, "CommitString")
+                            .with_body(vec![Value::String("你好".into())]),
+                        Message::signal(CONTEXT_PATH, FCITX_INPUT_CONTEXT, "DeleteSurroundingText")
+                            .with_body(vec![Value::Int32(-1), Value::UInt32(2)]),
+                        Message::method_return(call).with_body(vec![Value::Bool(true)]),
+                    ]
+                },
+                _ => {
+                    assert!(call.flags & NO_REPLY_EXPECTED != 0, "{member} expects a reply");
+                    Vec::new()
+                },
+            }
+        });
+
+        let connection = Connection::open(&bus.address).unwrap();
+        let mut context = InputContext::open(connection, ImeFramework::Fcitx5).unwrap();
+        context.set_content_type(ImeHint::SPELLCHECK, ImePurpose::Email).unwrap();
+        context
+            .set_surrounding_text(&ImeSurroundingText::new("aé|b".into(), 3, 3).unwrap())
+            .unwrap();
+        context.set_cursor_area(10, 20, 1, 16).unwrap();
+        assert_eq!(context.process_key(0x6e, 57, 0, true, 0).unwrap(), None);
+
+        wait_created(&mut context);
+        let serial = context.process_key(0x6e, 57, 0, true, 0).unwrap().unwrap();
+        assert!(context.key_deadline().is_some());
+
+        let events = dispatch_until(&mut context, 5);
+        assert_eq!(events, [
+            InputContextEvent::Ime(Ime::Preedit("你好".into(), Some((3, 3)), vec![
+                (0..3, PreeditStyle::HIGHLIGHT),
+                (3..6, PreeditStyle::UNDERLINE),
+            ])),
+            InputContextEvent::Ime(Ime::Preedit(String::new(), None, Vec::new())),
+            InputContextEvent::Ime(Ime::Commit("你好".into())),
+            InputContextEvent::Ime(Ime::DeleteSurrounding { before_bytes: 2, after_bytes: 1 }),
+            InputContextEvent::KeyProcessed { serial, consumed: true },
+        ]);
+        assert_eq!(context.key_deadline(), None);
+
+        drop(context);
+        drop(bus);
+
+        let calls = calls.lock().unwrap();
+        let body = |member: &str| {
+            calls.iter().rev().find(|(name, _)| name == member).map(|(_, body)| body.clone())
+        };
+        let capabilities = FCITX_CAP_PREEDIT
+            | FCITX_CAP_FORMATTED_PREEDIT
+            | FCITX_CAP_SURROUNDING_TEXT
+            | FCITX_CAP_SPELL_CHECK
+            | FCITX_CAP_EMAIL;
+        assert_eq!(body("SetCapability"), Some(vec![Value::UInt64(capabilities)]));
+        assert_eq!(
+            body("SetSurroundingText"),
+            Some(vec![Value::String("aé|b".into()), Value::UInt32(2), Value::UInt32(2)])
+        );
+        assert_eq!(
+            body("ProcessKeyEvent"),
+            Some(vec![
+                Value::UInt32(0x6e),
+                Value::UInt32(57),
+                Value::UInt32(0),
+                Value::Bool(false),
+                Value::UInt32(0),
+            ])
+        );
+    }
+
+    #[test]
+    fn ibus_preedit() {
+        let bus = MockBus::new(|call| match call.member.as_deref() {
+            Some("CreateInputContext") => {
+                assert_eq!(call.destination.as_deref(), Some(IBUS_NAME));
+                vec![
+                    Message::method_return(call)
+                        .with_body(vec![Value::ObjectPath(CONTEXT_PATH.into())]),
+                ]
+            },
+            Some("ProcessKeyEvent") => {
+                // Underline the whole text and highlight the second character.
+                let attribute = |kind, value, start, end| {
+                    Value::Variant(Box::new(Value::Struct(vec![
+                        Value::String("IBusAttribute".into()),
+                        Value::dict("s", "v", []),
+                        Value::UInt32(kind),
+                        Value::UInt32(value),
+                        Value::UInt32(start),
+                        Value::UInt32(end),
+                    ])))
+                };
+                let mut text = ibus_text("かな");
+                if let Value::Variant(text) = &mut text {
+                    if let Value::Struct(fields) = &mut **text {
+                        fields[3] = Value::Variant(Box::new(Value::Struct(vec![
+                            Value::String("IBusAttrList".into()),
+                            Value::dict("s", "v", []),
+                            Value::Array {
+                                signature: "v".into(),
+                                items: vec![
+                                    attribute(IBUS_ATTR_TYPE_UNDERLINE, 1, 0, 2),
+                                    attribute(IBUS_ATTR_TYPE_BACKGROUND, 0xc8c8f0, 1, 2),
+                                ],
+                            },
+                        ])));
+                    }
+                }
+
+                let release = call.body[2].as_u32().unwrap() & IBUS_RELEASE_MASK != 0;
+                vec![
+                    Message::signal(CONTEXT_PATH, IBUS_INPUT_CONTEXT, "UpdatePreeditText")
+                        .with_body(vec![text, Value::UInt32(1), Value::Bool(true)]),
+                    Message::signal(CONTEXT_PATH, IBUS_INPUT_CONTEXT, "HidePreeditText"),
+                    Message::signal(CONTEXT_PATH, IBUS_INPUT_CONTEXT, "ShowPreeditText"),
+                    Message::method_return(call).with_body(vec![Value::Bool(!release)]),
+                ]
+            },
+            _ => Vec::new(),
+        });
+
+        let connection = Connection::open(&bus.address).unwrap();
+        let mut context = InputContext::open(connection, ImeFramework::IBus).unwrap();
+        wait_created(&mut context);
+        let serial = context.process_key(0x6b, 45, 0, false, 0).unwrap().unwrap();
+
+        let preedit = InputContextEvent::Ime(Ime::Preedit("かな".into(), Some((3, 3)), vec![
+            (0..3, PreeditStyle::UNDERLINE),
+            (3..6, PreeditStyle::UNDERLINE | PreeditStyle::HIGHLIGHT),
+        ]));
+        assert_eq!(dispatch_until(&mut context, 4), [
+            preedit.clone(),
+            InputContextEvent::Ime(Ime::Preedit(String::new(), None, Vec::new())),
+            preedit,
+            InputContextEvent::KeyProcessed { serial, consumed: false },
+        ]);
+    }
+
+    #[test]
+    fn creation_failure() {
+        let bus = MockBus::new(|call| match call.member.as_deref() {
+            Some("CreateInputContext") => {
+                vec![Message::error(call, "org.example.Failed", "no input method")]
+            },
+            _ => Vec::new(),
+        });
+
+        let connection = Connection::open(&bus.address).unwrap();
+        let mut context = InputContext::open(connection, ImeFramework::IBus).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            assert!(Instant::now() < deadline, "creation failure not reported");
+            if context.dispatch().is_err() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(!context.is_created());
+    }
+
+    #[test]
+    fn framework_from_env() {
+        assert_eq!(ImeFramework::from_vars(Some("ibus"), None), Some(ImeFramework::IBus));
+        assert_eq!(
+            ImeFramework::from_vars(Some("fcitx"), Some("@im=ibus")),
+            Some(ImeFramework::Fcitx5)
+        );
+        assert_eq!(ImeFramework::from_vars(Some("xim"), Some("@im=ibus")), None);
+        assert_eq!(
+            ImeFramework::from_vars(Some(""), Some("@im=fcitx5")),
+            Some(ImeFramework::Fcitx5)
+        );
+        assert_eq!(ImeFramework::from_vars(None, Some("@im=none")), None);
+        assert_eq!(ImeFramework::from_vars(None, None), None);
+
+        assert_eq!(ibus_address_file("0123", ":1"), "0123-unix-1");
+        assert_eq!(ibus_address_file("0123", "localhost:10.0"), "0123-localhost-10");
+    }
+}
@@ -0,0 +1,742 @@
+//! Marshalling of D-Bus messages.
+//!
+//! Only the little endian encoding is produced, but messages in both byte orders are accepted.
+
+use super::Error;
+
+/// Type of a message, the second byte of the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::exhaustive_enums)]
+pub enum MessageType {
+    MethodCall,
+    MethodReturn,
+    Error,
+    Signal,
+}
+
+impl MessageType {
+    fn from_raw(raw: u8) -> Option<Self> {
+        match raw {
+            1 => Some(Self::MethodCall),
+            2 => Some(Self::MethodReturn),
+            3 => Some(Self::Error),
+            4 => Some(Self::Signal),
+            _ => None,
+        }
+    }
+
+    fn to_raw(self) -> u8 {
+        match self {
+            Self::MethodCall => 1,
+            Self::MethodReturn => 2,
+            Self::Error => 3,
+            Self::Signal => 4,
+        }
+    }
+}
+
+/// The `NO_REPLY_EXPECTED` header flag.
+pub const NO_REPLY_EXPECTED: u8 = 0x1;
+
+/// A D-Bus value together with enough type information to marshal it.
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::exhaustive_enums)]
+pub enum Value {
+    Byte(u8),
+    Bool(bool),
+    Int16(i16),
+    UInt16(u16),
+    Int32(i32),
+    UInt32(u32),
+    Int64(i64),
+    UInt64(u64),
+    Double(f64),
+    String(String),
+    ObjectPath(String),
+    Signature(String),
+    /// File descriptors are not passed over the connection, only their index is kept.
+    UnixFd(u32),
+    /// An array, the element signature is needed to marshal empty arrays.
+    Array {
+        signature: String,
+        items: Vec<Value>,
+    },
+    Struct(Vec<Value>),
+    DictEntry(Box<Value>, Box<Value>),
+    Variant(Box<Value>),
+}
+
+impl Value {
+    /// Create a `a{..}` dictionary.
+    pub fn dict(
+        key_signature: &str,
+        value_signature: &str,
+        entries: impl IntoIterator<Item = (Value, Value)>,
+    ) -> Self {
+        Self::Array {
+            signature: format!("{{{key_signature}{value_signature}}}"),
+            items: entries
+                .into_iter()
+                .map(|(key, value)| Self::DictEntry(Box::new(key), Box::new(value)))
+                .collect(),
+        }
+    }
+
+    /// Create an `as` array.
+    pub fn string_array<'a>(items: impl IntoIterator<Item = &'a str>) -> Self {
+        Self::Array {
+            signature: String::from("s"),
+            items: items.into_iter().map(|item| Self::String(item.to_owned())).collect(),
+        }
+    }
+
+    /// The signature of the value.
+    pub fn signature(&self) -> String {
+        let mut signature = String::new();
+        self.write_signature(&mut signature);
+        signature
+    }
+
+    fn write_signature(&self, out: &mut String) {
+        match self {
+            Self::Byte(_) => out.push('y'),
+            Self::Bool(_) => out.push('b'),
+            Self::Int16(_) => out.push('n'),
+            Self::UInt16(_) => out.push('q'),
+            Self::Int32(_) => out.push('i'),
+            Self::UInt32(_) => out.push('u'),
+            Self::Int64(_) => out.push('x'),
+            Self::UInt64(_) => out.push('t'),
+            Self::Double(_) => out.push('d'),
+            Self::String(_) => out.push('s'),
+            Self::ObjectPath(_) => out.push('o'),
+            Self::Signature(_) => out.push('g'),
+            Self::UnixFd(_) => out.push('h'),
+            Self::Array { signature, .. } => {
+                out.push('a');
+                out.push_str(signature);
+            },
+            Self::Struct(fields) => {
+                out.push('(');
+                fields.iter().for_each(|field| field.write_signature(out));
+                out.push(')');
+            },
+            Self::DictEntry(key, value) => {
+                out.push('{');
+                key.write_signature(out);
+                value.write_signature(out);
+                out.push('}');
+            },
+            Self::Variant(_) => out.push('v'),
+        }
+    }
+
+    /// Strip any levels of variant around the value.
+    pub fn flatten(&self) -> &Value {
+        let mut value = self;
+        while let Self::Variant(inner) = value {
+            value = inner;
+        }
+        value
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self.flatten() {
+            Self::String(value) | Self::ObjectPath(value) | Self::Signature(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self.flatten() {
+            Self::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        match *self.flatten() {
+            Self::Byte(value) => Some(value.into()),
+            Self::UInt16(value) => Some(value.into()),
+            Self::UInt32(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> Option<i32> {
+        match *self.flatten() {
+            Self::Byte(value) => Some(value.into()),
+            Self::Int16(value) => Some(value.into()),
+            Self::UInt16(value) => Some(value.into()),
+            Self::Int32(value) => Some(value),
+            Self::UInt32(value) => value.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self.flatten() {
+            Self::Double(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The items of an array, or the fields of a structure.
+    pub fn as_slice(&self) -> Option<&[Value]> {
+        match self.flatten() {
+            Self::Array { items, .. } => Some(items),
+            Self::Struct(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// The entries of a dictionary.
+    pub fn dict_entries(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.as_slice().unwrap_or_default().iter().filter_map(|entry| match entry {
+            Self::DictEntry(key, value) => Some((&**key, &**value)),
+            _ => None,
+        })
+    }
+}
+
+/// A D-Bus message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub message_type: MessageType,
+    pub flags: u8,
+    /// Serial of the message, assigned when it is sent.
+    pub serial: u32,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    pub reply_serial: Option<u32>,
+    pub destination: Option<String>,
+    pub sender: Option<String>,
+    pub body: Vec<Value>,
+}
+
+const HEADER_PATH: u8 = 1;
+const HEADER_INTERFACE: u8 = 2;
+const HEADER_MEMBER: u8 = 3;
+const HEADER_ERROR_NAME: u8 = 4;
+const HEADER_REPLY_SERIAL: u8 = 5;
+const HEADER_DESTINATION: u8 = 6;
+const HEADER_SENDER: u8 = 7;
+const HEADER_SIGNATURE: u8 = 8;
+
+/// The maximum size of a message allowed by the specification.
+const MAX_MESSAGE_SIZE: usize = 1 << 27;
+
+/// The maximum nesting of containers, 32 arrays and 32 structures per the specification.
+const MAX_DEPTH: usize = 64;
+
+impl Message {
+    fn new(message_type: MessageType) -> Self {
+        Self {
+            message_type,
+            flags: 0,
+            serial: 0,
+            path: None,
+            interface: None,
+            member: None,
+            error_name: None,
+            reply_serial: None,
+            destination: None,
+            sender: None,
+            body: Vec::new(),
+        }
+    }
+
+    pub fn method_call(destination: &str, path: &str, interface: &str, member: &str) -> Self {
+        Self {
+            destination: Some(destination.to_owned()),
+            path: Some(path.to_owned()),
+            interface: Some(interface.to_owned()),
+            member: Some(member.to_owned()),
+            ..Self::new(MessageType::MethodCall)
+        }
+    }
+
+    pub fn signal(path: &str, interface: &str, member: &str) -> Self {
+        Self {
+            path: Some(path.to_owned()),
+            interface: Some(interface.to_owned()),
+            member: Some(member.to_owned()),
+            ..Self::new(MessageType::Signal)
+        }
+    }
+
+    pub fn method_return(call: &Message) -> Self {
+        Self {
+            reply_serial: Some(call.serial),
+            destination: call.sender.clone(),
+            ..Self::new(MessageType::MethodReturn)
+        }
+    }
+
+    pub fn error(call: &Message, error_name: &str, text: &str) -> Self {
+        Self {
+            reply_serial: Some(call.serial),
+            destination: call.sender.clone(),
+            error_name: Some(error_name.to_owned()),
+            body: vec![Value::String(text.to_owned())],
+            ..Self::new(MessageType::Error)
+        }
+    }
+
+    pub fn with_body(mut self, body: Vec<Value>) -> Self {
+        self.body = body;
+        self
+    }
+
+    /// Whether this is the signal `interface.member`.
+    pub fn is_signal(&self, interface: &str, member: &str) -> bool {
+        self.message_type == MessageType::Signal
+            && self.interface.as_deref() == Some(interface)
+            && self.member.as_deref() == Some(member)
+    }
+
+    /// Marshal the message with the given serial.
+    pub fn encode(&self, serial: u32) -> Vec<u8> {
+        let mut body = Writer::default();
+        let mut signature = String::new();
+        for value in &self.body {
+            body.write_value(value);
+            value.write_signature(&mut signature);
+        }
+
+        let mut fields = Vec::new();
+        let mut string_field = |code, value: &Option<String>, wrap: fn(String) -> Value| {
+            if let Some(value) = value {
+                fields.push(Value::Struct(vec![
+                    Value::Byte(code),
+                    Value::Variant(Box::new(wrap(value.clone()))),
+                ]));
+            }
+        };
+        string_field(HEADER_PATH, &self.path, Value::ObjectPath);
+        string_field(HEADER_INTERFACE, &self.interface, Value::String);
+        string_field(HEADER_MEMBER, &self.member, Value::String);
+        string_field(HEADER_ERROR_NAME, &self.error_name, Value::String);
+        string_field(HEADER_DESTINATION, &self.destination, Value::String);
+        string_field(HEADER_SENDER, &self.sender, Value::String);
+        if let Some(reply_serial) = self.reply_serial {
+            fields.push(Value::Struct(vec![
+                Value::Byte(HEADER_REPLY_SERIAL),
+                Value::Variant(Box::new(Value::UInt32(reply_serial))),
+            ]));
+        }
+        if !signature.is_empty() {
+            fields.push(Value::Struct(vec![
+                Value::Byte(HEADER_SIGNATURE),
+                Value::Variant(Box::new(Value::Signature(signature))),
+            ]));
+        }
+
+        let mut header = Writer::default();
+        header.write_value(&Value::Byte(b'l'));
+        header.write_value(&Value::Byte(self.message_type.to_raw()));
+        header.write_value(&Value::Byte(self.flags));
+        header.write_value(&Value::Byte(1));
+        header.write_value(&Value::UInt32(body.buf.len() as u32));
+        header.write_value(&Value::UInt32(serial));
+        header.write_value(&Value::Array { signature: String::from("(yv)"), items: fields });
+        header.align(8);
+
+        header.buf.extend_from_slice(&body.buf);
+        header.buf
+    }
+
+    /// The total length of the message starting the buffer, if enough of it is available to tell.
+    pub fn encoded_len(buf: &[u8]) -> Result<Option<usize>, Error> {
+        if buf.len() < 16 {
+            return Ok(None);
+        }
+
+        let big_endian = match buf[0] {
+            b'l' => false,
+            b'B' => true,
+            _ => return Err(Error::Malformed("invalid endianness")),
+        };
+        let read_u32 = |offset: usize| {
+            let bytes = buf[offset..offset + 4].try_into().unwrap();
+            if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+        };
+
+        let body_len = read_u32(4) as usize;
+        let fields_len = read_u32(12) as usize;
+        let len = (16 + fields_len).next_multiple_of(8) + body_len;
+        if len > MAX_MESSAGE_SIZE {
+            return Err(Error::Malformed("message too large"));
+        }
+
+        Ok(Some(len))
+    }
+
+    /// Unmarshal a complete message.
+    pub fn decode(buf: &[u8]) -> Result<Self, Error> {
+        let big_endian = buf.first() == Some(&b'B');
+        let mut reader = Reader { buf, pos: 0, big_endian };
+
+        reader.read_u8()?;
+        let message_type = MessageType::from_raw(reader.read_u8()?)
+            .ok_or(Error::Malformed("invalid message type"))?;
+        let flags = reader.read_u8()?;
+        if reader.read_u8()? != 1 {
+            return Err(Error::Malformed("unsupported protocol version"));
+        }
+        let body_len = reader.read_u32()? as usize;
+        let serial = reader.read_u32()?;
+
+        let mut message = Self { flags, serial, ..Self::new(message_type) };
+        let mut signature = String::new();
+        let fields = reader.read_value(b"a(yv)", 0)?;
+        for field in fields.as_slice().unwrap_or_default() {
+            let [Value::Byte(code), Value::Variant(value)] = field.as_slice().unwrap_or_default()
+            else {
+                return Err(Error::Malformed("invalid header field"));
+            };
+
+            let string = || value.as_str().map(str::to_owned);
+            match *code {
+                HEADER_PATH => message.path = string(),
+                HEADER_INTERFACE => message.interface = string(),
+                HEADER_MEMBER => message.member = string(),
+                HEADER_ERROR_NAME => message.error_name = string(),
+                HEADER_REPLY_SERIAL => message.reply_serial = value.as_u32(),
+                HEADER_DESTINATION => message.destination = string(),
+                HEADER_SENDER => message.sender = string(),
+                HEADER_SIGNATURE => signature = string().unwrap_or_default(),
+                _ => (),
+            }
+        }
+        reader.align(8)?;
+
+        let body_end = reader.pos.checked_add(body_len).filter(|end| *end <= buf.len());
+        let body_end = body_end.ok_or(Error::Malformed("truncated body"))?;
+        let mut body = Reader { buf: &buf[reader.pos..body_end], pos: 0, big_endian };
+        let mut signature = signature.as_bytes();
+        while !signature.is_empty() {
+            let (ty, rest) = split_type(signature, 0)?;
+            message.body.push(body.read_value(ty, 0)?);
+            signature = rest;
+        }
+
+        Ok(message)
+    }
+}
+
+/// Split the first complete type from the signature.
+fn split_type(signature: &[u8], depth: usize) -> Result<(&[u8], &[u8]), Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::Malformed("signature nested too deeply"));
+    }
+
+    let len = match signature.first() {
+        Some(b'y' | b'b' | b'n' | b'q' | b'i' | b'u' | b'x' | b't' | b'd' | b's' | b'o')
+        | Some(b'g' | b'h' | b'v') => 1,
+        Some(b'a') => 1 + split_type(&signature[1..], depth + 1)?.0.len(),
+        Some(&open @ (b'(' | b'{')) => {
+            let close = if open == b'(' { b')' } else { b'}' };
+            let mut len = 1;
+            while signature.get(len) != Some(&close) {
+                if len >= signature.len() {
+                    return Err(Error::Malformed("unterminated container in signature"));
+                }
+                len += split_type(&signature[len..], depth + 1)?.0.len();
+            }
+            len + 1
+        },
+        _ => return Err(Error::Malformed("invalid signature")),
+    };
+
+    Ok(signature.split_at(len))
+}
+
+fn alignment(ty: u8) -> usize {
+    match ty {
+        b'y' | b'g' | b'v' => 1,
+        b'n' | b'q' => 2,
+        b'x' | b't' | b'd' | b'(' | b'{' => 8,
+        _ => 4,
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn align(&mut self, alignment: usize) {
+        let len = self.buf.len().next_multiple_of(alignment);
+        self.buf.resize(len, 0);
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_string(&mut self, value: &str) {
+        self.write_u32(value.len() as u32);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn write_signature(&mut self, value: &str) {
+        self.buf.push(value.len() as u8);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn write_value(&mut self, value: &Value) {
+        match value {
+            Value::Byte(value) => self.buf.push(*value),
+            Value::Bool(value) => self.write_u32(*value as u32),
+            Value::Int16(value) => {
+                self.align(2);
+                self.buf.extend_from_slice(&value.to_le_bytes());
+            },
+            Value::UInt16(value) => {
+                self.align(2);
+                self.buf.extend_from_slice(&value.to_le_bytes());
+            },
+            Value::Int32(value) => self.write_u32(*value as u32),
+            Value::UInt32(value) | Value::UnixFd(value) => self.write_u32(*value),
+            Value::Int64(value) => {
+                self.align(8);
+                self.buf.extend_from_slice(&value.to_le_bytes());
+            },
+            Value::UInt64(value) => {
+                self.align(8);
+                self.buf.extend_from_slice(&value.to_le_bytes());
+            },
+            Value::Double(value) => {
+                self.align(8);
+                self.buf.extend_from_slice(&value.to_le_bytes());
+            },
+            Value::String(value) | Value::ObjectPath(value) => self.write_string(value),
+            Value::Signature(value) => self.write_signature(value),
+            Value::Array { signature, items } => {
+                self.write_u32(0);
+                let len_pos = self.buf.len() - 4;
+                self.align(alignment(signature.as_bytes().first().copied().unwrap_or(b'y')));
+                let start = self.buf.len();
+                items.iter().for_each(|item| self.write_value(item));
+                let len = (self.buf.len() - start) as u32;
+                self.buf[len_pos..len_pos + 4].copy_from_slice(&len.to_le_bytes());
+            },
+            Value::Struct(fields) => {
+                self.align(8);
+                fields.iter().for_each(|field| self.write_value(field));
+            },
+            Value::DictEntry(key, value) => {
+                self.align(8);
+                self.write_value(key);
+                self.write_value(value);
+            },
+            Value::Variant(value) => {
+                self.write_signature(&value.signature());
+                self.write_value(value);
+            },
+        }
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn align(&mut self, alignment: usize) -> Result<(), Error> {
+        let pos = self.pos.next_multiple_of(alignment);
+        if pos > self.buf.len() {
+            return Err(Error::Malformed("truncated message"));
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        self.align(N)?;
+        let bytes =
+            self.buf.get(self.pos..self.pos + N).ok_or(Error::Malformed("truncated message"))?;
+        self.pos += N;
+        let mut bytes: [u8; N] = bytes.try_into().unwrap();
+        if self.big_endian {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&[u8], Error> {
+        let end = self.pos.checked_add(len + 1).filter(|end| *end <= self.buf.len());
+        let end = end.ok_or(Error::Malformed("truncated string"))?;
+        let bytes = &self.buf[self.pos..end - 1];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_string(&mut self) -> Result<String, Error> {
+        let len = self.read_u32()? as usize;
+        let bytes = self.read_bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| Error::Malformed("invalid UTF-8 string"))
+    }
+
+    fn read_signature(&mut self) -> Result<String, Error> {
+        let len = self.read_u8()? as usize;
+        let bytes = self.read_bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| Error::Malformed("invalid signature"))
+    }
+
+    /// Read a value of the single complete type `ty`.
+    fn read_value(&mut self, ty: &[u8], depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::Malformed("value nested too deeply"));
+        }
+
+        let value = match ty[0] {
+            b'y' => Value::Byte(self.read_u8()?),
+            b'b' => Value::Bool(self.read_u32()? != 0),
+            b'n' => Value::Int16(i16::from_le_bytes(self.take()?)),
+            b'q' => Value::UInt16(u16::from_le_bytes(self.take()?)),
+            b'i' => Value::Int32(i32::from_le_bytes(self.take()?)),
+            b'u' => Value::UInt32(self.read_u32()?),
+            b'x' => Value::Int64(i64::from_le_bytes(self.take()?)),
+            b't' => Value::UInt64(u64::from_le_bytes(self.take()?)),
+            b'd' => Value::Double(f64::from_le_bytes(self.take()?)),
+            b'h' => Value::UnixFd(self.read_u32()?),
+            b's' => Value::String(self.read_string()?),
+            b'o' => Value::ObjectPath(self.read_string()?),
+            b'g' => Value::Signature(self.read_signature()?),
+            b'a' => {
+                let element = &ty[1..];
+                let len = self.read_u32()? as usize;
+                self.align(alignment(element[0]))?;
+                let end = self.pos.checked_add(len).filter(|end| *end <= self.buf.len());
+                let end = end.ok_or(Error::Malformed("truncated array"))?;
+                let mut items = Vec::new();
+                while self.pos < end {
+                    items.push(self.read_value(element, depth + 1)?);
+                }
+                let signature = String::from_utf8_lossy(element).into_owned();
+                Value::Array { signature, items }
+            },
+            b'(' => {
+                self.align(8)?;
+                let mut fields = Vec::new();
+                let mut rest = &ty[1..ty.len() - 1];
+                while !rest.is_empty() {
+                    let (field, next) = split_type(rest, depth + 1)?;
+                    fields.push(self.read_value(field, depth + 1)?);
+                    rest = next;
+                }
+                Value::Struct(fields)
+            },
+            b'{' => {
+                self.align(8)?;
+                let (key, rest) = split_type(&ty[1..ty.len() - 1], depth + 1)?;
+                let (value, _) = split_type(rest, depth + 1)?;
+                let key = self.read_value(key, depth + 1)?;
+                let value = self.read_value(value, depth + 1)?;
+                Value::DictEntry(Box::new(key), Box::new(value))
+            },
+            b'v' => {
+                let signature = self.read_signature()?;
+                let (inner, rest) = split_type(signature.as_bytes(), depth + 1)?;
+                if !rest.is_empty() {
+                    return Err(Error::Malformed("variant with multiple types"));
+                }
+                Value::Variant(Box::new(self.read_value(inner, depth + 1)?))
+            },
+            _ => return Err(Error::Malformed("invalid signature")),
+        };
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let message = Message::method_call(
+            "org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Settings",
+            "ReadAll",
+        )
+        .with_body(vec![
+            Value::string_array(["org.freedesktop.appearance"]),
+            Value::dict("s", "v", [(
+                Value::String("accent-color".into()),
+                Value::Variant(Box::new(Value::Struct(vec![
+                    Value::Double(0.2),
+                    Value::Double(0.4),
+                    Value::Double(1.0),
+                ]))),
+            )]),
+            Value::Array { signature: "x".into(), items: Vec::new() },
+            Value::Byte(7),
+            Value::Int64(-3),
+            Value::Bool(true),
+            Value::Signature("a{sv}".into()),
+        ]);
+
+        let data = message.encode(42);
+        assert_eq!((data.len() - message_body_len(&data)) % 8, 0);
+        assert_eq!(Message::encoded_len(&data).unwrap(), Some(data.len()));
+        assert_eq!(Message::encoded_len(&data[..15]).unwrap(), None);
+
+        let decoded = Message::decode(&data).unwrap();
+        assert_eq!(decoded, Message { serial: 42, ..message });
+    }
+
+    #[test]
+    fn decode_big_endian() {
+        // A signal with the body `u 1`, as sent by a big endian peer.
+        #[rustfmt::skip]
+        let data = [
+            b'B', 4, 0, 1, 0, 0, 0, 4, 0, 0, 0, 9, 0, 0, 0, 18,
+            8, 1, b'g', 0, 1, b'u', 0, 0,
+            3, 1, b's', 0, 0, 0, 0, 1, b'X', 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 1,
+        ];
+
+        let message = Message::decode(&data).unwrap();
+        assert_eq!(message.message_type, MessageType::Signal);
+        assert_eq!(message.serial, 9);
+        assert_eq!(message.member.as_deref(), Some("X"));
+        assert_eq!(message.body, vec![Value::UInt32(1)]);
+    }
+
+    #[test]
+    fn reject_malformed() {
+        assert!(split_type(b"a", 0).is_err());
+        assert!(split_type(b"(ii", 0).is_err());
+        assert!(split_type("a".repeat(100).as_bytes(), 0).is_err());
+
+        let mut data = Message::signal("/", "a.b", "C").with_body(vec![Value::UInt32(1)]).encode(1);
+        let len = data.len();
+        data.truncate(len - 2);
+        assert!(Message::decode(&data).is_err());
+    }
+
+    fn message_body_len(data: &[u8]) -> usize {
+        u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize
+    }
+}
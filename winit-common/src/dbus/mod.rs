@@ -0,0 +1,73 @@
+//! A minimal D-Bus client.
+//!
+//! Only what winit needs from the desktop is implemented: connecting to a bus over a unix socket,
+//! calling methods and receiving signals.
+//!
+//! The existing crates don't fit the backends: `zbus` brings an async executor, proc-macros and a
+//! few dozen dependencies into every X11 and Wayland build for a handful of method calls, and
+//! `dbus` links `libdbus` at build time, while the backends load the system libraries at runtime.
+//! The subset implemented here is small: only the `EXTERNAL` authentication, and a decoder which
+//! bounds the size of the messages and the nesting of the values, and rejects any truncated or
+//! malformed data with [`Error::Malformed`] instead of trusting the lengths it reads.
+
+use std::{fmt, io};
+
+mod connection;
+mod ime;
+mod message;
+mod settings;
+
+pub use connection::Connection;
+pub use ime::{ImeFramework, InputContext, InputContextEvent};
+pub use message::{Message, MessageType, NO_REPLY_EXPECTED, Value};
+pub use settings::SettingsPortal;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading from or writing to the bus failed.
+    Io(io::Error),
+    /// The bus address is not supported.
+    Address(String),
+    /// The bus refused the authentication.
+    Auth,
+    /// The peer sent data not following the D-Bus wire format.
+    Malformed(&'static str),
+    /// A method call returned an error.
+    Method { name: String, message: String },
+    /// A method call wasn't answered in time.
+    Timeout,
+    /// The bus closed the connection.
+    Disconnected,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "D-Bus I/O error: {err}"),
+            Self::Address(address) => write!(f, "unsupported D-Bus address: {address}"),
+            Self::Auth => f.write_str("D-Bus authentication failed"),
+            Self::Malformed(reason) => write!(f, "malformed D-Bus message: {reason}"),
+            Self::Method { name, message } => {
+                write!(f, "D-Bus method call failed: {name}: {message}")
+            },
+            Self::Timeout => f.write_str("D-Bus method call timed out"),
+            Self::Disconnected => f.write_str("D-Bus connection closed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
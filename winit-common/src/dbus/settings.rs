@@ -0,0 +1,257 @@
+//! The `org.freedesktop.portal.Settings` interface of the xdg-desktop-portal.
+
+use std::os::fd::{AsFd, BorrowedFd};
+use std::time::Duration;
+
+use winit_core::event_loop::SystemSettings;
+
+use super::{Connection, Error, Message, MessageType, Value};
+
+const PORTAL_NAME: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SETTINGS_INTERFACE: &str = "org.freedesktop.portal.Settings";
+
+const APPEARANCE: &str = "org.freedesktop.appearance";
+const GNOME_INTERFACE: &str = "org.gnome.desktop.interface";
+const GNOME_MOUSE: &str = "org.gnome.desktop.peripherals.mouse";
+
+/// Reads the desktop settings from the portal and follows their changes.
+#[derive(Debug)]
+pub struct SettingsPortal {
+    connection: Connection,
+    settings: PortalSettings,
+    /// Serial of the pending `ReadAll` call.
+    read_all_serial: Option<u32>,
+}
+
+impl SettingsPortal {
+    /// Subscribe to the setting changes and request the current values.
+    ///
+    /// Nothing is waited for, the values are available once [`Self::dispatch()`] processed the
+    /// reply, so a slow or broken bus or portal doesn't stall the caller.
+    pub fn new(mut connection: Connection) -> Result<Self, Error> {
+        connection.add_match(&format!(
+            "type='signal',sender='{PORTAL_NAME}',path='{PORTAL_PATH}',interface='\
+             {SETTINGS_INTERFACE}',member='SettingChanged'"
+        ))?;
+
+        let read_all =
+            Message::method_call(PORTAL_NAME, PORTAL_PATH, SETTINGS_INTERFACE, "ReadAll")
+                .with_body(vec![Value::string_array([APPEARANCE, GNOME_INTERFACE, GNOME_MOUSE])]);
+        let read_all_serial = Some(connection.send(&read_all)?);
+
+        Ok(Self { connection, settings: PortalSettings::default(), read_all_serial })
+    }
+
+    /// The settings read from the portal so far.
+    pub fn system_settings(&self) -> SystemSettings {
+        self.settings.system_settings()
+    }
+
+    /// Process the received messages without blocking, returning whether the settings changed.
+    pub fn dispatch(&mut self) -> Result<bool, Error> {
+        let old_settings = self.system_settings();
+
+        while let Some(message) = self.connection.try_recv()? {
+            if self.read_all_serial.is_some() && message.reply_serial == self.read_all_serial {
+                self.read_all_serial = None;
+                if message.message_type == MessageType::Error {
+                    tracing::warn!(
+                        "failed to read the portal settings: {}",
+                        message.error_name.as_deref().unwrap_or_default()
+                    );
+                    continue;
+                }
+
+                let namespaces = message.body.first().into_iter().flat_map(Value::dict_entries);
+                for (namespace, values) in namespaces {
+                    for (key, value) in values.dict_entries() {
+                        self.settings.set(
+                            namespace.as_str().unwrap_or_default(),
+                            key.as_str().unwrap_or_default(),
+                            value,
+                        );
+                    }
+                }
+            } else if message.is_signal(SETTINGS_INTERFACE, "SettingChanged") {
+                if let [namespace, key, value] = &message.body[..] {
+                    self.settings.set(
+                        namespace.as_str().unwrap_or_default(),
+                        key.as_str().unwrap_or_default(),
+                        value,
+                    );
+                }
+            }
+        }
+
+        Ok(old_settings != self.system_settings())
+    }
+}
+
+impl AsFd for SettingsPortal {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.connection.as_fd()
+    }
+}
+
+/// The raw portal values winit cares about.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct PortalSettings {
+    accent_color: Option<[u8; 3]>,
+    reduced_motion: Option<bool>,
+    enable_animations: Option<bool>,
+    cursor_size: Option<u32>,
+    cursor_blink: Option<bool>,
+    /// Length of a whole blink cycle in milliseconds.
+    cursor_blink_time: Option<u32>,
+    double_click: Option<u32>,
+    drag_threshold: Option<u32>,
+}
+
+impl PortalSettings {
+    fn set(&mut self, namespace: &str, key: &str, value: &Value) {
+        let unsigned = || value.as_i32().and_then(|value| u32::try_from(value).ok());
+        match (namespace, key) {
+            (APPEARANCE, "accent-color") => self.accent_color = accent_color(value),
+            (APPEARANCE, "reduced-motion") => {
+                // 0: no preference, 1: reduce motion.
+                self.reduced_motion = value.as_u32().map(|value| value == 1)
+            },
+            (GNOME_INTERFACE, "enable-animations") => self.enable_animations = value.as_bool(),
+            (GNOME_INTERFACE, "cursor-size") => self.cursor_size = unsigned(),
+            (GNOME_INTERFACE, "cursor-blink") => self.cursor_blink = value.as_bool(),
+            (GNOME_INTERFACE, "cursor-blink-time") => self.cursor_blink_time = unsigned(),
+            (GNOME_MOUSE, "double-click") => self.double_click = unsigned(),
+            (GNOME_MOUSE, "drag-threshold") => self.drag_threshold = unsigned(),
+            _ => (),
+        }
+    }
+
+    fn system_settings(&self) -> SystemSettings {
+        let millis = |value: Option<u32>| value.map(|value| Duration::from_millis(value.into()));
+
+        // A blink cycle is made of an "on" and an "off" phase.
+        let caret_blink_interval = match self.cursor_blink {
+            Some(false) => Some(Duration::ZERO),
+            _ => millis(self.cursor_blink_time).map(|cycle| cycle / 2),
+        };
+
+        SystemSettings {
+            double_click_time: millis(self.double_click),
+            drag_threshold: self.drag_threshold,
+            caret_blink_interval,
+            cursor_size: self.cursor_size,
+            reduce_motion: self.reduced_motion.or(self.enable_animations.map(|enabled| !enabled)),
+            accent_color: self.accent_color,
+            ..Default::default()
+        }
+    }
+}
+
+/// Parse the `(ddd)` accent color, components outside of `0.0..=1.0` mean it is unset.
+fn accent_color(value: &Value) -> Option<[u8; 3]> {
+    let [r, g, b] = value.as_slice()? else {
+        return None;
+    };
+
+    let component = |value: &Value| {
+        value
+            .as_f64()
+            .filter(|value| (0.0..=1.0).contains(value))
+            .map(|value| (value * 255.).round() as u8)
+    };
+    Some([component(r)?, component(g)?, component(b)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::dbus::connection::tests::MockBus;
+
+    fn variant(value: Value) -> Value {
+        Value::Variant(Box::new(value))
+    }
+
+    #[test]
+    fn read_and_follow_changes() {
+        let bus = MockBus::new(|call| {
+            assert_eq!(call.destination.as_deref(), Some(PORTAL_NAME));
+            assert_eq!(call.member.as_deref(), Some("ReadAll"));
+
+            let appearance = Value::dict("s", "v", [
+                (
+                    Value::String("accent-color".into()),
+                    variant(Value::Struct(vec![
+                        Value::Double(1.0),
+                        Value::Double(0.0),
+                        Value::Double(0.2),
+                    ])),
+                ),
+                (Value::String("color-scheme".into()), variant(Value::UInt32(1))),
+            ]);
+            let interface = Value::dict("s", "v", [
+                (Value::String("cursor-size".into()), variant(Value::Int32(32))),
+                (Value::String("cursor-blink-time".into()), variant(Value::Int32(1200))),
+                (Value::String("enable-animations".into()), variant(Value::Bool(false))),
+            ]);
+            let mouse = Value::dict("s", "v", [(
+                Value::String("double-click".into()),
+                variant(Value::Int32(400)),
+            )]);
+            let namespaces = Value::dict("s", "a{sv}", [
+                (Value::String(APPEARANCE.into()), appearance),
+                (Value::String(GNOME_INTERFACE.into()), interface),
+                (Value::String(GNOME_MOUSE.into()), mouse),
+            ]);
+
+            let changed = Message::signal(PORTAL_PATH, SETTINGS_INTERFACE, "SettingChanged")
+                .with_body(vec![
+                    Value::String(GNOME_INTERFACE.into()),
+                    Value::String("cursor-blink".into()),
+                    variant(Value::Bool(false)),
+                ]);
+
+            vec![Message::method_return(call).with_body(vec![namespaces]), changed]
+        });
+
+        let connection = Connection::open(&bus.address).unwrap();
+        let mut portal = SettingsPortal::new(connection).unwrap();
+        assert_eq!(portal.system_settings(), SystemSettings::default());
+
+        let expected = SystemSettings {
+            double_click_time: Some(Duration::from_millis(400)),
+            caret_blink_interval: Some(Duration::ZERO),
+            cursor_size: Some(32),
+            reduce_motion: Some(true),
+            accent_color: Some([255, 0, 51]),
+            ..Default::default()
+        };
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut changed = false;
+        while portal.system_settings() != expected {
+            assert!(Instant::now() < deadline, "settings not received");
+            changed |= portal.dispatch().unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert!(changed);
+        assert!(!portal.dispatch().unwrap());
+    }
+
+    #[test]
+    fn unset_accent_color() {
+        let mut settings = PortalSettings::default();
+        let unset =
+            Value::Struct(vec![Value::Double(-1.0), Value::Double(-1.0), Value::Double(-1.0)]);
+        settings.set(APPEARANCE, "accent-color", &variant(unset));
+        assert_eq!(settings.accent_color, None);
+
+        settings.set(GNOME_INTERFACE, "cursor-blink-time", &Value::Int32(1000));
+        assert_eq!(
+            settings.system_settings().caret_blink_interval,
+            Some(Duration::from_millis(500))
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Timestamps of the input events of X11 and Wayland.
+
+use std::time::Duration;
+
+use winit_core::event::EventTimestamp;
+
+/// The oldest an event can be for its time to be taken from `CLOCK_MONOTONIC`.
+const MAX_AGE: Duration = Duration::from_secs(60);
+
+/// The timestamp of an input event with the given time from the display server.
+///
+/// The time is in milliseconds with an undefined base, truncated to 32 bits. X servers and the
+/// compositors take it from the `CLOCK_MONOTONIC` used by libinput, in which case it tells how long
+/// ago the event happened. Otherwise the event is assumed to have happened when it was received.
+pub fn event_timestamp(time: u32) -> EventTimestamp {
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+    let now = Duration::new(now.tv_sec as u64, now.tv_nsec as u32);
+    from_monotonic(time, now)
+}
+
+fn from_monotonic(time: u32, now: Duration) -> EventTimestamp {
+    // Extend the time to 64 bits with the wrap-arounds of the clock, which is consistent as long as
+    // the server clock runs alongside `CLOCK_MONOTONIC`, even if it's another one.
+    let now_millis = now.as_millis() as i64;
+    let age = (now_millis as u32).wrapping_sub(time) as i32;
+    let extended = match now_millis - age as i64 {
+        extended @ 0.. => extended as u64,
+        _ => time as u64,
+    };
+
+    // Events from the future or from too long ago mean the server uses another clock.
+    let age = Duration::from_millis(age.max(0) as u64);
+    let age = if age <= MAX_AGE { age } else { Duration::ZERO };
+    EventTimestamp::new(Duration::from_millis(extended), age)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn extend_and_age() {
+        let after = |timestamp: EventTimestamp, age: Duration| {
+            let instant = Instant::now() - age;
+            let to_instant = timestamp.to_instant();
+            to_instant <= instant && instant - to_instant < Duration::from_millis(100)
+        };
+
+        // The same clock, after it wrapped around.
+        let now = Duration::from_millis((1 << 32) + 5_000);
+        let timestamp = from_monotonic(4_000, now);
+        assert_eq!(timestamp.time(), Duration::from_millis((1 << 32) + 4_000));
+        assert!(after(timestamp, Duration::from_secs(1)));
+
+        // Just before the wrap around.
+        let timestamp = from_monotonic(u32::MAX, now);
+        assert_eq!(timestamp.time(), Duration::from_millis(u32::MAX as u64));
+        assert!(after(timestamp, Duration::from_millis(5_001)));
+
+        // Another clock keeps its time, received now.
+        let timestamp = from_monotonic(1_000_000_000, Duration::from_millis(5_000));
+        assert_eq!(timestamp.time(), Duration::from_millis(1_000_000_000));
+        assert!(after(timestamp, Duration::ZERO));
+        let later = from_monotonic(1_000_000_016, Duration::from_millis(5_016));
+        assert_eq!(later.time() - timestamp.time(), Duration::from_millis(16));
+    }
+}
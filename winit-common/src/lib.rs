@@ -2,10 +2,20 @@
 
 #![warn(clippy::exhaustive_enums)]
 
+#[cfg(feature = "app-sources")]
+pub mod app_sources;
+#[cfg(feature = "click-count")]
+pub mod click_count;
+#[cfg(feature = "coalescing")]
+pub mod coalescing;
 #[cfg(feature = "core-foundation")]
 pub mod core_foundation;
+#[cfg(feature = "dbus")]
+pub mod dbus;
 #[cfg(feature = "event-handler")]
 pub mod event_handler;
+#[cfg(feature = "event-timestamp")]
+pub mod event_timestamp;
 #[cfg(feature = "foundation")]
 pub mod foundation;
 #[cfg(feature = "xkb")]
@@ -7,6 +7,7 @@ use std::os::unix::ffi::OsStringExt;
 use std::ptr::NonNull;
 
 use smol_str::SmolStr;
+use winit_core::event::Compose;
 use xkbcommon_dl::{
     xkb_compose_compile_flags, xkb_compose_feed_result, xkb_compose_state, xkb_compose_state_flags,
     xkb_compose_status, xkb_compose_table, xkb_keysym_t,
@@ -120,3 +121,62 @@ pub enum ComposeStatus {
     Ignored,
     None,
 }
+
+/// The text shown for the pending sequence, and how the last key event changed it.
+#[derive(Debug, Default)]
+pub struct ComposeSequence {
+    text: String,
+    change: Option<Compose>,
+}
+
+impl ComposeSequence {
+    /// Add the representation of a key continuing the sequence.
+    pub fn push(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.change = Some(Compose::Pending { sequence: self.text.clone() });
+    }
+
+    /// End the pending sequence, if any, reporting it with `change`.
+    pub fn end(&mut self, change: Compose) {
+        if self.clear() {
+            self.change = Some(change);
+        }
+    }
+
+    /// Forget the pending sequence without reporting it, returning whether there was one.
+    pub fn clear(&mut self) -> bool {
+        let pending = !self.text.is_empty();
+        self.text.clear();
+        self.change = None;
+        pending
+    }
+
+    pub fn take_change(&mut self) -> Option<Compose> {
+        self.change.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compose_sequence() {
+        let mut sequence = ComposeSequence::default();
+        sequence.end(Compose::Finished);
+        assert_eq!(sequence.take_change(), None);
+
+        sequence.push("·");
+        sequence.push("o");
+        assert_eq!(sequence.take_change(), Some(Compose::Pending { sequence: "·o".into() }));
+        assert_eq!(sequence.take_change(), None);
+
+        sequence.end(Compose::Finished);
+        assert_eq!(sequence.take_change(), Some(Compose::Finished));
+
+        sequence.push("´");
+        assert!(sequence.clear());
+        assert!(!sequence.clear());
+        assert_eq!(sequence.take_change(), None);
+    }
+}
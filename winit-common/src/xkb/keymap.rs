@@ -1,8 +1,10 @@
 //! XKB keymap.
 
-use std::ffi::c_char;
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::mem;
 use std::ops::Deref;
 use std::ptr::{self, NonNull};
+use std::sync::LazyLock;
 
 use winit_core::keyboard::{
     Key, KeyCode, KeyLocation, NamedKey, NativeKey, NativeKeyCode, PhysicalKey,
@@ -19,7 +21,7 @@ use {memmap2::MmapOptions, std::os::unix::io::OwnedFd};
 
 #[cfg(feature = "x11")]
 use super::XKBXH;
-use super::{XKBH, XkbContext};
+use super::{KeymapSource, XKBH, XkbContext};
 
 /// Map the raw X11-style keycode to the `KeyCode` enum.
 ///
@@ -977,6 +979,7 @@ pub fn keysym_location(keysym: u32) -> KeyLocation {
 pub struct XkbKeymap {
     keymap: NonNull<xkb_keymap>,
     _mods_indices: ModsIndices,
+    layout_names: Vec<String>,
     pub _core_keyboard_id: i32,
 }
 
@@ -998,6 +1001,48 @@ impl XkbKeymap {
         Some(Self::new_inner(keymap, 0))
     }
 
+    /// Compile the keymap supplied by the application.
+    pub fn from_source(context: &XkbContext, source: &KeymapSource) -> Option<Self> {
+        let keymap = match source {
+            KeymapSource::Names { rules, model, layout, variant, options } => {
+                let names = [rules, model, layout, variant, options]
+                    .map(|name| CString::new(name.as_str()).ok());
+                let [Some(rules), Some(model), Some(layout), Some(variant), Some(options)] = names
+                else {
+                    return None;
+                };
+
+                let names = xkb::xkb_rule_names {
+                    rules: rules.as_ptr(),
+                    model: model.as_ptr(),
+                    layout: layout.as_ptr(),
+                    variant: variant.as_ptr(),
+                    options: options.as_ptr(),
+                };
+                unsafe {
+                    (XKBH.xkb_keymap_new_from_names)(
+                        context.as_ptr(),
+                        &names,
+                        xkb_keymap_compile_flags::XKB_KEYMAP_COMPILE_NO_FLAGS,
+                    )
+                }
+            },
+            KeymapSource::String(string) => {
+                let string = CString::new(string.as_str()).ok()?;
+                unsafe {
+                    (XKBH.xkb_keymap_new_from_string)(
+                        context.as_ptr(),
+                        string.as_ptr(),
+                        xkb::xkb_keymap_format::XKB_KEYMAP_FORMAT_TEXT_V1,
+                        xkb_keymap_compile_flags::XKB_KEYMAP_COMPILE_NO_FLAGS,
+                    )
+                }
+            },
+        };
+
+        Some(Self::new_inner(NonNull::new(keymap)?, 0))
+    }
+
     #[cfg(feature = "x11")]
     pub fn from_x11_keymap(
         context: &XkbContext,
@@ -1026,18 +1071,24 @@ impl XkbKeymap {
             mod3: mod_index_for_name(keymap, b"Mod3\0"),
             logo: mod_index_for_name(keymap, xkb::XKB_MOD_NAME_LOGO),
             mod5: mod_index_for_name(keymap, b"Mod5\0"),
+            scroll: mod_index_for_name(keymap, b"ScrollLock\0"),
         };
 
-        Self { keymap, _mods_indices: mods_indices, _core_keyboard_id }
+        let layout_names = layout_names(keymap);
+        Self { keymap, _mods_indices: mods_indices, layout_names, _core_keyboard_id }
+    }
+
+    /// The names of the layouts, indexed by the XKB group.
+    pub fn layout_names(&self) -> &[String] {
+        &self.layout_names
     }
 
-    #[cfg(feature = "x11")]
     pub fn mods_indices(&self) -> ModsIndices {
         self._mods_indices
     }
 
     pub fn first_keysym_by_level(
-        &mut self,
+        &self,
         layout: xkb_layout_index_t,
         keycode: xkb_keycode_t,
     ) -> xkb_keysym_t {
@@ -1062,6 +1113,18 @@ impl XkbKeymap {
     }
 }
 
+impl Clone for XkbKeymap {
+    fn clone(&self) -> Self {
+        unsafe { (XKBH.xkb_keymap_ref)(self.keymap.as_ptr()) };
+        Self {
+            keymap: self.keymap,
+            _mods_indices: self._mods_indices,
+            layout_names: self.layout_names.clone(),
+            _core_keyboard_id: self._core_keyboard_id,
+        }
+    }
+}
+
 impl Drop for XkbKeymap {
     fn drop(&mut self) {
         unsafe {
@@ -1090,8 +1153,44 @@ pub struct ModsIndices {
     pub mod3: Option<xkb_mod_index_t>,
     pub logo: Option<xkb_mod_index_t>,
     pub mod5: Option<xkb_mod_index_t>,
+    /// The `ScrollLock` virtual modifier.
+    pub scroll: Option<xkb_mod_index_t>,
 }
 
+/// Read the group names of the keymap.
+fn layout_names(keymap: NonNull<xkb_keymap>) -> Vec<String> {
+    let num_layouts = unsafe { (XKBH.xkb_keymap_num_layouts)(keymap.as_ptr()) };
+    (0..num_layouts)
+        .map(|layout| {
+            let name = XKB_KEYMAP_LAYOUT_GET_NAME
+                .map(|get_name| unsafe { get_name(keymap.as_ptr(), layout) })
+                .unwrap_or(ptr::null());
+            if name.is_null() {
+                String::new()
+            } else {
+                unsafe { CStr::from_ptr(name) }.to_string_lossy().into_owned()
+            }
+        })
+        .collect()
+}
+
+type LayoutGetName = unsafe extern "C" fn(*mut xkb_keymap, xkb_layout_index_t) -> *const c_char;
+
+/// `xkb_keymap_layout_get_name`, which `xkbcommon-dl` doesn't expose.
+static XKB_KEYMAP_LAYOUT_GET_NAME: LazyLock<Option<LayoutGetName>> = LazyLock::new(|| {
+    // Look the function up in the libxkbcommon already loaded by `xkbcommon_handle()`.
+    LazyLock::force(&XKBH);
+    let library = [c"libxkbcommon.so.0", c"libxkbcommon.so"].into_iter().find_map(|name| {
+        let library = unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_LAZY | libc::RTLD_NOLOAD) };
+        (!library.is_null()).then_some(library)
+    })?;
+
+    let symbol = unsafe { libc::dlsym(library, c"xkb_keymap_layout_get_name".as_ptr()) };
+    // The library stays loaded through the handle of `xkbcommon-dl`.
+    unsafe { libc::dlclose(library) };
+    (!symbol.is_null()).then(|| unsafe { mem::transmute::<*mut c_void, LayoutGetName>(symbol) })
+});
+
 fn mod_index_for_name(keymap: NonNull<xkb_keymap>, name: &[u8]) -> Option<xkb_mod_index_t> {
     unsafe {
         let mod_index =
@@ -1099,3 +1198,30 @@ fn mod_index_for_name(keymap: NonNull<xkb_keymap>, name: &[u8]) -> Option<xkb_mo
         if mod_index == XKB_MOD_INVALID { None } else { Some(mod_index) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_source() {
+        let context = XkbContext::new().unwrap();
+
+        let names = KeymapSource::Names {
+            rules: "evdev".into(),
+            model: "pc105".into(),
+            layout: "us,de".into(),
+            variant: String::new(),
+            options: String::new(),
+        };
+        let keymap = XkbKeymap::from_source(&context, &names).unwrap();
+        assert_eq!(keymap.layout_names(), ["English (US)", "German"]);
+
+        let string = KeymapSource::String(crate::xkb::lookup::tests::KEYMAP.into());
+        let keymap = XkbKeymap::from_source(&context, &string).unwrap();
+        assert_eq!(keymap.layout_names(), ["English (US)", "Russian"]);
+
+        let invalid = KeymapSource::String("xkb_keymap {".into());
+        assert!(XkbKeymap::from_source(&context, &invalid).is_none());
+    }
+}
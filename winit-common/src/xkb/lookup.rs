@@ -0,0 +1,184 @@
+//! Key lookups outside of key events.
+
+use winit_core::keyboard::{Key, ModifiersState, PhysicalKey};
+use xkbcommon_dl::{xkb_keycode_t, xkb_keysym_t, xkb_layout_index_t, xkb_mod_mask_t};
+
+use super::keymap::{self, XkbKeymap};
+use super::state::XkbState;
+use super::{XKBH, keysym_to_utf8};
+
+/// The keymap and the active layout, to look up the keys without waiting for a key press.
+#[derive(Debug, Clone)]
+pub struct KeyLookup {
+    keymap: XkbKeymap,
+    layout: xkb_layout_index_t,
+}
+
+impl KeyLookup {
+    pub(super) fn new(keymap: XkbKeymap, layout: xkb_layout_index_t) -> Self {
+        Self { keymap, layout }
+    }
+
+    /// The key produced by the physical key with the modifiers on the active layout.
+    pub fn key_for_physical(&self, key: PhysicalKey, modifiers: ModifiersState) -> Option<Key> {
+        let keycode = keymap::physicalkey_to_scancode(key)? + 8;
+
+        let keysym = if modifiers.is_empty() {
+            self.keymap.first_keysym_by_level(self.layout, keycode)
+        } else {
+            let mut state = XkbState::new(&self.keymap)?;
+            state.update_modifiers(self.mod_mask(modifiers), 0, 0, 0, 0, self.layout);
+            state.get_one_sym_raw(keycode)
+        };
+
+        keysym_key(keysym)
+    }
+
+    /// The physical key and the modifiers producing the key on the active layout.
+    ///
+    /// Only the unmodified and the <kbd>Shift</kbd> levels are searched.
+    pub fn physical_for_key(&self, key: &Key) -> Option<(PhysicalKey, ModifiersState)> {
+        let (min_keycode, max_keycode) = unsafe {
+            (
+                (XKBH.xkb_keymap_min_keycode)(self.keymap.as_ptr()),
+                (XKBH.xkb_keymap_max_keycode)(self.keymap.as_ptr()),
+            )
+        };
+        let keycodes = || min_keycode..=max_keycode;
+
+        [ModifiersState::empty(), ModifiersState::SHIFT].into_iter().find_map(|modifiers| {
+            let mut state = XkbState::new(&self.keymap)?;
+            state.update_modifiers(self.mod_mask(modifiers), 0, 0, 0, 0, self.layout);
+            keycodes().find_map(|keycode: xkb_keycode_t| {
+                let physical_key = keymap::raw_keycode_to_physicalkey(keycode);
+                (keysym_key(state.get_one_sym_raw(keycode)).as_ref() == Some(key))
+                    .then_some((physical_key, modifiers))
+            })
+        })
+    }
+
+    fn mod_mask(&self, modifiers: ModifiersState) -> xkb_mod_mask_t {
+        let indices = self.keymap.mods_indices();
+        [
+            (ModifiersState::SHIFT, indices.shift),
+            (ModifiersState::CONTROL, indices.ctrl),
+            (ModifiersState::ALT, indices.alt),
+            (ModifiersState::META, indices.logo),
+        ]
+        .into_iter()
+        .filter(|(modifier, _)| modifiers.contains(*modifier))
+        .filter_map(|(_, index)| index)
+        .fold(0, |mask, index| mask | (1 << index))
+    }
+}
+
+/// The key for the keysym, falling back to the text it produces.
+fn keysym_key(keysym: xkb_keysym_t) -> Option<Key> {
+    if keysym == 0 {
+        return None;
+    }
+
+    match keymap::keysym_to_key(keysym) {
+        Key::Unidentified(native) => {
+            let text = keysym_to_utf8(&mut Vec::new(), keysym);
+            Some(text.map(Key::Character).unwrap_or(Key::Unidentified(native)))
+        },
+        key => Some(key),
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use winit_core::keyboard::{KeyCode, NamedKey};
+
+    use super::*;
+    use crate::xkb::{KeymapSource, XkbContext};
+
+    /// A self-contained keymap with an English and a Russian layout.
+    pub(in crate::xkb) const KEYMAP: &str = r#"xkb_keymap {
+    xkb_keycodes "test" {
+        minimum = 8;
+        maximum = 255;
+        <ESC> = 9;
+        <AC01> = 38;
+        <LFSH> = 50;
+    };
+    xkb_types "test" {
+        type "ONE_LEVEL" {
+            modifiers = none;
+            level_name[Level1] = "Any";
+        };
+        type "TWO_LEVEL" {
+            modifiers = Shift;
+            map[Shift] = Level2;
+            level_name[Level1] = "Base";
+            level_name[Level2] = "Shift";
+        };
+        type "ALPHABETIC" {
+            modifiers = Shift + Lock;
+            map[Shift] = Level2;
+            map[Lock] = Level2;
+            level_name[Level1] = "Base";
+            level_name[Level2] = "Caps";
+        };
+    };
+    xkb_compat "test" {
+        interpret Any + AnyOf(all) {
+            action = SetMods(modifiers = modMapMods, clearLocks);
+        };
+    };
+    xkb_symbols "test" {
+        name[Group1] = "English (US)";
+        name[Group2] = "Russian";
+        key <ESC> { [ Escape ] };
+        key <AC01> {
+            symbols[Group1] = [ a, A ],
+            symbols[Group2] = [ Cyrillic_ef, Cyrillic_EF ]
+        };
+        key <LFSH> { [ Shift_L ] };
+        modifier_map Shift { <LFSH> };
+    };
+};
+"#;
+
+    #[test]
+    fn lookup_keys() {
+        let context = XkbContext::new().unwrap();
+        let keymap =
+            XkbKeymap::from_source(&context, &KeymapSource::String(KEYMAP.into())).unwrap();
+        let key_a = PhysicalKey::Code(KeyCode::KeyA);
+
+        let english = KeyLookup::new(keymap.clone(), 0);
+        assert_eq!(
+            english.key_for_physical(key_a, ModifiersState::empty()),
+            Some(Key::Character("a".into()))
+        );
+        assert_eq!(
+            english.key_for_physical(key_a, ModifiersState::SHIFT),
+            Some(Key::Character("A".into()))
+        );
+        assert_eq!(
+            english.key_for_physical(PhysicalKey::Code(KeyCode::Escape), ModifiersState::empty()),
+            Some(Key::Named(NamedKey::Escape))
+        );
+        assert_eq!(
+            english.key_for_physical(PhysicalKey::Code(KeyCode::KeyB), ModifiersState::empty()),
+            None
+        );
+        assert_eq!(
+            english.physical_for_key(&Key::Character("A".into())),
+            Some((key_a, ModifiersState::SHIFT))
+        );
+
+        let russian = KeyLookup::new(keymap, 1);
+        assert_eq!(
+            russian.key_for_physical(key_a, ModifiersState::empty()),
+            Some(Key::Character("ф".into()))
+        );
+        assert_eq!(
+            russian.physical_for_key(&Key::Character("ф".into())),
+            Some((key_a, ModifiersState::empty()))
+        );
+        assert_eq!(russian.physical_for_key(&Key::Character("a".into())), None);
+    }
+}
@@ -7,8 +7,8 @@ use std::sync::LazyLock;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use smol_str::SmolStr;
-use winit_core::event::{ElementState, KeyEvent};
-use winit_core::keyboard::{Key, KeyLocation};
+use winit_core::event::{Compose, ElementState, KeyEvent};
+use winit_core::keyboard::{Key, KeyLocation, KeyboardLayouts};
 use xkbcommon_dl::{
     self as xkb, XkbCommon, XkbCommonCompose, xkb_compose_status, xkb_context, xkb_context_flags,
     xkbcommon_compose_handle, xkbcommon_handle,
@@ -18,13 +18,15 @@ use {x11_dl::xlib_xcb::xcb_connection_t, xkbcommon_dl::x11::xkbcommon_x11_handle
 
 mod compose;
 mod keymap;
+mod lookup;
 mod state;
 
-use compose::{ComposeStatus, XkbComposeState, XkbComposeTable};
+use compose::{ComposeSequence, ComposeStatus, XkbComposeState, XkbComposeTable};
 use keymap::XkbKeymap;
 #[cfg(feature = "x11")]
 pub use keymap::raw_keycode_to_physicalkey;
 pub use keymap::{physicalkey_to_scancode, scancode_to_physicalkey};
+pub use lookup::KeyLookup;
 pub use state::XkbState;
 
 // TODO: Wire this up without using a static `AtomicBool`.
@@ -47,6 +49,19 @@ pub enum Error {
     XKBNotFound,
 }
 
+/// A keymap supplied by the application, used instead of the one of the display server.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum KeymapSource {
+    /// Compile the keymap from RMLVO names, as in `setxkbmap`.
+    ///
+    /// Empty names select the defaults of libxkbcommon, which honor the `XKB_DEFAULT_*`
+    /// environment variables.
+    Names { rules: String, model: String, layout: String, variant: String, options: String },
+    /// A keymap in the XKB text format, as printed by `xkbcli compile-keymap`.
+    String(String),
+}
+
 #[derive(Debug)]
 pub struct Context {
     // NOTE: field order matters.
@@ -57,6 +72,9 @@ pub struct Context {
     compose_state1: Option<XkbComposeState>,
     compose_state2: Option<XkbComposeState>,
     _compose_table: Option<XkbComposeTable>,
+    compose_sequence: ComposeSequence,
+    /// Whether the keymap was supplied by the application, ignoring the one of the server.
+    fixed_keymap: bool,
     context: XkbContext,
     scratch_buffer: Vec<u8>,
 }
@@ -87,6 +105,8 @@ impl Context {
             #[cfg(feature = "x11")]
             core_keyboard_id: 0,
             _compose_table: compose_table,
+            compose_sequence: ComposeSequence::default(),
+            fixed_keymap: false,
             context,
             scratch_buffer: Vec::with_capacity(8),
         })
@@ -125,10 +145,32 @@ impl Context {
         self.keymap.as_mut()
     }
 
+    /// Use the keymap supplied by the application from now on, the server only providing the
+    /// modifier state.
+    ///
+    /// The modifier masks of the server are applied as is, so the keymap should map the real
+    /// modifiers the usual way.
+    pub fn set_fixed_keymap(&mut self, source: &KeymapSource) {
+        let keymap = XkbKeymap::from_source(&self.context, source);
+        let state = keymap.as_ref().and_then(XkbState::new);
+        let (Some(keymap), Some(state)) = (keymap, state) else {
+            tracing::warn!("failed to compile the application keymap, using the server one");
+            return;
+        };
+
+        self.state = Some(state);
+        self.keymap = Some(keymap);
+        self.fixed_keymap = true;
+    }
+
     #[cfg(feature = "wayland")]
     pub fn set_keymap_from_fd(&mut self, fd: OwnedFd, size: usize) {
+        if self.fixed_keymap {
+            return;
+        }
+
         let keymap = XkbKeymap::from_fd(&self.context, fd, size);
-        let state = keymap.as_ref().and_then(XkbState::new_wayland);
+        let state = keymap.as_ref().and_then(XkbState::new);
         if keymap.is_none() || state.is_none() {
             tracing::warn!("failed to update xkb keymap");
         }
@@ -138,6 +180,10 @@ impl Context {
 
     #[cfg(feature = "x11")]
     pub fn set_keymap_from_x11(&mut self, xcb: *mut xcb_connection_t) {
+        if self.fixed_keymap {
+            return;
+        }
+
         let keymap = XkbKeymap::from_x11_keymap(&self.context, xcb, self.core_keyboard_id);
         let state = keymap.as_ref().and_then(|keymap| XkbState::new_x11(xcb, keymap));
         if keymap.is_none() || state.is_none() {
@@ -147,14 +193,42 @@ impl Context {
         self.keymap = keymap;
     }
 
+    /// The layouts of the keymap and the active one.
+    pub fn keyboard_layouts(&self) -> Option<KeyboardLayouts> {
+        let keymap = self.keymap.as_ref()?;
+        let state = self.state.as_ref()?;
+        let names = keymap.layout_names().to_vec();
+        Some(KeyboardLayouts { names, active: state.active_layout() as usize })
+    }
+
+    /// The index of the active layout.
+    pub fn active_layout(&self) -> Option<usize> {
+        Some(self.state.as_ref()?.active_layout() as usize)
+    }
+
+    /// Snapshot of the keymap and the active layout to look up keys outside of key events.
+    pub fn key_lookup(&self) -> Option<KeyLookup> {
+        let keymap = self.keymap.as_ref()?;
+        let state = self.state.as_ref()?;
+        Some(KeyLookup::new(keymap.clone(), state.active_layout()))
+    }
+
     /// Key builder context with the user provided xkb state.
     pub fn key_context(&mut self) -> Option<KeyContext<'_>> {
         let state = self.state.as_mut()?;
         let keymap = self.keymap.as_mut()?;
         let compose_state1 = self.compose_state1.as_mut();
         let compose_state2 = self.compose_state2.as_mut();
+        let compose_sequence = &mut self.compose_sequence;
         let scratch_buffer = &mut self.scratch_buffer;
-        Some(KeyContext { state, keymap, compose_state1, compose_state2, scratch_buffer })
+        Some(KeyContext {
+            state,
+            keymap,
+            compose_state1,
+            compose_state2,
+            compose_sequence,
+            scratch_buffer,
+        })
     }
 
     /// Key builder context with the user provided xkb state.
@@ -168,8 +242,30 @@ impl Context {
         let keymap = self.keymap.as_mut()?;
         let compose_state1 = self.compose_state1.as_mut();
         let compose_state2 = self.compose_state2.as_mut();
+        let compose_sequence = &mut self.compose_sequence;
         let scratch_buffer = &mut self.scratch_buffer;
-        Some(KeyContext { state, keymap, compose_state1, compose_state2, scratch_buffer })
+        Some(KeyContext {
+            state,
+            keymap,
+            compose_state1,
+            compose_state2,
+            compose_sequence,
+            scratch_buffer,
+        })
+    }
+
+    /// How the last key event changed the dead key or compose sequence.
+    pub fn take_compose(&mut self) -> Option<Compose> {
+        self.compose_sequence.take_change()
+    }
+
+    /// Cancel the pending dead key or compose sequence, returning whether there was one.
+    pub fn cancel_compose(&mut self) -> bool {
+        let compose_states = [self.compose_state1.as_mut(), self.compose_state2.as_mut()];
+        for state in compose_states.into_iter().flatten() {
+            state.reset();
+        }
+        self.compose_sequence.clear()
     }
 }
 
@@ -178,6 +274,7 @@ pub struct KeyContext<'a> {
     pub keymap: &'a mut XkbKeymap,
     compose_state1: Option<&'a mut XkbComposeState>,
     compose_state2: Option<&'a mut XkbComposeState>,
+    compose_sequence: &'a mut ComposeSequence,
     scratch_buffer: &'a mut Vec<u8>,
 }
 
@@ -188,6 +285,9 @@ impl KeyContext<'_> {
         state: ElementState,
         repeat: bool,
     ) -> KeyEvent {
+        // Only report the changes caused by this key.
+        self.compose_sequence.take_change();
+
         let mut event =
             KeyEventResults::new(self, keycode, !repeat && state == ElementState::Pressed);
         let physical_key = keymap::raw_keycode_to_physicalkey(keycode);
@@ -195,6 +295,8 @@ impl KeyContext<'_> {
         let text = event.text();
         let (key_without_modifiers, _) = event.key_without_modifiers();
         let text_with_all_modifiers = event.text_with_all_modifiers();
+        let (compose, keysym) = (event.compose, event.keysym);
+        self.update_compose_sequence(compose, keysym, &logical_key);
 
         KeyEvent {
             physical_key,
@@ -209,29 +311,32 @@ impl KeyContext<'_> {
     }
 
     fn keysym_to_utf8_raw(&mut self, keysym: u32) -> Option<SmolStr> {
-        self.scratch_buffer.clear();
-        self.scratch_buffer.reserve(8);
-        loop {
-            let bytes_written = unsafe {
-                (XKBH.xkb_keysym_to_utf8)(
-                    keysym,
-                    self.scratch_buffer.as_mut_ptr().cast(),
-                    self.scratch_buffer.capacity(),
-                )
-            };
-            if bytes_written == 0 {
-                return None;
-            } else if bytes_written == -1 {
-                self.scratch_buffer.reserve(8);
-            } else {
-                unsafe { self.scratch_buffer.set_len(bytes_written.try_into().unwrap()) };
-                break;
-            }
-        }
+        keysym_to_utf8(self.scratch_buffer, keysym)
+    }
 
-        // Remove the null-terminator
-        self.scratch_buffer.pop();
-        byte_slice_to_smol_str(self.scratch_buffer)
+    fn update_compose_sequence(&mut self, compose: ComposeStatus, keysym: u32, logical_key: &Key) {
+        let ComposeStatus::Accepted(status) = compose else {
+            return;
+        };
+
+        match status {
+            xkb_compose_status::XKB_COMPOSE_COMPOSING => {
+                // Show dead keys with their spacing variant, and keys without text like the
+                // compose key itself with a middle dot.
+                let text = match logical_key {
+                    Key::Dead(Some(char)) => SmolStr::new(char.encode_utf8(&mut [0; 4])),
+                    _ => self.keysym_to_utf8_raw(keysym).unwrap_or_else(|| SmolStr::new("·")),
+                };
+                self.compose_sequence.push(&text);
+            },
+            xkb_compose_status::XKB_COMPOSE_COMPOSED => {
+                self.compose_sequence.end(Compose::Finished)
+            },
+            xkb_compose_status::XKB_COMPOSE_CANCELLED => {
+                self.compose_sequence.end(Compose::Cancelled)
+            },
+            xkb_compose_status::XKB_COMPOSE_NOTHING => (),
+        }
     }
 }
 
@@ -250,6 +355,7 @@ impl<'a, 'b> KeyEventResults<'a, 'b> {
             if RESET_DEAD_KEYS.swap(false, Ordering::SeqCst) {
                 state.reset();
                 context.compose_state2.as_mut().unwrap().reset();
+                context.compose_sequence.end(Compose::Cancelled);
             }
             state.feed(keysym)
         } else {
@@ -378,6 +484,33 @@ impl Deref for XkbContext {
     }
 }
 
+/// The text produced by the keysym, ignoring the modifiers.
+fn keysym_to_utf8(scratch_buffer: &mut Vec<u8>, keysym: u32) -> Option<SmolStr> {
+    scratch_buffer.clear();
+    scratch_buffer.reserve(8);
+    loop {
+        let bytes_written = unsafe {
+            (XKBH.xkb_keysym_to_utf8)(
+                keysym,
+                scratch_buffer.as_mut_ptr().cast(),
+                scratch_buffer.capacity(),
+            )
+        };
+        if bytes_written == 0 {
+            return None;
+        } else if bytes_written == -1 {
+            scratch_buffer.reserve(8);
+        } else {
+            unsafe { scratch_buffer.set_len(bytes_written.try_into().unwrap()) };
+            break;
+        }
+    }
+
+    // Remove the null-terminator
+    scratch_buffer.pop();
+    byte_slice_to_smol_str(scratch_buffer)
+}
+
 /// Shared logic for constructing a string with `xkb_compose_state_get_utf8` and
 /// `xkb_state_key_get_utf8`.
 fn make_string_with<F>(scratch_buffer: &mut Vec<u8>, mut f: F) -> Option<SmolStr>
@@ -4,28 +4,31 @@ use std::os::raw::c_char;
 use std::ptr::NonNull;
 
 use smol_str::SmolStr;
+use winit_core::event::Modifiers;
+use winit_core::keyboard::LockState;
 #[cfg(feature = "x11")]
 use x11_dl::xlib_xcb::xcb_connection_t;
 use xkbcommon_dl::{
-    self as xkb, xkb_keycode_t, xkb_keysym_t, xkb_layout_index_t, xkb_state, xkb_state_component,
+    self as xkb, xkb_keycode_t, xkb_keysym_t, xkb_layout_index_t, xkb_mod_index_t, xkb_state,
+    xkb_state_component,
 };
 
 #[cfg(feature = "x11")]
 use super::XKBXH;
-use super::keymap::XkbKeymap;
+use super::keymap::{ModsIndices, XkbKeymap};
 use super::{XKBH, make_string_with};
 
 #[derive(Debug)]
 pub struct XkbState {
     state: NonNull<xkb_state>,
+    mods_indices: ModsIndices,
     modifiers: ModifiersState,
 }
 
 impl XkbState {
-    #[cfg(feature = "wayland")]
-    pub fn new_wayland(keymap: &XkbKeymap) -> Option<Self> {
+    pub fn new(keymap: &XkbKeymap) -> Option<Self> {
         let state = NonNull::new(unsafe { (XKBH.xkb_state_new)(keymap.as_ptr()) })?;
-        Some(Self::new_inner(state))
+        Some(Self::new_inner(state, keymap.mods_indices()))
     }
 
     #[cfg(feature = "x11")]
@@ -34,12 +37,12 @@ impl XkbState {
             (XKBXH.xkb_x11_state_new_from_device)(keymap.as_ptr(), xcb, keymap._core_keyboard_id)
         };
         let state = NonNull::new(state)?;
-        Some(Self::new_inner(state))
+        Some(Self::new_inner(state, keymap.mods_indices()))
     }
 
-    fn new_inner(state: NonNull<xkb_state>) -> Self {
+    fn new_inner(state: NonNull<xkb_state>, mods_indices: ModsIndices) -> Self {
         let modifiers = ModifiersState::default();
-        let mut this = Self { state, modifiers };
+        let mut this = Self { state, mods_indices, modifiers };
         this.reload_modifiers();
         this
     }
@@ -52,6 +55,16 @@ impl XkbState {
         unsafe { (XKBH.xkb_state_key_get_layout)(self.state.as_ptr(), key) }
     }
 
+    /// The index of the effective layout.
+    pub fn active_layout(&self) -> xkb_layout_index_t {
+        unsafe {
+            (XKBH.xkb_state_serialize_layout)(
+                self.state.as_ptr(),
+                xkb_state_component::XKB_STATE_LAYOUT_EFFECTIVE,
+            )
+        }
+    }
+
     #[cfg(feature = "x11")]
     pub fn depressed_modifiers(&mut self) -> xkb::xkb_mod_mask_t {
         unsafe {
@@ -131,6 +144,28 @@ impl XkbState {
         self.modifiers.caps_lock = self.mod_name_is_active(xkb::XKB_MOD_NAME_CAPS);
         self.modifiers.logo = self.mod_name_is_active(xkb::XKB_MOD_NAME_LOGO);
         self.modifiers.num_lock = self.mod_name_is_active(xkb::XKB_MOD_NAME_NUM);
+        self.modifiers.lock_state = self.lock_state();
+    }
+
+    /// The engaged locks, from the locked modifiers.
+    fn lock_state(&mut self) -> LockState {
+        let mut lock_state = LockState::empty();
+        lock_state.set(LockState::CAPS_LOCK, self.mod_index_is_locked(self.mods_indices.caps));
+        lock_state.set(LockState::NUM_LOCK, self.mod_index_is_locked(self.mods_indices.num));
+        lock_state.set(LockState::SCROLL_LOCK, self.mod_index_is_locked(self.mods_indices.scroll));
+        lock_state
+    }
+
+    /// Check if the modifier is locked within xkb.
+    fn mod_index_is_locked(&mut self, index: Option<xkb_mod_index_t>) -> bool {
+        let Some(index) = index else { return false };
+        unsafe {
+            (XKBH.xkb_state_mod_index_is_active)(
+                self.state.as_ptr(),
+                index,
+                xkb_state_component::XKB_STATE_MODS_LOCKED,
+            ) > 0
+        }
     }
 
     /// Check if the modifier is active within xkb.
@@ -176,6 +211,8 @@ pub struct ModifiersState {
     pub logo: bool,
     /// The "Num lock" key
     pub num_lock: bool,
+    /// The engaged lock keys
+    pub lock_state: LockState,
 }
 
 impl From<ModifiersState> for winit_core::keyboard::ModifiersState {
@@ -188,3 +225,10 @@ impl From<ModifiersState> for winit_core::keyboard::ModifiersState {
         to_mods
     }
 }
+
+impl From<ModifiersState> for Modifiers {
+    fn from(mods: ModifiersState) -> Modifiers {
+        Modifiers::from(winit_core::keyboard::ModifiersState::from(mods))
+            .with_lock_state(mods.lock_state)
+    }
+}
@@ -1,7 +1,7 @@
 //! End user application handling.
 
 use crate::event::{DeviceEvent, DeviceId, StartCause, WindowEvent};
-use crate::event_loop::ActiveEventLoop;
+use crate::event_loop::{ActiveEventLoop, FdReadiness, FdSourceId, TimerId};
 use crate::window::WindowId;
 
 pub mod macos;
@@ -191,6 +191,32 @@ pub trait ApplicationHandler {
         let _ = event_loop;
     }
 
+    /// Emitted when a timer added with [`ActiveEventLoop::add_timer()`] fires.
+    ///
+    /// Timers are delivered after the window and device events pending when they fired.
+    fn timer_fired(&mut self, event_loop: &dyn ActiveEventLoop, timer_id: TimerId) {
+        let _ = (event_loop, timer_id);
+    }
+
+    /// Emitted when a file descriptor registered with the event loop is ready.
+    ///
+    /// The file descriptor is watched in level-triggered mode, so it's reported again on the next
+    /// iteration until the application reads or writes what it can.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11 / Wayland:** Registered with `ActiveEventLoopExtX11::register_fd` and
+    ///   `ActiveEventLoopExtWayland::register_fd`.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    fn fd_ready(
+        &mut self,
+        event_loop: &dyn ActiveEventLoop,
+        source_id: FdSourceId,
+        readiness: FdReadiness,
+    ) {
+        let _ = (event_loop, source_id, readiness);
+    }
+
     /// Emitted when the OS sends an event to a winit window.
     fn window_event(
         &mut self,
@@ -336,6 +362,31 @@ pub trait ApplicationHandler {
         let _ = event_loop;
     }
 
+    /// Emitted when one of the values returned by [`ActiveEventLoop::system_settings()`] changed.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    fn system_settings_changed(&mut self, event_loop: &dyn ActiveEventLoop) {
+        let _ = event_loop;
+    }
+
+    /// Emitted when the connection to the display server was lost, for example because the
+    /// compositor or the X server exited, or because the SSH connection forwarding it dropped.
+    ///
+    /// The windows can't be used anymore, this is the last chance for the application to save its
+    /// state. The event loop then exits, `run_app` returning [`EventLoopError::ConnectionLost`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Requires libX11 1.7 or newer, Xlib exiting the process otherwise.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    ///
+    /// [`EventLoopError::ConnectionLost`]: crate::error::EventLoopError::ConnectionLost
+    fn connection_lost(&mut self, event_loop: &dyn ActiveEventLoop) {
+        let _ = event_loop;
+    }
+
     /// The macOS-specific handler.
     ///
     /// The return value from this should not change at runtime.
@@ -367,6 +418,21 @@ impl<A: ?Sized + ApplicationHandler> ApplicationHandler for &mut A {
         (**self).proxy_wake_up(event_loop);
     }
 
+    #[inline]
+    fn timer_fired(&mut self, event_loop: &dyn ActiveEventLoop, timer_id: TimerId) {
+        (**self).timer_fired(event_loop, timer_id);
+    }
+
+    #[inline]
+    fn fd_ready(
+        &mut self,
+        event_loop: &dyn ActiveEventLoop,
+        source_id: FdSourceId,
+        readiness: FdReadiness,
+    ) {
+        (**self).fd_ready(event_loop, source_id, readiness);
+    }
+
     #[inline]
     fn window_event(
         &mut self,
@@ -407,6 +473,16 @@ impl<A: ?Sized + ApplicationHandler> ApplicationHandler for &mut A {
         (**self).memory_warning(event_loop);
     }
 
+    #[inline]
+    fn system_settings_changed(&mut self, event_loop: &dyn ActiveEventLoop) {
+        (**self).system_settings_changed(event_loop);
+    }
+
+    #[inline]
+    fn connection_lost(&mut self, event_loop: &dyn ActiveEventLoop) {
+        (**self).connection_lost(event_loop);
+    }
+
     #[inline]
     fn macos_handler(&mut self) -> Option<&mut dyn macos::ApplicationHandlerExtMacOS> {
         (**self).macos_handler()
@@ -435,6 +511,21 @@ impl<A: ?Sized + ApplicationHandler> ApplicationHandler for Box<A> {
         (**self).proxy_wake_up(event_loop);
     }
 
+    #[inline]
+    fn timer_fired(&mut self, event_loop: &dyn ActiveEventLoop, timer_id: TimerId) {
+        (**self).timer_fired(event_loop, timer_id);
+    }
+
+    #[inline]
+    fn fd_ready(
+        &mut self,
+        event_loop: &dyn ActiveEventLoop,
+        source_id: FdSourceId,
+        readiness: FdReadiness,
+    ) {
+        (**self).fd_ready(event_loop, source_id, readiness);
+    }
+
     #[inline]
     fn window_event(
         &mut self,
@@ -475,6 +566,16 @@ impl<A: ?Sized + ApplicationHandler> ApplicationHandler for Box<A> {
         (**self).memory_warning(event_loop);
     }
 
+    #[inline]
+    fn system_settings_changed(&mut self, event_loop: &dyn ActiveEventLoop) {
+        (**self).system_settings_changed(event_loop);
+    }
+
+    #[inline]
+    fn connection_lost(&mut self, event_loop: &dyn ActiveEventLoop) {
+        (**self).connection_lost(event_loop);
+    }
+
     #[inline]
     fn macos_handler(&mut self) -> Option<&mut dyn macos::ApplicationHandlerExtMacOS> {
         (**self).macos_handler()
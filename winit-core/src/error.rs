@@ -14,6 +14,11 @@ pub enum EventLoopError {
     Os(OsError),
     /// Creating the event loop with the requested configuration is not supported.
     NotSupported(NotSupportedError),
+    /// The connection to the display server was lost, see
+    /// [`ApplicationHandler::connection_lost()`].
+    ///
+    /// [`ApplicationHandler::connection_lost()`]: crate::application::ApplicationHandler::connection_lost
+    ConnectionLost,
 }
 
 impl fmt::Display for EventLoopError {
@@ -29,6 +34,7 @@ impl fmt::Display for EventLoopError {
             Self::Os(err) => err.fmt(f),
             Self::ExitFailure(status) => write!(f, "Exit Failure: {status}"),
             Self::NotSupported(err) => err.fmt(f),
+            Self::ConnectionLost => write!(f, "the connection to the display server was lost"),
         }
     }
 }
@@ -2,8 +2,11 @@
 use std::cell::LazyCell;
 use std::cmp::Ordering;
 use std::f64;
+use std::ops::Range;
 use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
 
+use bitflags::bitflags;
 use dpi::{PhysicalPosition, PhysicalSize};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -13,10 +16,10 @@ use crate::Instant;
 use crate::data_transfer::{DataTransferId, TypedData};
 use crate::error::RequestError;
 use crate::event_loop::{AsyncRequestSerial, DndAction};
-use crate::keyboard::{self, ModifiersKeyState, ModifiersKeys, ModifiersState};
+use crate::keyboard::{self, LockState, ModifiersKeyState, ModifiersKeys, ModifiersState};
 #[cfg(doc)]
 use crate::window::Window;
-use crate::window::{ActivationToken, Theme};
+use crate::window::{ActivationToken, Theme, WindowStates};
 
 /// Describes the reason the event loop is resuming.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -205,11 +208,26 @@ pub enum WindowEvent {
         ///
         /// Otherwise, this value is always `false`.
         is_synthetic: bool,
+
+        /// When the input happened, see [`EventTimestamp`].
+        timestamp: Option<EventTimestamp>,
     },
 
-    /// The keyboard modifiers have changed.
+    /// The keyboard modifiers or the state of the lock keys have changed.
     ModifiersChanged(Modifiers),
 
+    /// The active keyboard layout or the configured layouts have changed.
+    ///
+    /// Contains the new value of [`ActiveEventLoop::keyboard_layouts`], it is sent to the focused
+    /// window.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    ///
+    /// [`ActiveEventLoop::keyboard_layouts`]: crate::event_loop::ActiveEventLoop::keyboard_layouts
+    KeyboardLayoutChanged(keyboard::KeyboardLayouts),
+
     /// An event from an input method.
     ///
     /// **Note:** You have to explicitly enable this event using [`Window::set_ime_allowed`].
@@ -219,6 +237,19 @@ pub enum WindowEvent {
     /// - **iOS / Android / Web / Orbital:** Unsupported.
     Ime(Ime),
 
+    /// A dead key or compose sequence progressed.
+    ///
+    /// The keys of the sequence are still reported with [`WindowEvent::KeyboardInput`], the
+    /// composed text being the `text` of the key completing it. This event lets the application
+    /// show the pending sequence at the cursor, like native toolkits do.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Not sent while the IME is allowed, the input method handles the sequences and
+    ///   reports them with [`WindowEvent::Ime`] instead.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    Compose(Compose),
+
     /// The pointer has moved on the window.
     ///
     /// Should be emitted regardless of window focus.
@@ -247,6 +278,24 @@ pub enum WindowEvent {
         primary: bool,
 
         source: PointerSource,
+
+        /// When the input happened, see [`EventTimestamp`].
+        timestamp: Option<EventTimestamp>,
+
+        /// The earlier samples of the same pointer merged into this event, oldest first.
+        ///
+        /// The samples the windowing system reports at once are merged unless
+        /// [`PointerCoalescing::Latest`] is set, and those of the coalesced events too when
+        /// [`PointerCoalescing::History`] is set.
+        ///
+        /// ## Platform-specific
+        ///
+        /// - **X11:** The queued XInput2 motions of the pointer are reported at once.
+        /// - **Wayland:** The motions of a `wl_pointer` frame are reported at once.
+        ///
+        /// [`PointerCoalescing::Latest`]: crate::event_loop::PointerCoalescing::Latest
+        /// [`PointerCoalescing::History`]: crate::event_loop::PointerCoalescing::History
+        history: Vec<PointerSample>,
     },
 
     /// The pointer has entered the window.
@@ -305,7 +354,46 @@ pub enum WindowEvent {
     },
 
     /// A mouse wheel movement or touchpad scroll occurred.
-    MouseWheel { device_id: Option<DeviceId>, delta: MouseScrollDelta, phase: TouchPhase },
+    MouseWheel {
+        device_id: Option<DeviceId>,
+        delta: MouseScrollDelta,
+        phase: TouchPhase,
+
+        /// When the input happened, see [`EventTimestamp`].
+        timestamp: Option<EventTimestamp>,
+
+        /// The kind of device which scrolled.
+        ///
+        /// ## Platform-specific
+        ///
+        /// - **X11:** [`Wheel`](ScrollSource::Wheel) and [`WheelTilt`](ScrollSource::WheelTilt)
+        ///   are only reported for the legacy wheel buttons, smooth scrolling being
+        ///   [`Unknown`](ScrollSource::Unknown).
+        /// - **iOS / Android / Web / Windows / macOS / Orbital:** Always
+        ///   [`Unknown`](ScrollSource::Unknown).
+        source: ScrollSource,
+
+        /// The horizontal and vertical distance scrolled by a wheel in fractions of a detent,
+        /// `120` being one detent, with the sign convention of `delta`.
+        ///
+        /// High-resolution wheels report values smaller than `120` for partial detents.
+        ///
+        /// ## Platform-specific
+        ///
+        /// - **X11:** Computed from the smooth scrolling valuators relative to their increment.
+        /// - **iOS / Android / Web / Windows / macOS / Orbital:** Always `None`.
+        value120: Option<(i32, i32)>,
+
+        /// Whether the scrolling stopped, such as when the fingers were lifted from the touchpad.
+        ///
+        /// Applications implementing kinetic scrolling should start it from the latest deltas when
+        /// the scrolling stops with a [`Finger`](ScrollSource::Finger) source.
+        ///
+        /// ## Platform-specific
+        ///
+        /// - **iOS / Android / Web / Windows / macOS / Orbital / X11:** Always `false`.
+        stopped: bool,
+    },
 
     /// An mouse button press has been received.
     PointerButton {
@@ -350,6 +438,23 @@ pub enum WindowEvent {
         ///
         /// [`acceptsFirstMouse:`]: https://developer.apple.com/documentation/appkit/nsview/acceptsfirstmouse(_:)
         is_macos_activation_click: bool,
+
+        /// When the input happened, see [`EventTimestamp`].
+        timestamp: Option<EventTimestamp>,
+
+        /// The number of successive clicks of the button, `1` for a single click and `2` for a
+        /// double click.
+        ///
+        /// A press counts as the next click when it happens within the double click time and
+        /// distance of [`SystemSettings`] from the previous press of the same button, without the
+        /// pointer travelling further in between. The release reports the count of its press.
+        ///
+        /// ## Platform-specific
+        ///
+        /// - **iOS / Android / Web / Windows / macOS / Orbital:** Always `1`.
+        ///
+        /// [`SystemSettings`]: crate::event_loop::SystemSettings
+        click_count: u32,
     },
 
     /// Multi-finger hold gesture on the touchpad or touchscreen without movement.
@@ -364,13 +469,20 @@ pub enum WindowEvent {
     /// ## Platform-specific
     ///
     /// - Only available on **Wayland**.
-    HoldGesture { device_id: Option<DeviceId>, phase: TouchPhase },
+    HoldGesture {
+        device_id: Option<DeviceId>,
+        phase: TouchPhase,
+
+        /// When the input happened, see [`EventTimestamp`].
+        timestamp: Option<EventTimestamp>,
+    },
 
     /// Two-finger pinch gesture, often used for magnification.
     ///
     /// ## Platform-specific
     ///
-    /// - Only available on **macOS**, **iOS**, and **Wayland**.
+    /// - Only available on **macOS**, **iOS**, **Wayland**, and **X11**.
+    /// - On X11, requires an X server supporting XInput 2.4.
     /// - On iOS, not recognized by default. It must be enabled when needed.
     PinchGesture {
         device_id: Option<DeviceId>,
@@ -380,19 +492,26 @@ pub enum WindowEvent {
         /// This value may be NaN.
         delta: f64,
         phase: TouchPhase,
+
+        /// When the input happened, see [`EventTimestamp`].
+        timestamp: Option<EventTimestamp>,
     },
 
     /// N-finger pan gesture
     ///
     /// ## Platform-specific
     ///
-    /// - Only available on **iOS** and **Wayland**.
+    /// - Only available on **iOS**, **Wayland**, and **X11**.
+    /// - On X11, requires an X server supporting XInput 2.4.
     /// - On iOS, not recognized by default. It must be enabled when needed.
     PanGesture {
         device_id: Option<DeviceId>,
         /// Change in pixels of pan gesture from last update.
         delta: PhysicalPosition<f32>,
         phase: TouchPhase,
+
+        /// When the input happened, see [`EventTimestamp`].
+        timestamp: Option<EventTimestamp>,
     },
 
     /// Double tap gesture.
@@ -422,13 +541,17 @@ pub enum WindowEvent {
     ///
     /// ## Platform-specific
     ///
-    /// - Only available on **macOS**, **iOS**, and **Wayland**.
+    /// - Only available on **macOS**, **iOS**, **Wayland**, and **X11**.
+    /// - On X11, requires an X server supporting XInput 2.4.
     /// - On iOS, not recognized by default. It must be enabled when needed.
     RotationGesture {
         device_id: Option<DeviceId>,
         /// change in rotation in degrees
         delta: f32,
         phase: TouchPhase,
+
+        /// When the input happened, see [`EventTimestamp`].
+        timestamp: Option<EventTimestamp>,
     },
 
     /// Touchpad pressure event.
@@ -480,7 +603,8 @@ pub enum WindowEvent {
     ///
     /// ## Platform-specific
     ///
-    /// - **iOS / Android / X11 / Wayland / Orbital:** Unsupported.
+    /// - **X11:** Follows the `Net/ThemeName` XSETTINGS value.
+    /// - **iOS / Android / Wayland / Orbital:** Unsupported.
     ThemeChanged(Theme),
 
     /// The window has been occluded (completely hidden from view).
@@ -512,6 +636,15 @@ pub enum WindowEvent {
     /// [`transform`]: https://developer.mozilla.org/en-US/docs/Web/CSS/transform
     Occluded(bool),
 
+    /// The states of the window changed.
+    ///
+    /// Contains the new value of [`Window::states`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    StateChanged(WindowStates),
+
     /// Emitted when a window should be redrawn.
     ///
     /// This gets triggered in a few scenarios:
@@ -701,6 +834,55 @@ impl DeviceId {
     }
 }
 
+/// The time an input event happened, as reported by the windowing system.
+///
+/// Unlike the time the event is delivered to the application, it isn't affected by the latency of
+/// the event loop, making it suitable for gesture recognition or latency measurements.
+///
+/// ## Platform-specific
+///
+/// - **X11 / Wayland:** The timestamps have a millisecond precision.
+/// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported, the events have no
+///   timestamp.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EventTimestamp {
+    time: Duration,
+    instant: Instant,
+}
+
+impl EventTimestamp {
+    /// Create a timestamp for an input event which happened at `time` on the windowing system
+    /// clock, `age` before now.
+    pub fn new(time: Duration, age: Duration) -> Self {
+        let now = Instant::now();
+        Self { time, instant: now.checked_sub(age).unwrap_or(now) }
+    }
+
+    /// The time of the event on the monotonic clock of the windowing system.
+    ///
+    /// Only the difference between the times of two events is meaningful, the origin of the clock
+    /// being unspecified.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// The time of the event as an [`Instant`], to compare it with the current time.
+    ///
+    /// When the clock of the windowing system can't be related to [`Instant`], this is the time
+    /// the event was received instead.
+    pub fn to_instant(&self) -> Instant {
+        self.instant
+    }
+}
+
+/// A pointer position merged into a later [`WindowEvent::PointerMoved`], see its `history`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointerSample {
+    pub position: PhysicalPosition<f64>,
+    pub source: PointerSource,
+    pub timestamp: Option<EventTimestamp>,
+}
+
 /// Identifier of a finger in a touch event.
 ///
 /// Whenever a touch event is received it contains a `FingerId` which uniquely identifies the finger
@@ -944,12 +1126,20 @@ pub struct Modifiers {
     //
     // The field providing a metadata, it shouldn't be used as a source of truth.
     pub(crate) pressed_mods: ModifiersKeys,
+
+    pub(crate) lock_state: LockState,
 }
 
 impl Modifiers {
     /// Create a new modifiers from state and pressed mods.
     pub fn new(state: ModifiersState, pressed_mods: ModifiersKeys) -> Self {
-        Self { state, pressed_mods }
+        Self { state, pressed_mods, lock_state: LockState::empty() }
+    }
+
+    /// Set the state of the lock keys.
+    pub fn with_lock_state(mut self, lock_state: LockState) -> Self {
+        self.lock_state = lock_state;
+        self
     }
 
     /// The logical state of the modifiers.
@@ -957,6 +1147,17 @@ impl Modifiers {
         self.state
     }
 
+    /// The state of the lock keys.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11 / Wayland:** Scroll Lock is only reported when the keymap maps it to the
+    ///   `ScrollLock` modifier.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported, always empty.
+    pub fn lock_state(&self) -> LockState {
+        self.lock_state
+    }
+
     /// The logical state of the left shift key.
     pub fn lshift_state(&self) -> ModifiersKeyState {
         self.mod_state(ModifiersKeys::LSHIFT)
@@ -1008,7 +1209,7 @@ impl Modifiers {
 
 impl From<ModifiersState> for Modifiers {
     fn from(value: ModifiersState) -> Self {
-        Self { state: value, pressed_mods: Default::default() }
+        Self { state: value, pressed_mods: Default::default(), lock_state: Default::default() }
     }
 }
 
@@ -1030,9 +1231,9 @@ impl From<ModifiersState> for Modifiers {
 ///
 /// ```ignore
 /// // Press "`" key
-/// Ime::Preedit("`", Some((0, 0)))
+/// Ime::Preedit("`", Some((0, 0)), vec![])
 /// // Press "E" key
-/// Ime::Preedit("", None) // Synthetic event generated by winit to clear preedit.
+/// Ime::Preedit("", None, vec![]) // Synthetic event generated by winit to clear preedit.
 /// Ime::Commit("é")
 /// ```
 ///
@@ -1045,15 +1246,15 @@ impl From<ModifiersState> for Modifiers {
 ///
 /// ```ignore
 /// // Press "A" key
-/// Ime::Preedit("a", Some((1, 1)))
+/// Ime::Preedit("a", Some((1, 1)), vec![])
 /// // Press "B" key
-/// Ime::Preedit("a b", Some((3, 3)))
+/// Ime::Preedit("a b", Some((3, 3)), vec![])
 /// // Press left arrow key
-/// Ime::Preedit("a b", Some((1, 1)))
+/// Ime::Preedit("a b", Some((1, 1)), vec![])
 /// // Press space key
-/// Ime::Preedit("啊b", Some((3, 3)))
+/// Ime::Preedit("啊b", Some((3, 3)), vec![])
 /// // Press space key
-/// Ime::Preedit("", None) // Synthetic event generated by winit to clear preedit.
+/// Ime::Preedit("", None, vec![]) // Synthetic event generated by winit to clear preedit.
 /// Ime::Commit("啊不")
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -1069,12 +1270,20 @@ pub enum Ime {
 
     /// Notifies when a new composing text should be set at the cursor position.
     ///
-    /// The value represents a pair of the preedit string and the cursor begin position and end
-    /// position. When it's `None`, the cursor should be hidden. When `String` is an empty string
-    /// this indicates that preedit was cleared.
+    /// The value represents the preedit string, the cursor begin position and end position, and
+    /// the styled segments of the preedit. When the cursor is `None`, it should be hidden. When
+    /// `String` is an empty string this indicates that preedit was cleared.
+    ///
+    /// The styled segments usually mark the clause being converted, and don't overlap. The parts
+    /// of the preedit outside of them have no particular style.
+    ///
+    /// The cursor position and the segment ranges are byte-wise indexed, assuming UTF-8.
+    ///
+    /// ## Platform-specific
     ///
-    /// The cursor position is byte-wise indexed, assuming UTF-8.
-    Preedit(String, Option<(usize, usize)>),
+    /// - **Wayland:** The styles are only reported with the text-input v1 and v2 protocols.
+    /// - **Windows / macOS / iOS / Android / Web / Orbital:** The styles are always empty.
+    Preedit(String, Option<(usize, usize)>, Vec<(Range<usize>, PreeditStyle)>),
 
     /// Notifies when text should be inserted into the editor widget.
     ///
@@ -1104,6 +1313,58 @@ pub enum Ime {
     Disabled,
 }
 
+bitflags! {
+    /// The style of a segment of the [`Ime::Preedit`] text.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct PreeditStyle: u8 {
+        /// The segment is underlined.
+        const UNDERLINE = 1 << 0;
+        /// The segment is highlighted, usually the clause being converted.
+        const HIGHLIGHT = 1 << 1;
+        /// The segment is drawn with the foreground and background colors swapped.
+        const REVERSE = 1 << 2;
+    }
+}
+
+/// The progress of a dead key or compose sequence, see [`WindowEvent::Compose`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Compose {
+    /// A sequence is in progress.
+    ///
+    /// `sequence` represents the keys typed so far, e.g. `´` after the acute dead key, or `·` for
+    /// the compose key. It is meant to be shown at the cursor until the sequence ends.
+    Pending { sequence: String },
+
+    /// The sequence completed, the [`WindowEvent::KeyboardInput`] following this event carries
+    /// the composed text.
+    Finished,
+
+    /// The sequence was abandoned without producing text, because of a key not continuing it or
+    /// of [`Window::cancel_compose`].
+    Cancelled,
+}
+
+/// The kind of device which produced a [`WindowEvent::MouseWheel`].
+#[derive(Debug, Default, Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum ScrollSource {
+    /// A mouse wheel scrolling by detents.
+    Wheel,
+    /// Fingers on a touchpad, the scrolling stopping when they are lifted.
+    Finger,
+    /// A device scrolling continuously without detents nor fingers, such as a pointing stick.
+    Continuous,
+    /// A mouse wheel tilted sideways.
+    WheelTilt,
+    /// The source isn't known.
+    #[default]
+    Unknown,
+}
+
 /// Describes touch-screen input state.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -1696,8 +1957,11 @@ mod tests {
                 primary: true,
                 position: (0, 0).into(),
                 source: PointerSource::Mouse,
+                timestamp: None,
+                history: Vec::new(),
             });
             with_window_event(ModifiersChanged(event::Modifiers::default()));
+            with_window_event(KeyboardLayoutChanged(Default::default()));
             with_window_event(PointerEntered {
                 device_id: None,
                 primary: true,
@@ -1714,6 +1978,10 @@ mod tests {
                 device_id: None,
                 delta: event::MouseScrollDelta::LineDelta(0.0, 0.0),
                 phase: event::TouchPhase::Started,
+                timestamp: None,
+                source: event::ScrollSource::Unknown,
+                value120: None,
+                stopped: false,
             });
             with_window_event(PointerButton {
                 device_id: None,
@@ -1722,6 +1990,8 @@ mod tests {
                 position: (0, 0).into(),
                 button: event::ButtonSource::Unknown(0),
                 is_macos_activation_click: false,
+                timestamp: None,
+                click_count: 1,
             });
             with_window_event(PointerButton {
                 device_id: None,
@@ -1733,26 +2003,32 @@ mod tests {
                     force: Some(event::Force::Normalized(0.0)),
                 },
                 is_macos_activation_click: false,
+                timestamp: None,
+                click_count: 1,
             });
             with_window_event(PinchGesture {
                 device_id: None,
                 delta: 0.0,
                 phase: event::TouchPhase::Started,
+                timestamp: None,
             });
             with_window_event(DoubleTapGesture { device_id: None });
             with_window_event(RotationGesture {
                 device_id: None,
                 delta: 0.0,
                 phase: event::TouchPhase::Started,
+                timestamp: None,
             });
             with_window_event(PanGesture {
                 device_id: None,
                 delta: PhysicalPosition::<f32>::new(0.0, 0.0),
                 phase: event::TouchPhase::Started,
+                timestamp: None,
             });
             with_window_event(TouchpadPressure { device_id: None, pressure: 0.0, stage: 0 });
             with_window_event(ThemeChanged(crate::window::Theme::Light));
             with_window_event(Occluded(true));
+            with_window_event(StateChanged(crate::window::WindowStates::MAXIMIZED));
         }};
         (device: $closure:expr) => {{
             use event::DeviceEvent::*;
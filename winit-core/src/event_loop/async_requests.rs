@@ -0,0 +1,369 @@
+//! Futures resolving with the answers to the requests tagged with an [`AsyncRequestSerial`].
+//!
+//! Requests like [`ActiveEventLoop::fetch_data_transfer()`] are answered later by an event
+//! carrying the serial they returned. Instead of correlating the serials by hand, the application
+//! can turn them into futures with [`AsyncRequests`], forward its window events to
+//! [`AsyncRequests::handle_window_event()`], and await the futures on any executor, such as the
+//! [`LocalExecutor`] driven by the event loop:
+//!
+//! ```rust,ignore
+//! fn window_event(&mut self, event_loop: &dyn ActiveEventLoop, id: WindowId, event: WindowEvent) {
+//!     if self.requests.handle_window_event(&event) {
+//!         return;
+//!     }
+//!
+//!     if let WindowEvent::DragDropped { data, .. } = event {
+//!         let serial = event_loop.fetch_data_transfer(data, &PlainText).unwrap();
+//!         if let Some(text) = self.requests.data_transfer(serial) {
+//!             self.executor.spawn(async move { println!("{:?}", text.await) });
+//!         }
+//!     }
+//! }
+//!
+//! fn proxy_wake_up(&mut self, _: &dyn ActiveEventLoop) {
+//!     self.executor.run_until_stalled();
+//! }
+//! ```
+//!
+//! [`ActiveEventLoop::fetch_data_transfer()`]: super::ActiveEventLoop::fetch_data_transfer
+
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::{fmt, mem};
+
+use super::{AsyncRequestSerial, EventLoopProxy};
+use crate::data_transfer::TypedData;
+use crate::event::WindowEvent;
+use crate::window::ActivationToken;
+
+/// The state of a request awaited by an [`AsyncAnswer`].
+enum Slot {
+    /// Waiting for an answer of the type awaited.
+    Waiting(TypeId, Option<Waker>),
+    Answered(Box<dyn Any>),
+}
+
+/// The requests awaited by the application.
+#[derive(Clone, Default)]
+pub struct AsyncRequests {
+    pending: Rc<RefCell<HashMap<AsyncRequestSerial, Slot>>>,
+}
+
+impl AsyncRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The token requested with `Window::request_activation_token`, answered with
+    /// [`WindowEvent::ActivationTokenDone`].
+    ///
+    /// Returns `None` when the request is already awaited.
+    pub fn activation_token(
+        &self,
+        serial: AsyncRequestSerial,
+    ) -> Option<AsyncAnswer<ActivationToken>> {
+        self.answer(serial)
+    }
+
+    /// The data requested with [`ActiveEventLoop::fetch_data_transfer()`], answered with
+    /// [`WindowEvent::DataTransferReceived`].
+    ///
+    /// Returns `None` when the request is already awaited.
+    ///
+    /// [`ActiveEventLoop::fetch_data_transfer()`]: super::ActiveEventLoop::fetch_data_transfer
+    pub fn data_transfer(
+        &self,
+        serial: AsyncRequestSerial,
+    ) -> Option<AsyncAnswer<Arc<dyn TypedData>>> {
+        self.answer(serial)
+    }
+
+    /// Resolve the future awaiting the answer carried by the event, returning whether there was
+    /// one.
+    ///
+    /// The events answering requests which aren't awaited, or awaited for another kind of answer,
+    /// are left to the application.
+    pub fn handle_window_event(&self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::ActivationTokenDone { serial, token } => {
+                self.resolve(*serial, token.clone())
+            },
+            WindowEvent::DataTransferReceived { serial, value, .. } => {
+                self.resolve(*serial, Arc::clone(value))
+            },
+            _ => false,
+        }
+    }
+
+    fn answer<T: 'static>(&self, serial: AsyncRequestSerial) -> Option<AsyncAnswer<T>> {
+        let mut pending = self.pending.borrow_mut();
+        if pending.contains_key(&serial) {
+            return None;
+        }
+
+        pending.insert(serial, Slot::Waiting(TypeId::of::<T>(), None));
+        let pending = Rc::clone(&self.pending);
+        Some(AsyncAnswer { serial, pending, completed: false, _marker: PhantomData })
+    }
+
+    fn resolve<T: 'static>(&self, serial: AsyncRequestSerial, value: T) -> bool {
+        let mut pending = self.pending.borrow_mut();
+        let Some(slot) = pending.get_mut(&serial) else {
+            return false;
+        };
+
+        // The serial was awaited with the accessor of another request.
+        if !matches!(slot, Slot::Waiting(type_id, _) if *type_id == TypeId::of::<T>()) {
+            return false;
+        }
+
+        let slot = mem::replace(slot, Slot::Answered(Box::new(value)));
+        drop(pending);
+        if let Slot::Waiting(_, Some(waker)) = slot {
+            waker.wake();
+        }
+        true
+    }
+}
+
+impl fmt::Debug for AsyncRequests {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pending = self.pending.borrow();
+        f.debug_struct("AsyncRequests").field("pending", &pending.keys()).finish()
+    }
+}
+
+/// A future resolving with the answer to a request, created by [`AsyncRequests`].
+///
+/// Dropping it stops awaiting the answer, which is then left to the application.
+pub struct AsyncAnswer<T> {
+    serial: AsyncRequestSerial,
+    pending: Rc<RefCell<HashMap<AsyncRequestSerial, Slot>>>,
+    /// Whether the answer was returned, the serial may be awaited again since.
+    completed: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> AsyncAnswer<T> {
+    /// The serial of the awaited request.
+    pub fn serial(&self) -> AsyncRequestSerial {
+        self.serial
+    }
+}
+
+impl<T: 'static> Future for AsyncAnswer<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let this = self.get_mut();
+        assert!(!this.completed, "`AsyncAnswer` polled after completion");
+
+        let mut pending = this.pending.borrow_mut();
+        match pending.remove(&this.serial) {
+            Some(Slot::Waiting(type_id, _)) => {
+                pending.insert(this.serial, Slot::Waiting(type_id, Some(cx.waker().clone())));
+                Poll::Pending
+            },
+            // Only the answers of the awaited type are stored, see `AsyncRequests::resolve()`.
+            Some(Slot::Answered(value)) => match value.downcast::<T>() {
+                Ok(value) => {
+                    this.completed = true;
+                    Poll::Ready(*value)
+                },
+                Err(_) => Poll::Pending,
+            },
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<T> Drop for AsyncAnswer<T> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.pending.borrow_mut().remove(&self.serial);
+        }
+    }
+}
+
+impl<T> fmt::Debug for AsyncAnswer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncAnswer").field("serial", &self.serial).finish_non_exhaustive()
+    }
+}
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+/// A minimal executor running futures on the event loop thread.
+///
+/// Woken tasks wake the event loop up with the [`EventLoopProxy`], and are polled when the
+/// application calls [`run_until_stalled()`], usually from
+/// [`ApplicationHandler::proxy_wake_up()`].
+///
+/// [`run_until_stalled()`]: Self::run_until_stalled
+/// [`ApplicationHandler::proxy_wake_up()`]: crate::application::ApplicationHandler::proxy_wake_up
+pub struct LocalExecutor {
+    proxy: EventLoopProxy,
+    tasks: RefCell<HashMap<u64, Task>>,
+    next_id: Cell<u64>,
+    /// The tasks woken since they were last polled.
+    woken: Arc<Mutex<Vec<u64>>>,
+}
+
+impl LocalExecutor {
+    pub fn new(proxy: EventLoopProxy) -> Self {
+        Self { proxy, tasks: Default::default(), next_id: Cell::new(0), woken: Default::default() }
+    }
+
+    /// Run the future until it completes, polling it first on the next
+    /// [`run_until_stalled()`][Self::run_until_stalled].
+    pub fn spawn(&self, future: impl Future<Output = ()> + 'static) {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.tasks.borrow_mut().insert(id, Box::pin(future));
+        TaskWaker { id, woken: Arc::clone(&self.woken), proxy: self.proxy.clone() }.wake_by_ref();
+    }
+
+    /// Poll the woken tasks until none of them can make progress.
+    pub fn run_until_stalled(&self) {
+        loop {
+            let woken = mem::take(&mut *self.woken.lock().unwrap());
+            if woken.is_empty() {
+                return;
+            }
+
+            for id in woken {
+                // Take the task out, so it can spawn other tasks while being polled.
+                let Some(mut task) = self.tasks.borrow_mut().remove(&id) else {
+                    continue;
+                };
+
+                let waker = Arc::new(TaskWaker {
+                    id,
+                    woken: Arc::clone(&self.woken),
+                    proxy: self.proxy.clone(),
+                });
+                let waker = Waker::from(waker);
+                if task.as_mut().poll(&mut Context::from_waker(&waker)).is_pending() {
+                    self.tasks.borrow_mut().insert(id, task);
+                }
+            }
+        }
+    }
+
+    /// Whether all the spawned tasks completed.
+    pub fn is_empty(&self) -> bool {
+        self.tasks.borrow().is_empty()
+    }
+}
+
+impl fmt::Debug for LocalExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalExecutor").field("tasks", &self.tasks.borrow().len()).finish()
+    }
+}
+
+struct TaskWaker {
+    id: u64,
+    woken: Arc<Mutex<Vec<u64>>>,
+    proxy: EventLoopProxy,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        TaskWaker::wake_by_ref(self);
+    }
+}
+
+impl TaskWaker {
+    fn wake_by_ref(&self) {
+        let mut woken = self.woken.lock().unwrap();
+        if !woken.contains(&self.id) {
+            woken.push(self.id);
+        }
+        drop(woken);
+        self.proxy.wake_up();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::event_loop::tests::CountingProxy;
+
+    #[test]
+    fn await_activation_token() {
+        let proxy = Arc::new(CountingProxy::default());
+        let executor = LocalExecutor::new(EventLoopProxy::new(proxy.clone()));
+        let requests = AsyncRequests::new();
+
+        let serial = AsyncRequestSerial::get();
+        let answer = requests.activation_token(serial).unwrap();
+        assert!(requests.activation_token(serial).is_none());
+        let token = Rc::new(RefCell::new(None));
+        let task_token = Rc::clone(&token);
+        executor.spawn(async move { *task_token.borrow_mut() = Some(answer.await) });
+        executor.run_until_stalled();
+        assert!(!executor.is_empty());
+        assert_eq!(proxy.0.load(Ordering::Relaxed), 1);
+
+        let other = WindowEvent::ActivationTokenDone {
+            serial: AsyncRequestSerial::get(),
+            token: ActivationToken::from_raw("other".into()),
+        };
+        assert!(!requests.handle_window_event(&other));
+
+        let done = WindowEvent::ActivationTokenDone {
+            serial,
+            token: ActivationToken::from_raw("token".into()),
+        };
+        assert!(requests.handle_window_event(&done));
+        assert_eq!(proxy.0.load(Ordering::Relaxed), 2);
+
+        executor.run_until_stalled();
+        assert!(executor.is_empty());
+        assert_eq!(*token.borrow(), Some(ActivationToken::from_raw("token".into())));
+        assert!(!requests.handle_window_event(&done));
+    }
+
+    #[test]
+    fn await_with_another_accessor() {
+        let requests = AsyncRequests::new();
+        let serial = AsyncRequestSerial::get();
+        let answer = requests.data_transfer(serial).unwrap();
+
+        // The answer of another type is left to the application.
+        let done = WindowEvent::ActivationTokenDone {
+            serial,
+            token: ActivationToken::from_raw("token".into()),
+        };
+        assert!(!requests.handle_window_event(&done));
+
+        // The completed answer doesn't stop awaiting the serial again.
+        drop(answer);
+        let token = requests.activation_token(serial).unwrap();
+        assert!(requests.handle_window_event(&done));
+        let mut token = Box::pin(token);
+        let mut cx = Context::from_waker(Waker::noop());
+        let Poll::Ready(value) = token.as_mut().poll(&mut cx) else { panic!("not answered") };
+        assert_eq!(value, ActivationToken::from_raw("token".into()));
+
+        let again = requests.activation_token(serial).unwrap();
+        drop(token);
+        assert!(requests.handle_window_event(&done));
+        drop(again);
+        assert!(!requests.handle_window_event(&done));
+    }
+}
@@ -1,15 +1,18 @@
+pub mod async_requests;
 pub mod never_return;
+pub mod proxy_channel;
 pub mod pump_events;
 pub mod register;
 pub mod run_on_demand;
 
 use std::fmt::{self, Debug};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::Duration;
 
 use rwh_06::{DisplayHandle, HandleError, HasDisplayHandle};
 
+use self::proxy_channel::{ChannelMode, ProxyReceiver, ProxySender};
 use crate::Instant;
 use crate::application::ApplicationHandler;
 use crate::as_any::AsAny;
@@ -17,6 +20,7 @@ use crate::cursor::{CustomCursor, CustomCursorSource};
 use crate::data_transfer::{DataTransfer, DataTransferId, DataTransferSend, TransferType};
 use crate::error::{EventLoopError, NotSupportedError, RequestError};
 use crate::icon::Icon;
+use crate::keyboard::{Key, KeyboardLayouts, ModifiersState, PhysicalKey};
 use crate::monitor::MonitorHandle;
 use crate::window::{Theme, Window, WindowAttributes, WindowId};
 
@@ -173,9 +177,133 @@ pub trait ActiveEventLoop: AsAny + fmt::Debug {
     ///
     /// ## Platform-specific
     ///
-    /// - **iOS / Android / Wayland / x11 / Orbital:** Unsupported.
+    /// - **X11:** Derived from the `Net/ThemeName` XSETTINGS value.
+    /// - **iOS / Android / Wayland / Orbital:** Unsupported.
     fn system_theme(&self) -> Option<Theme>;
 
+    /// Returns the current desktop settings relevant to input handling and rendering.
+    ///
+    /// Fields that cannot be determined on the current platform are `None`. Changes are reported
+    /// through [`ApplicationHandler::system_settings_changed()`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Read from the XSETTINGS manager.
+    /// - **Wayland:** Key repeat is taken from the `wl_keyboard.repeat_info` of the seat whose
+    ///   keyboard is focused, or of any seat with a keyboard when none is focused. The rest is read
+    ///   from the `org.freedesktop.portal.Settings` interface of the xdg-desktop-portal, when
+    ///   available.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    fn system_settings(&self) -> SystemSettings {
+        SystemSettings::default()
+    }
+
+    /// Add a timer, reported with [`ApplicationHandler::timer_fired()`] once it fires.
+    ///
+    /// Timers are independent from the [`ControlFlow`]: the event loop wakes up for the earliest
+    /// of the timers and the [`ControlFlow::WaitUntil`] deadline. A timer with a deadline fires
+    /// once, an interval timer fires until it is cancelled.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    fn add_timer(&self, schedule: TimerSchedule) -> Result<TimerId, RequestError> {
+        let _ = schedule;
+        Err(NotSupportedError::new("add_timer is not supported").into())
+    }
+
+    /// Cancel a timer added with [`add_timer()`], so it won't fire anymore.
+    ///
+    /// Cancelling a timer that already fired for the last time does nothing.
+    ///
+    /// [`add_timer()`]: Self::add_timer
+    fn cancel_timer(&self, timer_id: TimerId) {
+        let _ = timer_id;
+    }
+
+    /// Change how held keys repeat.
+    ///
+    /// By default keys repeat as configured by the user, which is reported by
+    /// [`system_settings()`]. Repeats are delivered as [`KeyEvent`]s with [`KeyEvent::repeat`] set,
+    /// and a key being repeated stops repeating when another repeatable key is pressed.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** With [`KeyRepeat::Custom`], the repeats of the X server are replaced by ones
+    ///   synthesized by winit, which go through the input method like the key presses.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    ///
+    /// [`system_settings()`]: Self::system_settings
+    /// [`KeyEvent`]: crate::event::KeyEvent
+    /// [`KeyEvent::repeat`]: crate::event::KeyEvent::repeat
+    fn set_key_repeat(&self, key_repeat: KeyRepeat) {
+        let _ = key_repeat;
+    }
+
+    /// Change how pointer motion and scrolling are delivered.
+    ///
+    /// By default every [`PointerMoved`] and [`MouseWheel`] received from the system is delivered.
+    /// When coalescing, the successive ones of the same pointer and window are merged until another
+    /// event is delivered or the event loop is about to wait, so the application handles at most
+    /// one of them per frame without losing the samples when asking for
+    /// [`PointerCoalescing::History`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    ///
+    /// [`PointerMoved`]: crate::event::WindowEvent::PointerMoved
+    /// [`MouseWheel`]: crate::event::WindowEvent::MouseWheel
+    fn set_pointer_coalescing(&self, coalescing: PointerCoalescing) {
+        let _ = coalescing;
+    }
+
+    /// Returns the configured keyboard layouts and the active one.
+    ///
+    /// Returns `None` when the layouts are not known yet, e.g. before a keyboard was attached.
+    /// Changes are reported with [`WindowEvent::KeyboardLayoutChanged`] to the focused window.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11 / Wayland:** The names are the XKB group names of the keymap.
+    /// - **Wayland:** The keymap is the one of the seat whose keyboard is focused, or of any seat
+    ///   with a keyboard when none is focused.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    ///
+    /// [`WindowEvent::KeyboardLayoutChanged`]: crate::event::WindowEvent::KeyboardLayoutChanged
+    fn keyboard_layouts(&self) -> Option<KeyboardLayouts> {
+        None
+    }
+
+    /// Returns the logical key produced by the physical key with the given modifiers on the
+    /// active keyboard layout, e.g. to display shortcuts.
+    ///
+    /// Returns `None` when the key produces nothing or the layout is not known.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** The keymap is the one of the seat whose keyboard is focused, or of any seat
+    ///   with a keyboard when none is focused.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    fn key_for_physical(&self, key: PhysicalKey, modifiers: ModifiersState) -> Option<Key> {
+        let _ = key;
+        let _ = modifiers;
+        None
+    }
+
+    /// Returns a physical key and the modifiers producing the logical key on the active keyboard
+    /// layout, the inverse of [`ActiveEventLoop::key_for_physical`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11 / Wayland:** Only the unmodified and the [`ModifiersState::SHIFT`] combinations are
+    ///   searched.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported.
+    fn physical_for_key(&self, key: &Key) -> Option<(PhysicalKey, ModifiersState)> {
+        let _ = key;
+        None
+    }
+
     /// Sets the [`ControlFlow`].
     fn set_control_flow(&self, control_flow: ControlFlow);
 
@@ -440,6 +568,18 @@ impl EventLoopProxy {
         self.proxy.wake_up();
     }
 
+    /// Create a channel whose [sender] wakes the [`ActiveEventLoop`] up with each message.
+    ///
+    /// The sender can be moved to other threads, while the messages are taken with
+    /// [`ProxyReceiver::drain()`] from [`ApplicationHandler::proxy_wake_up()`], or delivered to a
+    /// handler by the event loop with [`ProxyReceiver::with_handler()`].
+    ///
+    /// [sender]: ProxySender
+    /// [`ApplicationHandler::proxy_wake_up()`]: crate::application::ApplicationHandler::proxy_wake_up
+    pub fn channel<T>(&self, mode: ChannelMode) -> (ProxySender<T>, ProxyReceiver<T>) {
+        proxy_channel::channel(self.clone(), mode)
+    }
+
     pub fn new(proxy: Arc<dyn EventLoopProxyProvider>) -> Self {
         Self { proxy }
     }
@@ -524,6 +664,24 @@ pub enum ControlFlow {
     ///
     /// [`Poll`]: Self::Poll
     WaitUntil(Instant),
+
+    /// When the current loop iteration finishes, suspend the thread until another event arrives
+    /// or a window is ready for its next frame, in which case
+    /// [`RedrawRequested`](crate::event::WindowEvent::RedrawRequested) is delivered to it.
+    ///
+    /// Useful for continuous animations, which are drawn at the display's refresh rate without
+    /// busy looping like [`Poll`] does. The application must present a frame on each
+    /// `RedrawRequested`, otherwise the window may stop receiving them.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** Frames are paced by the compositor's `wl_surface.frame` callbacks.
+    /// - **X11:** Frames are paced by the compositor's `_NET_WM_FRAME_DRAWN` messages when it
+    ///   supports them, and by the refresh rate of the windows' monitors otherwise.
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported, behaves like [`Poll`].
+    ///
+    /// [`Poll`]: Self::Poll
+    Animate,
 }
 
 impl ControlFlow {
@@ -556,6 +714,174 @@ pub enum DeviceEvents {
     Never,
 }
 
+/// When a timer added with [`ActiveEventLoop::add_timer()`] fires.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[allow(clippy::exhaustive_enums)]
+pub enum TimerSchedule {
+    /// Fire once, when the deadline is reached.
+    Deadline(Instant),
+    /// Fire repeatedly, every interval from now on.
+    ///
+    /// A zero interval is rejected with [`RequestError::NotSupported`].
+    Interval(Duration),
+}
+
+/// A unique identifier of a timer added with [`ActiveEventLoop::add_timer()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+impl TimerId {
+    /// Allocate a new identifier, distinct from the previous ones.
+    pub fn new_unique() -> Self {
+        static CURRENT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(CURRENT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Convert the `TimerId` into the underlying integer.
+    pub const fn into_raw(self) -> u64 {
+        self.0
+    }
+}
+
+/// A unique identifier of a file descriptor registered with the event loop.
+///
+/// The registration is done by platform-specific extensions, such as
+/// `ActiveEventLoopExtX11::register_fd`, and reported with [`ApplicationHandler::fd_ready()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FdSourceId(u64);
+
+impl FdSourceId {
+    /// Allocate a new identifier, distinct from the previous ones.
+    pub fn new_unique() -> Self {
+        static CURRENT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(CURRENT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Convert the `FdSourceId` into the underlying integer.
+    pub const fn into_raw(self) -> u64 {
+        self.0
+    }
+}
+
+bitflags::bitflags! {
+    /// The readiness of a file descriptor registered with the event loop, also used to select
+    /// the readiness to watch for.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct FdReadiness: u8 {
+        /// The file descriptor can be read from.
+        const READABLE = 1 << 0;
+        /// The file descriptor can be written to.
+        const WRITABLE = 1 << 1;
+        /// An error occurred on the file descriptor, only reported.
+        const ERROR = 1 << 2;
+    }
+}
+
+/// A file descriptor registered with the event loop, unregistered when dropped.
+pub struct FdRegistration {
+    source_id: FdSourceId,
+    unregister: Option<Box<dyn FnOnce()>>,
+}
+
+impl FdRegistration {
+    /// Create a registration running `unregister` when dropped.
+    pub fn new(source_id: FdSourceId, unregister: impl FnOnce() + 'static) -> Self {
+        Self { source_id, unregister: Some(Box::new(unregister)) }
+    }
+
+    /// The identifier reported with the readiness of the file descriptor.
+    pub fn id(&self) -> FdSourceId {
+        self.source_id
+    }
+}
+
+impl fmt::Debug for FdRegistration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FdRegistration").field("source_id", &self.source_id).finish_non_exhaustive()
+    }
+}
+
+impl Drop for FdRegistration {
+    fn drop(&mut self) {
+        if let Some(unregister) = self.unregister.take() {
+            unregister();
+        }
+    }
+}
+
+/// How held keys repeat, set with [`ActiveEventLoop::set_key_repeat()`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::exhaustive_enums)]
+pub enum KeyRepeat {
+    /// Repeat keys as configured by the user.
+    #[default]
+    System,
+    /// Never repeat keys.
+    Disabled,
+    /// Start repeating a key after it was held for `delay`, then repeat it `rate` times per
+    /// second. A `rate` of zero disables key repeat, rates above a million repeat every
+    /// microsecond.
+    Custom { delay: Duration, rate: u32 },
+}
+
+impl KeyRepeat {
+    /// The delay and the time between two repeats of [`KeyRepeat::Custom`], `None` when it doesn't
+    /// repeat.
+    pub fn custom_interval(self) -> Option<(Duration, Duration)> {
+        match self {
+            Self::Custom { delay, rate } if rate != 0 => {
+                Some((delay, Duration::from_micros((1_000_000 / rate as u64).max(1))))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// How pointer events are merged, set with [`ActiveEventLoop::set_pointer_coalescing()`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::exhaustive_enums)]
+pub enum PointerCoalescing {
+    /// Deliver every pointer event.
+    #[default]
+    Disabled,
+    /// Only deliver the latest position of the pointer, and the sum of the scroll deltas.
+    Latest,
+    /// Like [`Latest`](Self::Latest), keeping the merged positions in the `history` of
+    /// [`WindowEvent::PointerMoved`].
+    ///
+    /// [`WindowEvent::PointerMoved`]: crate::event::WindowEvent::PointerMoved
+    History,
+}
+
+/// Desktop settings returned by [`ActiveEventLoop::system_settings()`].
+///
+/// Every field is `None` when the platform doesn't provide the value.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SystemSettings {
+    /// Maximum time between two clicks for them to count as a double click.
+    pub double_click_time: Option<Duration>,
+    /// Maximum distance in physical pixels the pointer may travel between two clicks for them to
+    /// count as a double click.
+    pub double_click_distance: Option<u32>,
+    /// Distance in physical pixels the pointer has to travel with a button held before a drag
+    /// operation should start.
+    pub drag_threshold: Option<u32>,
+    /// Duration of one caret blink phase, [`Duration::ZERO`] when blinking is disabled.
+    pub caret_blink_interval: Option<Duration>,
+    /// Delay before a held key starts repeating.
+    pub key_repeat_delay: Option<Duration>,
+    /// Time between two repeats of a held key, [`Duration::ZERO`] when key repeat is disabled.
+    pub key_repeat_interval: Option<Duration>,
+    /// Preferred cursor size in logical pixels.
+    pub cursor_size: Option<u32>,
+    /// Whether the user asked for animations to be reduced or disabled.
+    pub reduce_motion: Option<bool>,
+    /// The accent color chosen by the user, as sRGB components.
+    pub accent_color: Option<[u8; 3]>,
+}
+
 /// A unique identifier of the winit's async request.
 ///
 /// This could be used to identify the async request once it's done
@@ -579,3 +905,37 @@ impl AsyncRequestSerial {
         Self { serial }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::{EventLoopProxyProvider, KeyRepeat};
+
+    /// A proxy counting the wake ups.
+    #[derive(Debug, Default)]
+    pub(crate) struct CountingProxy(pub AtomicUsize);
+
+    impl EventLoopProxyProvider for CountingProxy {
+        fn wake_up(&self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn key_repeat_interval() {
+        let delay = Duration::from_millis(300);
+        assert_eq!(KeyRepeat::System.custom_interval(), None);
+        assert_eq!(KeyRepeat::Disabled.custom_interval(), None);
+        assert_eq!(KeyRepeat::Custom { delay, rate: 0 }.custom_interval(), None);
+        assert_eq!(
+            KeyRepeat::Custom { delay, rate: 25 }.custom_interval(),
+            Some((delay, Duration::from_millis(40)))
+        );
+        assert_eq!(
+            KeyRepeat::Custom { delay, rate: u32::MAX }.custom_interval(),
+            Some((delay, Duration::from_micros(1)))
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Channels waking the event loop up when a message is sent, see [`EventLoopProxy::channel()`].
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::rc::{Rc, Weak};
+use std::sync::{Arc, Mutex};
+
+use super::EventLoopProxy;
+
+/// How a [proxy channel](EventLoopProxy::channel) queues the messages not yet received.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(clippy::exhaustive_enums)]
+pub enum ChannelMode {
+    /// Queue all the messages.
+    #[default]
+    Unbounded,
+    /// Queue up to the given number of messages, failing to send the others with
+    /// [`SendError::Full`].
+    Bounded(usize),
+    /// Only keep the latest message, replacing the one not yet received.
+    Latest,
+}
+
+struct Shared<T> {
+    mode: ChannelMode,
+    queue: VecDeque<T>,
+    receiver_alive: bool,
+}
+
+/// The sending half of a [proxy channel](EventLoopProxy::channel).
+///
+/// It can be cloned and sent to other threads.
+pub struct ProxySender<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    proxy: EventLoopProxy,
+}
+
+impl<T> ProxySender<T> {
+    /// Queue the message and wake the event loop up, resulting in
+    /// [`ApplicationHandler::proxy_wake_up()`] being called.
+    ///
+    /// [`ApplicationHandler::proxy_wake_up()`]: crate::application::ApplicationHandler::proxy_wake_up
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        let mut shared = self.shared.lock().unwrap();
+        if !shared.receiver_alive {
+            return Err(SendError::Disconnected(message));
+        }
+
+        match shared.mode {
+            ChannelMode::Unbounded => (),
+            ChannelMode::Bounded(capacity) if shared.queue.len() >= capacity => {
+                return Err(SendError::Full(message));
+            },
+            ChannelMode::Bounded(_) => (),
+            ChannelMode::Latest => shared.queue.clear(),
+        }
+
+        shared.queue.push_back(message);
+        drop(shared);
+        self.proxy.wake_up();
+        Ok(())
+    }
+}
+
+impl<T> Clone for ProxySender<T> {
+    fn clone(&self) -> Self {
+        Self { shared: Arc::clone(&self.shared), proxy: self.proxy.clone() }
+    }
+}
+
+impl<T> fmt::Debug for ProxySender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxySender").field("proxy", &self.proxy).finish_non_exhaustive()
+    }
+}
+
+/// The receiving half of a [proxy channel](EventLoopProxy::channel).
+pub struct ProxyReceiver<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> ProxyReceiver<T> {
+    /// Take the oldest message not yet received.
+    pub fn try_recv(&self) -> Option<T> {
+        self.shared.lock().unwrap().queue.pop_front()
+    }
+
+    /// Call the handler with each message not yet received, in the order they were sent.
+    ///
+    /// This is meant to be called from [`ApplicationHandler::proxy_wake_up()`], the messages sent
+    /// while the handler runs being delivered on the next wake up.
+    ///
+    /// [`ApplicationHandler::proxy_wake_up()`]: crate::application::ApplicationHandler::proxy_wake_up
+    pub fn drain(&self, mut handler: impl FnMut(T)) {
+        let queue = std::mem::take(&mut self.shared.lock().unwrap().queue);
+        queue.into_iter().for_each(&mut handler);
+    }
+
+    /// Let the event loop of this thread call the handler with each message, before
+    /// [`ApplicationHandler::proxy_wake_up()`], until the returned [`ProxyHandler`] is dropped.
+    ///
+    /// This must be called on the thread running the event loop.
+    ///
+    /// [`ApplicationHandler::proxy_wake_up()`]: crate::application::ApplicationHandler::proxy_wake_up
+    pub fn with_handler(self, handler: impl FnMut(T) + 'static) -> ProxyHandler<T>
+    where
+        T: 'static,
+    {
+        let handled = Rc::new(Handled { receiver: self, handler: RefCell::new(Box::new(handler)) });
+        let weak = Rc::downgrade(&handled) as Weak<dyn Dispatch>;
+        HANDLERS.with_borrow_mut(|handlers| handlers.push(weak));
+        ProxyHandler { handled }
+    }
+}
+
+impl<T> Drop for ProxyReceiver<T> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.receiver_alive = false;
+        shared.queue.clear();
+    }
+}
+
+impl<T> fmt::Debug for ProxyReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyReceiver").finish_non_exhaustive()
+    }
+}
+
+/// A [`ProxyReceiver`] whose messages are delivered to a handler by the event loop, see
+/// [`ProxyReceiver::with_handler()`].
+///
+/// Dropping it disconnects the channel.
+pub struct ProxyHandler<T> {
+    handled: Rc<Handled<T>>,
+}
+
+impl<T> fmt::Debug for ProxyHandler<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyHandler")
+            .field("receiver", &self.handled.receiver)
+            .finish_non_exhaustive()
+    }
+}
+
+struct Handled<T> {
+    receiver: ProxyReceiver<T>,
+    handler: RefCell<Box<dyn FnMut(T)>>,
+}
+
+trait Dispatch {
+    fn dispatch(&self);
+}
+
+impl<T> Dispatch for Handled<T> {
+    fn dispatch(&self) {
+        // A handler sending to its own channel is called again on the next wake up.
+        if let Ok(mut handler) = self.handler.try_borrow_mut() {
+            self.receiver.drain(&mut *handler);
+        }
+    }
+}
+
+thread_local! {
+    /// The channels of this thread with a handler.
+    static HANDLERS: RefCell<Vec<Weak<dyn Dispatch>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Call the handlers of the [proxy channels](EventLoopProxy::channel) of this thread with the
+/// messages they received.
+///
+/// This is called by the event loops before [`ApplicationHandler::proxy_wake_up()`].
+///
+/// [`ApplicationHandler::proxy_wake_up()`]: crate::application::ApplicationHandler::proxy_wake_up
+pub fn dispatch_handlers() {
+    let handlers: Vec<_> = HANDLERS.with_borrow_mut(|handlers| {
+        handlers.retain(|handler| handler.strong_count() > 0);
+        handlers.iter().filter_map(Weak::upgrade).collect()
+    });
+    for handler in handlers {
+        handler.dispatch();
+    }
+}
+
+pub(super) fn channel<T>(
+    proxy: EventLoopProxy,
+    mode: ChannelMode,
+) -> (ProxySender<T>, ProxyReceiver<T>) {
+    let shared =
+        Arc::new(Mutex::new(Shared { mode, queue: VecDeque::new(), receiver_alive: true }));
+    (ProxySender { shared: Arc::clone(&shared), proxy }, ProxyReceiver { shared })
+}
+
+/// The error returned by [`ProxySender::send()`], giving the message back.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(clippy::exhaustive_enums)]
+pub enum SendError<T> {
+    /// The [bounded](ChannelMode::Bounded) channel is full.
+    Full(T),
+    /// The [`ProxyReceiver`] was dropped.
+    Disconnected(T),
+}
+
+impl<T> SendError<T> {
+    /// The message that failed to be sent.
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Full(message) | Self::Disconnected(message) => message,
+        }
+    }
+}
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => f.write_str("Full(..)"),
+            Self::Disconnected(_) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => f.write_str("sending on a full channel"),
+            Self::Disconnected(_) => f.write_str("sending on a disconnected channel"),
+        }
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::event_loop::tests::CountingProxy;
+
+    fn received<T>(receiver: &ProxyReceiver<T>) -> Vec<T> {
+        let mut messages = Vec::new();
+        receiver.drain(|message| messages.push(message));
+        messages
+    }
+
+    #[test]
+    fn channel_modes() {
+        let provider = Arc::new(CountingProxy::default());
+        let proxy = EventLoopProxy::new(provider.clone());
+
+        let (sender, receiver) = proxy.channel(ChannelMode::Unbounded);
+        sender.send(1).unwrap();
+        sender.clone().send(2).unwrap();
+        assert_eq!(provider.0.load(Ordering::Relaxed), 2);
+        assert_eq!(received(&receiver), [1, 2]);
+        drop(receiver);
+        assert_eq!(sender.send(3), Err(SendError::Disconnected(3)));
+
+        let (sender, receiver) = proxy.channel(ChannelMode::Bounded(1));
+        sender.send(1).unwrap();
+        assert_eq!(sender.send(2), Err(SendError::Full(2)));
+        assert_eq!(receiver.try_recv(), Some(1));
+        sender.send(3).unwrap();
+        assert_eq!(received(&receiver), [3]);
+
+        let (sender, receiver) = proxy.channel(ChannelMode::Latest);
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        assert_eq!(received(&receiver), [2]);
+        assert_eq!(receiver.try_recv(), None);
+    }
+
+    #[test]
+    fn channel_handler() {
+        let provider = Arc::new(CountingProxy::default());
+        let proxy = EventLoopProxy::new(provider.clone());
+
+        let (sender, receiver) = proxy.channel(ChannelMode::Unbounded);
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let handler_messages = Rc::clone(&messages);
+        let handler = receiver.with_handler(move |message| {
+            handler_messages.borrow_mut().push(message);
+        });
+
+        let sender = std::thread::spawn(move || {
+            sender.send(1).unwrap();
+            sender.send(2).unwrap();
+            sender
+        })
+        .join()
+        .unwrap();
+        dispatch_handlers();
+        assert_eq!(*messages.borrow(), [1, 2]);
+        assert_eq!(provider.0.load(Ordering::Relaxed), 2);
+
+        drop(handler);
+        dispatch_handlers();
+        assert_eq!(sender.send(3), Err(SendError::Disconnected(3)));
+        assert_eq!(*messages.borrow(), [1, 2]);
+    }
+}
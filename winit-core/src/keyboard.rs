@@ -1,11 +1,17 @@
 //! Types related to the keyboard.
 
+use std::error::Error;
+use std::str::FromStr;
+use std::{fmt, iter};
+
 use bitflags::bitflags;
 pub use keyboard_types::{Code as KeyCode, Location as KeyLocation, NamedKey};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 pub use smol_str::SmolStr;
 
+use crate::event::{KeyEvent, Modifiers};
+
 /// Contains the platform-native physical key identifier
 ///
 /// The exact values vary from platform to platform (which is part of why this is a per-platform
@@ -463,6 +469,22 @@ pub enum ModifiersKeyState {
     Unknown,
 }
 
+bitflags! {
+    /// The state of the lock keys.
+    ///
+    /// Each flag is set if the lock is engaged, regardless of the key being held.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct LockState: u8 {
+        /// The "Caps Lock" key.
+        const CAPS_LOCK = 1 << 0;
+        /// The "Num Lock" key.
+        const NUM_LOCK = 1 << 1;
+        /// The "Scroll Lock" key.
+        const SCROLL_LOCK = 1 << 2;
+    }
+}
+
 // NOTE: the exact modifier key is not used to represent modifiers state in the
 // first place due to a fact that modifiers state could be changed without any
 // key being pressed and on some platforms like Wayland/X11 which key resulted
@@ -489,3 +511,433 @@ bitflags! {
         const RSUPER   = Self::RMETA.bits();
     }
 }
+
+/// The keyboard layouts configured on the system.
+///
+/// See [`ActiveEventLoop::keyboard_layouts`].
+///
+/// [`ActiveEventLoop::keyboard_layouts`]: crate::event_loop::ActiveEventLoop::keyboard_layouts
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyboardLayouts {
+    /// The names of the configured layouts, e.g. `"English (US)"`.
+    ///
+    /// A name is empty when the system doesn't provide one for the layout.
+    pub names: Vec<String>,
+    /// The index of the active layout in [`names`](Self::names).
+    pub active: usize,
+}
+
+impl KeyboardLayouts {
+    /// The name of the active layout.
+    pub fn active_name(&self) -> Option<&str> {
+        self.names.get(self.active).map(String::as_str)
+    }
+}
+
+/// The key of a [`Shortcut`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[allow(clippy::exhaustive_enums)]
+pub enum ShortcutKey {
+    /// A key following the keyboard layout, matched against
+    /// [`KeyEvent::key_without_modifiers`].
+    Logical(Key),
+
+    /// A key at a fixed position on the keyboard, matched against [`KeyEvent::physical_key`].
+    Physical(KeyCode),
+}
+
+impl From<Key> for ShortcutKey {
+    fn from(key: Key) -> Self {
+        Self::Logical(key)
+    }
+}
+
+impl From<NamedKey> for ShortcutKey {
+    fn from(key: NamedKey) -> Self {
+        Self::Logical(Key::Named(key))
+    }
+}
+
+impl From<KeyCode> for ShortcutKey {
+    fn from(code: KeyCode) -> Self {
+        Self::Physical(code)
+    }
+}
+
+/// A keyboard shortcut, such as <kbd>Ctrl</kbd>+<kbd>Shift</kbd>+<kbd>S</kbd>.
+///
+/// Shortcuts are parsed from and formatted as the modifiers followed by the key, separated by
+/// `+`, e.g. `"Ctrl+Shift+S"`.
+///
+/// - The modifiers are `Ctrl`/`Control`, `Shift`, `Alt`/`Option` and `Super`/`Cmd`/`Command`/
+///   `Meta`/`Win`, case insensitive. `Primary` stands for `Cmd` on macOS and iOS and for `Ctrl`
+///   elsewhere.
+/// - The key is either a single character, a [`NamedKey`] such as `Escape` or `F4`, or a
+///   [`KeyCode`] prefixed with `Code:`, such as `Code:KeyZ`, for a key at a fixed position. The
+///   prefix may be left out when the name is not a key too, e.g. `KeyZ`, while `Escape` is always
+///   the named key. `Space`, `Plus`, `Esc`, `Del`, `Ins`, `Up`, `Down`, `Left`, `Right`, `PgUp` and
+///   `PgDn` are accepted too.
+/// - [`Key::Dead`] is written `Dead:` followed by the combining character, if any, and
+///   [`Key::Unidentified`] is written `Native:` followed by the [`NativeKey`], e.g.
+///   `Native:Xkb:65`.
+///
+/// Formatting uses the platform conventional order and names of the modifiers, e.g.
+/// `"Ctrl+Alt+Shift+Super+S"`, or `"Ctrl+Option+Shift+Cmd+S"` on macOS and iOS. Formatted
+/// shortcuts parse back to the same shortcut, up to the case of characters.
+///
+/// With the `serde` feature, shortcuts are (de)serialized as strings.
+///
+/// # Examples
+///
+/// ```
+/// use winit_core::keyboard::{Key, ModifiersState, Shortcut, ShortcutKey};
+///
+/// let shortcut: Shortcut = "Ctrl+Shift+S".parse().unwrap();
+/// assert_eq!(shortcut.key, ShortcutKey::Logical(Key::Character("s".into())));
+/// assert_eq!(shortcut.modifiers, ModifiersState::CONTROL | ModifiersState::SHIFT);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    /// The key triggering the shortcut.
+    pub key: ShortcutKey,
+    /// The modifiers that must be active.
+    pub modifiers: ModifiersState,
+}
+
+impl Shortcut {
+    /// Creates a new shortcut.
+    pub fn new(key: impl Into<ShortcutKey>, modifiers: ModifiersState) -> Self {
+        Self { key: key.into(), modifiers }
+    }
+
+    /// Check whether the key event with the active modifiers triggers the shortcut.
+    ///
+    /// The modifiers must match exactly. Logical keys are compared with
+    /// [`KeyEvent::key_without_modifiers`], case insensitively, so `"Ctrl+Shift+S"` matches
+    /// regardless of the letter being shifted. Characters only reachable with
+    /// <kbd>Shift</kbd>, like `"Ctrl+?"` on a US layout, also match [`KeyEvent::logical_key`]
+    /// with <kbd>Shift</kbd> held.
+    ///
+    /// The [`KeyEvent::state`] is not checked, shortcuts usually trigger on press.
+    pub fn matches(&self, event: &KeyEvent, modifiers: &Modifiers) -> bool {
+        let state = modifiers.state();
+        match &self.key {
+            ShortcutKey::Physical(code) => event.physical_key == *code && state == self.modifiers,
+            ShortcutKey::Logical(key) => {
+                if state == self.modifiers && keys_match(&event.key_without_modifiers, key) {
+                    return true;
+                }
+
+                !self.modifiers.contains(ModifiersState::SHIFT)
+                    && state == self.modifiers | ModifiersState::SHIFT
+                    && keys_match(&event.logical_key, key)
+            },
+        }
+    }
+}
+
+fn keys_match(event_key: &Key, key: &Key) -> bool {
+    match (event_key, key) {
+        (Key::Character(event_key), Key::Character(key)) => {
+            event_key == key || event_key.to_lowercase() == key.to_lowercase()
+        },
+        (event_key, key) => event_key == key,
+    }
+}
+
+/// The modifier names accepted when parsing, and the name used when formatting.
+fn modifier_names() -> [(ModifiersState, &'static str); 4] {
+    let (alt, meta) = if cfg!(any(target_os = "macos", target_os = "ios")) {
+        ("Option", "Cmd")
+    } else if cfg!(windows) {
+        ("Alt", "Win")
+    } else {
+        ("Alt", "Super")
+    };
+
+    [
+        (ModifiersState::CONTROL, "Ctrl"),
+        (ModifiersState::ALT, alt),
+        (ModifiersState::SHIFT, "Shift"),
+        (ModifiersState::META, meta),
+    ]
+}
+
+fn parse_modifier(name: &str) -> Option<ModifiersState> {
+    Some(match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => ModifiersState::CONTROL,
+        "shift" => ModifiersState::SHIFT,
+        "alt" | "option" | "opt" => ModifiersState::ALT,
+        "super" | "cmd" | "command" | "meta" | "win" | "logo" => ModifiersState::META,
+        "primary" if cfg!(any(target_os = "macos", target_os = "ios")) => ModifiersState::META,
+        "primary" => ModifiersState::CONTROL,
+        _ => return None,
+    })
+}
+
+/// The prefixes of keys taking the rest of the shortcut, which may contain `+`, as their value.
+const KEY_PREFIXES: [&str; 3] = ["Code", "Dead", "Native"];
+
+/// The start of the key with one of the [`KEY_PREFIXES`] in the shortcut.
+fn prefixed_key_start(s: &str) -> Option<usize> {
+    iter::once(0).chain(s.match_indices('+').map(|(index, _)| index + 1)).find(|&start| {
+        s[start..].split_once(':').is_some_and(|(prefix, _)| {
+            KEY_PREFIXES.iter().any(|known| known.eq_ignore_ascii_case(prefix.trim()))
+        })
+    })
+}
+
+fn parse_prefixed_key(prefix: &str, value: &str) -> Option<ShortcutKey> {
+    let key = match prefix.trim().to_ascii_lowercase().as_str() {
+        "code" => return value.trim().parse::<KeyCode>().ok().map(ShortcutKey::Physical),
+        "dead" => {
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (ch, None) => Key::Dead(ch),
+                _ => return None,
+            }
+        },
+        "native" => Key::Unidentified(match value.split_once(':') {
+            Some(("Android", code)) => NativeKey::Android(code.parse().ok()?),
+            Some(("MacOS", code)) => NativeKey::MacOS(code.parse().ok()?),
+            Some(("Windows", code)) => NativeKey::Windows(code.parse().ok()?),
+            Some(("Xkb", code)) => NativeKey::Xkb(code.parse().ok()?),
+            Some(("Ohos", code)) => NativeKey::Ohos(code.parse().ok()?),
+            Some(("Web", key)) => NativeKey::Web(key.into()),
+            None if value == "Unidentified" => NativeKey::Unidentified,
+            _ => return None,
+        }),
+        _ => return None,
+    };
+
+    Some(key.into())
+}
+
+fn parse_key(name: &str) -> Option<ShortcutKey> {
+    let mut chars = name.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        let ch: String = ch.to_lowercase().collect();
+        return Some(Key::Character(ch.into()).into());
+    }
+
+    if let Some((prefix, value)) = name.split_once(':') {
+        return parse_prefixed_key(prefix, value);
+    }
+
+    let named = match name.to_ascii_lowercase().as_str() {
+        "space" => return Some(Key::Character(" ".into()).into()),
+        "plus" => return Some(Key::Character("+".into()).into()),
+        "esc" => NamedKey::Escape,
+        "del" => NamedKey::Delete,
+        "ins" => NamedKey::Insert,
+        "up" => NamedKey::ArrowUp,
+        "down" => NamedKey::ArrowDown,
+        "left" => NamedKey::ArrowLeft,
+        "right" => NamedKey::ArrowRight,
+        "pgup" => NamedKey::PageUp,
+        "pgdn" => NamedKey::PageDown,
+        "return" => NamedKey::Enter,
+        _ => match name.parse::<NamedKey>() {
+            Ok(named) => named,
+            Err(_) => return name.parse::<KeyCode>().ok().map(ShortcutKey::Physical),
+        },
+    };
+
+    Some(named.into())
+}
+
+impl FromStr for Shortcut {
+    type Err = ParseShortcutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseShortcutError::Empty);
+        }
+
+        // The key itself may be a `+`, or contain one after its prefix.
+        let (modifiers, key) = if let Some(start) = prefixed_key_start(s) {
+            (&s[..start], &s[start..])
+        } else if s == "+" {
+            ("", "+")
+        } else if let Some(modifiers) = s.strip_suffix("++") {
+            (modifiers, "+")
+        } else {
+            s.rsplit_once('+').unwrap_or(("", s))
+        };
+
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(ParseShortcutError::MissingKey);
+        }
+
+        let modifiers = modifiers
+            .split('+')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .try_fold(ModifiersState::empty(), |modifiers, name| match parse_modifier(name) {
+                Some(modifier) => Ok(modifiers | modifier),
+                None => Err(ParseShortcutError::UnknownModifier(name.to_owned())),
+            })?;
+        let key = parse_key(key).ok_or_else(|| ParseShortcutError::UnknownKey(key.to_owned()))?;
+
+        Ok(Self { key, modifiers })
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in modifier_names() {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+
+        match &self.key {
+            ShortcutKey::Logical(Key::Character(ch)) if ch == " " => f.write_str("Space"),
+            ShortcutKey::Logical(Key::Character(ch)) if ch == "+" => f.write_str("Plus"),
+            ShortcutKey::Logical(Key::Character(ch)) => {
+                let upper = ch.to_uppercase();
+                f.write_str(if upper.chars().count() == 1 { &upper } else { ch })
+            },
+            ShortcutKey::Logical(Key::Named(named)) => write!(f, "{named}"),
+            ShortcutKey::Logical(Key::Dead(ch)) => {
+                f.write_str("Dead:")?;
+                ch.map_or(Ok(()), |ch| write!(f, "{ch}"))
+            },
+            ShortcutKey::Logical(Key::Unidentified(native)) => {
+                f.write_str("Native:")?;
+                match native {
+                    NativeKey::Unidentified => f.write_str("Unidentified"),
+                    NativeKey::Android(code) => write!(f, "Android:{code}"),
+                    NativeKey::MacOS(code) => write!(f, "MacOS:{code}"),
+                    NativeKey::Windows(code) => write!(f, "Windows:{code}"),
+                    NativeKey::Xkb(code) => write!(f, "Xkb:{code}"),
+                    NativeKey::Ohos(code) => write!(f, "Ohos:{code}"),
+                    NativeKey::Web(key) => write!(f, "Web:{key}"),
+                }
+            },
+            ShortcutKey::Physical(code) => write!(f, "Code:{code}"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Shortcut {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Shortcut {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// An error when parsing a [`Shortcut`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseShortcutError {
+    /// The string is empty.
+    Empty,
+    /// The string ends with a separator instead of a key.
+    MissingKey,
+    /// The modifier is not known.
+    UnknownModifier(String),
+    /// The key is not a single character, a [`NamedKey`] or a [`KeyCode`].
+    UnknownKey(String),
+}
+
+impl fmt::Display for ParseShortcutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("empty shortcut"),
+            Self::MissingKey => f.write_str("shortcut without a key"),
+            Self::UnknownModifier(name) => write!(f, "unknown modifier `{name}`"),
+            Self::UnknownKey(name) => write!(f, "unknown key `{name}`"),
+        }
+    }
+}
+
+impl Error for ParseShortcutError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_shortcut() {
+        let modifiers = ModifiersState::CONTROL | ModifiersState::SHIFT;
+        assert_eq!(
+            "ctrl + shift + s".parse::<Shortcut>().unwrap(),
+            Shortcut::new(Key::Character("s".into()), modifiers)
+        );
+        assert_eq!(
+            "Alt+F4".parse::<Shortcut>().unwrap(),
+            Shortcut::new(NamedKey::F4, ModifiersState::ALT)
+        );
+        assert_eq!(
+            "Cmd+KeyZ".parse::<Shortcut>().unwrap(),
+            Shortcut::new(KeyCode::KeyZ, ModifiersState::META)
+        );
+        assert_eq!(
+            "Ctrl++".parse::<Shortcut>().unwrap(),
+            Shortcut::new(Key::Character("+".into()), ModifiersState::CONTROL)
+        );
+        assert_eq!("+".parse::<Shortcut>().unwrap().key, Key::Character("+".into()).into());
+        assert_eq!("Esc".parse::<Shortcut>().unwrap().key, NamedKey::Escape.into());
+
+        assert_eq!("".parse::<Shortcut>(), Err(ParseShortcutError::Empty));
+        assert_eq!("Ctrl+".parse::<Shortcut>(), Err(ParseShortcutError::MissingKey));
+        assert_eq!(
+            "Hyper+S".parse::<Shortcut>(),
+            Err(ParseShortcutError::UnknownModifier("Hyper".into()))
+        );
+        assert_eq!(
+            "Ctrl+Foo".parse::<Shortcut>(),
+            Err(ParseShortcutError::UnknownKey("Foo".into()))
+        );
+    }
+
+    #[test]
+    fn format_shortcut() {
+        let shortcuts =
+            ["Shift+Ctrl+s", "Alt+Space", "Ctrl+Plus", "Super+ArrowUp", "Ctrl+Alt+Digit1"];
+        for shortcut in shortcuts {
+            let shortcut: Shortcut = shortcut.parse().unwrap();
+            assert_eq!(shortcut.to_string().parse::<Shortcut>().unwrap(), shortcut);
+        }
+
+        // Physical keys sharing their name with a logical key, and keys without a name.
+        let keys = [
+            KeyCode::Escape.into(),
+            KeyCode::Enter.into(),
+            KeyCode::Tab.into(),
+            KeyCode::F4.into(),
+            KeyCode::ArrowUp.into(),
+            KeyCode::Backspace.into(),
+            KeyCode::Space.into(),
+            Key::Dead(None).into(),
+            Key::Dead(Some('+')).into(),
+            Key::Unidentified(NativeKey::Unidentified).into(),
+            Key::Unidentified(NativeKey::Xkb(0x1008ff13)).into(),
+            Key::Unidentified(NativeKey::Web("Soft+1".into())).into(),
+        ];
+        for key in keys {
+            let shortcut = Shortcut { key, modifiers: ModifiersState::CONTROL };
+            assert_eq!(shortcut.to_string().parse::<Shortcut>().unwrap(), shortcut);
+        }
+        assert_eq!(
+            Shortcut::new(KeyCode::Space, ModifiersState::empty()).to_string(),
+            "Code:Space"
+        );
+
+        let shortcut = Shortcut::new(Key::Character("s".into()), ModifiersState::all());
+        let (_, alt) = modifier_names()[1];
+        let (_, meta) = modifier_names()[3];
+        assert_eq!(shortcut.to_string(), format!("Ctrl+{alt}+Shift+{meta}+S"));
+    }
+}
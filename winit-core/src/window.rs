@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::as_any::AsAny;
 use crate::cursor::Cursor;
-use crate::error::RequestError;
+use crate::error::{NotSupportedError, RequestError};
 use crate::icon::Icon;
 use crate::monitor::{Fullscreen, MonitorHandle};
 
@@ -684,6 +684,22 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     // extension trait
     fn reset_dead_keys(&self);
 
+    /// Cancel the pending dead key or compose sequence of the keyboard focused on the window.
+    ///
+    /// Unlike [`reset_dead_keys`], this takes effect right away: when a sequence was pending,
+    /// [`WindowEvent::Compose`] with [`Compose::Cancelled`] is sent to the window.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows / macOS / iOS / Android / Web / Orbital:** Same as [`reset_dead_keys`].
+    ///
+    /// [`reset_dead_keys`]: Self::reset_dead_keys
+    /// [`WindowEvent::Compose`]: crate::event::WindowEvent::Compose
+    /// [`Compose::Cancelled`]: crate::event::Compose::Cancelled
+    fn cancel_compose(&self) {
+        self.reset_dead_keys();
+    }
+
     /// The position of the top-left hand corner of the surface relative to the top-left hand corner
     /// of the window.
     ///
@@ -1055,6 +1071,19 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     /// - **iOS / Android / Web:** Unsupported.
     fn is_maximized(&self) -> bool;
 
+    /// Gets the states of the window as last reported by the system.
+    ///
+    /// Changes are reported with [`WindowEvent::StateChanged`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **iOS / Android / Web / Windows / macOS / Orbital:** Unsupported, returns empty states.
+    ///
+    /// [`WindowEvent::StateChanged`]: crate::event::WindowEvent::StateChanged
+    fn states(&self) -> WindowStates {
+        WindowStates::empty()
+    }
+
     /// Set the window's fullscreen state.
     ///
     /// ## Platform-specific
@@ -1340,7 +1369,7 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     /// - **Wayland:** Sets the theme for the client side decorations. Using `None` will use dbus to
     ///   get the system preference.
     /// - **X11:** Sets `_GTK_THEME_VARIANT` hint to `dark` or `light` and if `None` is used, it
-    ///   will default to  [`Theme::Dark`].
+    ///   will follow the XSETTINGS theme, defaulting to [`Theme::Dark`].
     /// - **iOS / Android / Web / Orbital:** Unsupported.
     fn set_theme(&self, theme: Option<Theme>);
 
@@ -1350,7 +1379,7 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     ///
     /// ## Platform-specific
     ///
-    /// - **iOS / Android / x11 / Orbital:** Unsupported.
+    /// - **iOS / Android / Orbital:** Unsupported.
     /// - **Wayland:** Only returns theme overrides.
     fn theme(&self) -> Option<Theme>;
 
@@ -1475,9 +1504,32 @@ pub trait Window: AsAny + Send + Sync + fmt::Debug {
     ///
     /// ## Platform-specific
     ///
+    /// - **X11 / Wayland:** Same as calling [`set_input_region`] with `None` or an empty region.
     /// - **iOS / Android / Web / Orbital:** Always returns an [`RequestError::NotSupported`].
+    ///
+    /// [`set_input_region`]: Self::set_input_region
     fn set_cursor_hittest(&self, hittest: bool) -> Result<(), RequestError>;
 
+    /// Restricts the part of the window that catches pointer and touch events.
+    ///
+    /// Events over the parts of the window outside of the union of the given rectangles are
+    /// passed through to whatever is behind the window, as with [`set_cursor_hittest`]. The
+    /// rectangles are relative to the top-left corner of the surface. `None` makes the whole
+    /// window catch events again, an empty slice makes it ignore all of them.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Uses the input shape of the X Shape extension.
+    /// - **Wayland:** Rectangles are converted to logical coordinates.
+    /// - **iOS / Android / Web / Orbital / Windows / macOS:** Always returns an
+    ///   [`RequestError::NotSupported`].
+    ///
+    /// [`set_cursor_hittest`]: Self::set_cursor_hittest
+    fn set_input_region(&self, region: Option<&[Rect]>) -> Result<(), RequestError> {
+        let _ = region;
+        Err(RequestError::NotSupported(NotSupportedError::new("set_input_region is not supported")))
+    }
+
     /// Returns the monitor on which the window currently resides.
     ///
     /// Returns `None` if current monitor can't be detected.
@@ -1539,6 +1591,25 @@ impl rwh_06::HasWindowHandle for dyn Window + '_ {
     }
 }
 
+/// A rectangle in surface coordinates.
+///
+/// Used by [`Window::set_input_region`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rect {
+    /// The top-left corner of the rectangle.
+    pub position: Position,
+
+    /// The size of the rectangle.
+    pub size: Size,
+}
+
+impl Rect {
+    pub fn new(position: impl Into<Position>, size: impl Into<Size>) -> Self {
+        Self { position: position.into(), size: size.into() }
+    }
+}
+
 /// The behavior of cursor grabbing.
 ///
 /// Use this enum with [`Window::set_cursor_grab`] to grab the cursor.
@@ -1650,6 +1721,61 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    /// The states of a window, see [`Window::states`].
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** Read from `_NET_WM_STATE`, the tiled, suspended and constrained states are never
+    ///   set.
+    /// - **Wayland:** Read from the `xdg_toplevel` configure, the minimized, sticky, shaded, above
+    ///   and below states are never set. The constrained states are not reported yet, the
+    ///   toolkit the backend is built on doesn't expose them.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct WindowStates: u32 {
+        /// The window is maximized.
+        const MAXIMIZED = 1 << 0;
+        /// The window is fullscreen.
+        const FULLSCREEN = 1 << 1;
+        /// The window is minimized.
+        const MINIMIZED = 1 << 2;
+        /// The window is drawn as the active one.
+        const ACTIVATED = 1 << 3;
+        /// The left edge of the window is adjacent to another window or to the screen edge.
+        const TILED_LEFT = 1 << 4;
+        /// The right edge of the window is adjacent to another window or to the screen edge.
+        const TILED_RIGHT = 1 << 5;
+        /// The top edge of the window is adjacent to another window or to the screen edge.
+        const TILED_TOP = 1 << 6;
+        /// The bottom edge of the window is adjacent to another window or to the screen edge.
+        const TILED_BOTTOM = 1 << 7;
+        /// The window is not visible to the user, rendering can be stopped entirely.
+        const SUSPENDED = 1 << 8;
+        /// The left edge of the window can't be resized.
+        const CONSTRAINED_LEFT = 1 << 9;
+        /// The right edge of the window can't be resized.
+        const CONSTRAINED_RIGHT = 1 << 10;
+        /// The top edge of the window can't be resized.
+        const CONSTRAINED_TOP = 1 << 11;
+        /// The bottom edge of the window can't be resized.
+        const CONSTRAINED_BOTTOM = 1 << 12;
+        /// The window is shown on all workspaces.
+        const STICKY = 1 << 13;
+        /// The window is rolled up to its title bar.
+        const SHADED = 1 << 14;
+        /// The window is kept above other windows.
+        const ABOVE = 1 << 15;
+        /// The window is kept below other windows.
+        const BELOW = 1 << 16;
+    }
+}
+
+impl WindowStates {
+    /// All the tiled edges.
+    pub const TILED: Self =
+        Self::TILED_LEFT.union(Self::TILED_RIGHT).union(Self::TILED_TOP).union(Self::TILED_BOTTOM);
+}
+
 /// A window level groups windows with respect to their z-position.
 ///
 /// The relative ordering between windows in different window levels is fixed.
@@ -22,7 +22,7 @@ use winit_core::event_loop::pump_events::PumpStatus;
 use winit_core::event_loop::{
     ActiveEventLoop as RootActiveEventLoop, ControlFlow, DeviceEvents, EventLoopProvider,
     EventLoopProxy as CoreEventLoopProxy, EventLoopProxyProvider,
-    OwnedDisplayHandle as CoreOwnedDisplayHandle,
+    OwnedDisplayHandle as CoreOwnedDisplayHandle, proxy_channel,
 };
 use winit_core::keyboard::{
     Key, KeyCode, KeyLocation, ModifiersKeys, ModifiersState, NamedKey, NativeKey, NativeKeyCode,
@@ -422,6 +422,7 @@ impl EventLoop {
                         text_with_all_modifiers,
                     },
                     is_synthetic: false,
+                    timestamp: None,
                 };
 
                 app.window_event(window_target, window_id, event);
@@ -445,6 +446,8 @@ impl EventLoop {
                     primary: true,
                     position: event_state.mouse_pos.into(),
                     source: event::PointerSource::Mouse,
+                    timestamp: None,
+                    history: Vec::new(),
                 });
             },
             EventOption::MouseRelative(MouseRelativeEvent { dx, dy }) => {
@@ -461,6 +464,8 @@ impl EventLoop {
                         position: event_state.mouse_pos.into(),
                         button: button.into(),
                         is_macos_activation_click: false,
+                        timestamp: None,
+                        click_count: 1,
                     });
                 }
             },
@@ -469,6 +474,10 @@ impl EventLoop {
                     device_id: None,
                     delta: event::MouseScrollDelta::LineDelta(x as f32, y as f32),
                     phase: event::TouchPhase::Moved,
+                    timestamp: None,
+                    source: event::ScrollSource::Unknown,
+                    value120: None,
+                    stopped: false,
                 });
             },
             EventOption::Quit(QuitEvent {}) => {
@@ -647,6 +656,7 @@ impl EventLoop {
             }
 
             while self.user_events_receiver.try_recv().is_ok() {
+                proxy_channel::dispatch_handlers();
                 app.proxy_wake_up(&self.window_target);
             }
 
@@ -687,7 +697,7 @@ impl EventLoop {
         let start = Instant::now();
         let timeout = {
             let requested_resume = match self.window_target.control_flow() {
-                ControlFlow::Poll => Some(Duration::ZERO),
+                ControlFlow::Poll | ControlFlow::Animate => Some(Duration::ZERO),
                 ControlFlow::Wait => None,
                 ControlFlow::WaitUntil(instant) => Some(instant.saturating_duration_since(start)),
             };
@@ -724,7 +734,7 @@ impl EventLoop {
 
         // Normal window event or spurious timeout.
         let cause = match self.window_target.control_flow() {
-            ControlFlow::Poll => StartCause::Poll,
+            ControlFlow::Poll | ControlFlow::Animate => StartCause::Poll,
             ControlFlow::Wait => StartCause::WaitCancelled { start, requested_resume: None },
             ControlFlow::WaitUntil(deadline) => {
                 if Instant::now() < deadline {
@@ -20,7 +20,7 @@ use winit_common::core_foundation::EventLoopProxy;
 use winit_common::event_handler::EventHandler;
 use winit_core::application::ApplicationHandler;
 use winit_core::event::{StartCause, SurfaceSizeWriter, WindowEvent};
-use winit_core::event_loop::ControlFlow;
+use winit_core::event_loop::{ControlFlow, proxy_channel};
 use winit_core::window::WindowId;
 
 use crate::event_loop::ActiveEventLoop;
@@ -105,7 +105,10 @@ static GLOBAL: MainThreadBound<OnceCell<AppState>> =
 impl AppState {
     pub(crate) fn setup_global(mtm: MainThreadMarker) -> bool {
         let event_loop_proxy = Arc::new(EventLoopProxy::new(mtm, move || {
-            get_handler(mtm).handle(|app| app.proxy_wake_up(&ActiveEventLoop { mtm }));
+            get_handler(mtm).handle(|app| {
+                proxy_channel::dispatch_handlers();
+                app.proxy_wake_up(&ActiveEventLoop { mtm })
+            });
         }));
         GLOBAL
             .get(mtm)
@@ -149,7 +152,9 @@ impl AppState {
         }
 
         let start_cause = match (self.control_flow.get(), self.state.get()) {
-            (ControlFlow::Poll, AppStateImpl::PollFinished) => StartCause::Poll,
+            (ControlFlow::Poll | ControlFlow::Animate, AppStateImpl::PollFinished) => {
+                StartCause::Poll
+            },
             (ControlFlow::Wait, AppStateImpl::Waiting { start }) => {
                 StartCause::WaitCancelled { start, requested_resume: None }
             },
@@ -209,7 +214,7 @@ impl AppState {
                 self.waker.start_at(new_instant)
             },
             // Unlike on macOS, handle Poll to Poll transition here to call the waker
-            (_, ControlFlow::Poll) => {
+            (_, ControlFlow::Poll | ControlFlow::Animate) => {
                 self.state.set(AppStateImpl::PollFinished);
                 self.waker.start()
             },
@@ -185,7 +185,12 @@ define_class!(
 
             let gesture_event = EventWrapper::Window {
                 window_id: window.id(),
-                event: WindowEvent::PinchGesture { device_id: None, delta: delta as f64, phase },
+                event: WindowEvent::PinchGesture {
+                    device_id: None,
+                    delta: delta as f64,
+                    phase,
+                    timestamp: None,
+                },
             };
 
             let mtm = MainThreadMarker::new().unwrap();
@@ -246,6 +251,7 @@ define_class!(
                     device_id: None,
                     delta: -delta.to_degrees() as _,
                     phase,
+                    timestamp: None,
                 },
             };
 
@@ -299,6 +305,7 @@ define_class!(
                     device_id: None,
                     delta: PhysicalPosition::new(dx as _, dy as _),
                     phase,
+                    timestamp: None,
                 },
             };
 
@@ -572,6 +579,8 @@ impl WinitView {
                                 ButtonSource::Touch { finger_id, force }
                             },
                             is_macos_activation_click: false,
+                            timestamp: None,
+                            click_count: 1,
                         },
                     });
                 },
@@ -596,6 +605,8 @@ impl WinitView {
                             primary,
                             position,
                             source,
+                            timestamp: None,
+                            history: Vec::new(),
                         },
                     });
                 },
@@ -631,6 +642,8 @@ impl WinitView {
                                     ButtonSource::Touch { finger_id, force }
                                 },
                                 is_macos_activation_click: false,
+                                timestamp: None,
+                                click_count: 1,
                             },
                         });
                     }
@@ -715,6 +728,7 @@ impl WinitView {
                             key_without_modifiers: Key::Character(text.clone()),
                         },
                         is_synthetic: false,
+                        timestamp: None,
                     },
                 })
             }),
@@ -742,6 +756,7 @@ impl WinitView {
                         key_without_modifiers: Key::Named(NamedKey::Backspace),
                     },
                     is_synthetic: false,
+                    timestamp: None,
                 },
             }),
         );
@@ -11,8 +11,9 @@ use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 use std::{fmt, mem};
 
-use calloop::PostAction;
+use calloop::generic::Generic;
 use calloop::ping::Ping;
+use calloop::{Interest, Mode, PostAction};
 use dpi::LogicalSize;
 use rustix::event::{PollFd, PollFlags};
 use rustix::pipe::{self, PipeFlags};
@@ -22,19 +23,27 @@ use sctk::reexports::client::{Connection, QueueHandle, globals};
 use sctk::shell::WaylandSurface;
 use tracing::warn;
 use wayland_client::Proxy;
+use wayland_client::backend::WaylandError;
 use wayland_client::protocol::wl_data_device_manager::DndAction as WlDndAction;
 use wayland_client::protocol::wl_shm::Format;
+use winit_common::click_count::ClickCounter;
+use winit_common::coalescing::CoalescingHandler;
+use winit_common::dbus::{Connection as DBusConnection, SettingsPortal};
+use winit_common::xkb::KeymapSource;
 use winit_core::application::ApplicationHandler;
 use winit_core::cursor::{CustomCursor as CoreCustomCursor, CustomCursorSource};
 use winit_core::data_transfer::{DataTransfer, DataTransferId, DataTransferSend, TransferType};
 use winit_core::error::{EventLoopError, NotSupportedError, OsError, RequestError};
-use winit_core::event::{DeviceEvent, StartCause, SurfaceSizeWriter, WindowEvent};
+use winit_core::event::{Compose, DeviceEvent, StartCause, SurfaceSizeWriter, WindowEvent};
 use winit_core::event_loop::pump_events::PumpStatus;
 use winit_core::event_loop::{
     ActiveEventLoop as RootActiveEventLoop, AsyncRequestSerial, ControlFlow, DeviceEvents,
-    DndAction, DragIcon, EventLoopProvider, OwnedDisplayHandle as CoreOwnedDisplayHandle,
+    DndAction, DragIcon, EventLoopProvider, FdReadiness, FdRegistration, KeyRepeat,
+    OwnedDisplayHandle as CoreOwnedDisplayHandle, PointerCoalescing, SystemSettings, TimerId,
+    TimerSchedule, proxy_channel,
 };
 use winit_core::icon::RgbaIcon;
+use winit_core::keyboard::{Key, KeyboardLayouts, ModifiersState, PhysicalKey};
 use winit_core::monitor::MonitorHandle as CoreMonitorHandle;
 use winit_core::window::{Theme, WindowType};
 
@@ -68,7 +77,11 @@ pub struct EventLoop {
     /// Has `run` or `run_on_demand` been called or a call to `pump_events` that starts the loop
     loop_running: bool,
 
+    /// Whether the connection to the compositor was lost.
+    connection_lost: bool,
+
     buffer_sink: EventSink,
+    click_counter: ClickCounter,
     compositor_updates: Vec<WindowCompositorUpdate>,
     window_ids: Vec<WindowId>,
 
@@ -90,7 +103,7 @@ pub struct EventLoop {
 }
 
 impl EventLoop {
-    pub fn new() -> Result<EventLoop, EventLoopError> {
+    pub fn new(keymap: Option<KeymapSource>) -> Result<EventLoop, EventLoopError> {
         static EVENT_LOOP_CREATED: AtomicBool = AtomicBool::new(false);
         if EVENT_LOOP_CREATED.swap(true, Ordering::Relaxed) {
             // For better cross-platformness.
@@ -106,7 +119,8 @@ impl EventLoop {
         let event_loop =
             calloop::EventLoop::<WinitState>::try_new().map_err(|err| os_error!(err))?;
 
-        let mut winit_state = WinitState::new(&globals, &queue_handle, event_loop.handle())?;
+        let mut winit_state =
+            WinitState::new(&globals, &queue_handle, event_loop.handle(), keymap)?;
 
         // NOTE: do a roundtrip after binding the globals to prevent potential
         // races with the server.
@@ -153,6 +167,42 @@ impl EventLoop {
             })
             .map_err(|err| os_error!(err))?;
 
+        // Follow the desktop settings through the xdg-desktop-portal, the connection and the
+        // settings being set up from the calloop source without blocking here.
+        match DBusConnection::session().and_then(SettingsPortal::new) {
+            Ok(portal) => {
+                let fd = portal.as_fd().try_clone_to_owned().map_err(|err| os_error!(err))?;
+                winit_state.settings_portal = Some(portal);
+                event_loop
+                    .handle()
+                    .insert_source(
+                        Generic::new(fd, Interest::READ, Mode::Level),
+                        |_, _, winit_state: &mut WinitState| {
+                            let Some(portal) = winit_state.settings_portal.as_mut() else {
+                                return Ok(PostAction::Remove);
+                            };
+
+                            match portal.dispatch() {
+                                Ok(true) => {
+                                    winit_state.system_settings_changed = true;
+                                    winit_state.dispatched_events = true;
+                                },
+                                Ok(false) => (),
+                                Err(err) => {
+                                    warn!("lost the connection to the settings portal: {err}");
+                                    winit_state.settings_portal = None;
+                                    return Ok(PostAction::Remove);
+                                },
+                            }
+
+                            Ok(PostAction::Continue)
+                        },
+                    )
+                    .map_err(|err| os_error!(err))?;
+            },
+            Err(err) => tracing::debug!("settings portal not available: {err}"),
+        }
+
         let handle = Arc::new(OwnedDisplayHandle::new(connection));
         let active_event_loop = ActiveEventLoop {
             handle: handle.clone(),
@@ -169,6 +219,8 @@ impl EventLoop {
             loop_running: false,
             compositor_updates: Vec::new(),
             buffer_sink: EventSink::default(),
+            click_counter: ClickCounter::new(),
+            connection_lost: false,
             window_ids: Vec::new(),
             handle,
             wayland_dispatcher,
@@ -199,6 +251,10 @@ impl EventLoop {
             }
         };
 
+        if self.connection_lost {
+            return Err(EventLoopError::ConnectionLost);
+        }
+
         // Applications aren't allowed to carry windows between separate
         // `run_on_demand` calls but if they have only just dropped their
         // windows we need to make sure those last requests are sent to the
@@ -260,7 +316,7 @@ impl EventLoop {
 
             timeout = {
                 let control_flow_timeout = match self.control_flow() {
-                    ControlFlow::Wait => None,
+                    ControlFlow::Wait | ControlFlow::Animate => None,
                     ControlFlow::Poll => Some(Duration::ZERO),
                     ControlFlow::WaitUntil(wait_deadline) => {
                         Some(wait_deadline.saturating_duration_since(start))
@@ -275,8 +331,9 @@ impl EventLoop {
             //
             // Checking for flush error is essential to perform an exit with error, since
             // once we have a protocol error, we could get stuck retrying...
-            if self.handle.connection.flush().is_err() {
-                self.set_exit_code(1);
+            if let Err(error) = self.handle.connection.flush() {
+                let lost = matches!(&error, WaylandError::Io(error) if is_connection_lost(error));
+                self.connection_failed(app, lost, 1);
                 return;
             }
 
@@ -284,12 +341,11 @@ impl EventLoop {
                 // NOTE We exit on errors from dispatches, since if we've got protocol error
                 // libwayland-client/wayland-rs will inform us anyway, but crashing downstream is
                 // not really an option. Instead we inform that the event loop got
-                // destroyed. We may communicate an error that something was
-                // terminated, but winit doesn't provide us with an API to do that
-                // via some event. Still, we set the exit code to the error's OS
-                // error code, or to 1 if not possible.
+                // destroyed, with `ApplicationHandler::connection_lost` when the compositor went
+                // away. We set the exit code to the error's OS error code, or to 1 if not
+                // possible.
                 let exit_code = error.raw_os_error().unwrap_or(1);
-                self.set_exit_code(exit_code);
+                self.connection_failed(app, is_connection_lost(&error), exit_code);
                 return;
             }
 
@@ -297,7 +353,9 @@ impl EventLoop {
             // to be considered here
             let cause = match self.control_flow() {
                 ControlFlow::Poll => StartCause::Poll,
-                ControlFlow::Wait => StartCause::WaitCancelled { start, requested_resume: None },
+                ControlFlow::Wait | ControlFlow::Animate => {
+                    StartCause::WaitCancelled { start, requested_resume: None }
+                },
                 ControlFlow::WaitUntil(deadline) => {
                     if Instant::now() < deadline {
                         StartCause::WaitCancelled { start, requested_resume: Some(deadline) }
@@ -372,9 +430,14 @@ impl EventLoop {
 
         // Indicate user wake up.
         if self.with_state(|state| mem::take(&mut state.proxy_wake_up)) {
+            proxy_channel::dispatch_handlers();
             app.proxy_wake_up(&self.active_event_loop);
         }
 
+        if self.with_state(|state| mem::take(&mut state.system_settings_changed)) {
+            app.system_settings_changed(&self.active_event_loop);
+        }
+
         // Drain the pending compositor updates.
         self.with_state(|state| compositor_updates.append(&mut state.window_compositor_updates));
 
@@ -445,6 +508,16 @@ impl EventLoop {
                 app.window_event(&self.active_event_loop, window_id, event);
             }
 
+            if compositor_update.states_changed {
+                let states = self.with_state(|state| {
+                    let windows = state.windows.get_mut();
+                    windows.get(&window_id).unwrap().lock().unwrap().states()
+                });
+
+                let event = WindowEvent::StateChanged(states);
+                app.window_event(&self.active_event_loop, window_id, event);
+            }
+
             if compositor_update.close_window {
                 app.window_event(&self.active_event_loop, window_id, WindowEvent::CloseRequested);
             }
@@ -466,19 +539,40 @@ impl EventLoop {
         }
 
         // Handle non-synthetic events.
-        self.with_state(|state| {
+        let (coalescing, settings) = self.with_state(|state| {
             buffer_sink.append(&mut state.events_sink);
+            let settings = state
+                .settings_portal
+                .as_ref()
+                .map(SettingsPortal::system_settings)
+                .unwrap_or_default();
+            (state.pointer_coalescing, settings)
         });
+        let mut coalescing_app = CoalescingHandler::new(coalescing, &mut *app);
         for event in buffer_sink.drain() {
             match event {
-                Event::WindowEvent { window_id, event } => {
-                    app.window_event(&self.active_event_loop, window_id, event)
+                Event::WindowEvent { window_id, mut event } => {
+                    self.click_counter.button(window_id, &mut event, &settings);
+                    self.click_counter.moved(window_id, &event);
+                    coalescing_app.window_event(&self.active_event_loop, window_id, event)
                 },
                 Event::DeviceEvent { event } => {
-                    app.device_event(&self.active_event_loop, None, event)
+                    coalescing_app.device_event(&self.active_event_loop, None, event)
                 },
             }
         }
+        coalescing_app.flush(&self.active_event_loop);
+
+        // Fire the timers, and report the ready file descriptors. Both may be unregistered by the
+        // application meanwhile.
+        while let Some(timer_id) = self.with_state(|state| state.sources.take_fired_timer()) {
+            app.timer_fired(&self.active_event_loop, timer_id);
+        }
+        while let Some((source_id, readiness)) =
+            self.with_state(|state| state.sources.take_ready_fd())
+        {
+            app.fd_ready(&self.active_event_loop, source_id, readiness);
+        }
 
         // Collect the window ids
         self.with_state(|state| {
@@ -512,9 +606,28 @@ impl EventLoop {
                 continue;
             }
 
+            let compose_cancelled = self.with_state(|state| {
+                if !state.window_requests.get_mut().get(window_id).unwrap().take_cancel_compose() {
+                    return false;
+                }
+
+                state
+                    .seats
+                    .values_mut()
+                    .fold(false, |cancelled, seat| seat.cancel_compose(*window_id) | cancelled)
+            });
+
+            if compose_cancelled {
+                let event = WindowEvent::Compose(Compose::Cancelled);
+                app.window_event(&self.active_event_loop, *window_id, event);
+            }
+
+            let animate = self.control_flow() == ControlFlow::Animate;
             let event = self.with_state(|state| {
                 let mut window =
                     state.windows.get_mut().get_mut(window_id).unwrap().lock().unwrap();
+                let window_requests = state.window_requests.get_mut();
+                let window_requests = window_requests.get(window_id).unwrap();
 
                 if window.frame_callback_state() == FrameCallbackState::Requested {
                     return None;
@@ -522,13 +635,18 @@ impl EventLoop {
 
                 // Reset the frame callbacks state.
                 window.frame_callback_reset();
-                let window_requests = state.window_requests.get_mut();
-                let mut redraw_requested =
-                    window_requests.get(window_id).unwrap().take_redraw_requested();
+                let mut redraw_requested = window_requests.take_redraw_requested();
 
                 // Redraw the frame while at it.
                 redraw_requested |= window.refresh_frame();
 
+                // Animated windows are redrawn once the compositor is ready for their next frame,
+                // paced by the frame callback committed with the redraw.
+                if animate {
+                    redraw_requested = true;
+                    window.request_frame_callback();
+                }
+
                 redraw_requested.then_some(WindowEvent::RedrawRequested)
             });
 
@@ -607,6 +725,23 @@ impl EventLoop {
         event_queue.roundtrip(state).map_err(|err| os_error!(err))
     }
 
+    /// Exit after an error of the event loop, telling the application when the connection was
+    /// lost rather than closed because of a protocol error.
+    fn connection_failed<A: ApplicationHandler>(
+        &mut self,
+        app: &mut A,
+        lost: bool,
+        exit_code: i32,
+    ) {
+        if lost && !self.connection_lost && self.handle.connection.protocol_error().is_none() {
+            tracing::error!("lost the connection to the compositor");
+            self.connection_lost = true;
+            app.connection_lost(&self.active_event_loop);
+        }
+
+        self.set_exit_code(exit_code);
+    }
+
     fn control_flow(&self) -> ControlFlow {
         self.active_event_loop.control_flow()
     }
@@ -624,6 +759,12 @@ impl EventLoop {
     }
 }
 
+/// Whether the error of the Wayland connection tells that the compositor went away, unlike the
+/// errors of the other sources of the event loop.
+fn is_connection_lost(error: &io::Error) -> bool {
+    matches!(error.kind(), io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset)
+}
+
 impl EventLoopProvider for EventLoop {
     fn run_app<A: ApplicationHandler + 'static>(
         mut self,
@@ -742,6 +883,53 @@ impl RootActiveEventLoop for ActiveEventLoop {
         None
     }
 
+    fn system_settings(&self) -> SystemSettings {
+        let state = self.state.borrow();
+        let mut settings =
+            state.settings_portal.as_ref().map(SettingsPortal::system_settings).unwrap_or_default();
+
+        if let Some((delay, interval)) = state.keyboard_seat().and_then(|seat| seat.key_repeat()) {
+            settings.key_repeat_delay = Some(delay);
+            settings.key_repeat_interval = Some(interval);
+        }
+
+        settings
+    }
+
+    fn add_timer(&self, schedule: TimerSchedule) -> Result<TimerId, RequestError> {
+        self.state.borrow().sources.add_timer(schedule)
+    }
+
+    fn cancel_timer(&self, timer_id: TimerId) {
+        self.state.borrow().sources.cancel_timer(timer_id)
+    }
+
+    fn set_key_repeat(&self, key_repeat: KeyRepeat) {
+        let mut state = self.state.borrow_mut();
+        if state.key_repeat != key_repeat {
+            state.key_repeat = key_repeat;
+            state.seats.values_mut().for_each(|seat| seat.stop_key_repeat());
+        }
+    }
+
+    fn set_pointer_coalescing(&self, coalescing: PointerCoalescing) {
+        self.state.borrow_mut().pointer_coalescing = coalescing;
+    }
+
+    fn keyboard_layouts(&self) -> Option<KeyboardLayouts> {
+        self.state.borrow().keyboard_seat()?.keyboard_layouts()
+    }
+
+    fn key_for_physical(&self, key: PhysicalKey, modifiers: ModifiersState) -> Option<Key> {
+        let key_lookup = self.state.borrow().keyboard_seat()?.key_lookup()?;
+        key_lookup.key_for_physical(key, modifiers)
+    }
+
+    fn physical_for_key(&self, key: &Key) -> Option<(PhysicalKey, ModifiersState)> {
+        let key_lookup = self.state.borrow().keyboard_seat()?.key_lookup()?;
+        key_lookup.physical_for_key(key)
+    }
+
     fn create_window(
         &self,
         window_attributes: winit_core::window::WindowAttributes,
@@ -1011,6 +1199,15 @@ impl fmt::Display for UnknownDataTransfer {
 impl std::error::Error for UnknownDataTransfer {}
 
 impl ActiveEventLoop {
+    /// Watch a file descriptor of the application.
+    pub(crate) fn register_fd(
+        &self,
+        fd: BorrowedFd<'_>,
+        interest: FdReadiness,
+    ) -> Result<FdRegistration, RequestError> {
+        self.state.borrow().sources.register_fd(fd, interest)
+    }
+
     fn clear_exit(&self) {
         self.exit.set(None)
     }
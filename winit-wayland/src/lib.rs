@@ -20,6 +20,7 @@
 
 use std::ffi::c_void;
 use std::hash::BuildHasher;
+use std::os::fd::BorrowedFd;
 use std::ptr::NonNull;
 
 use dpi::{LogicalSize, PhysicalSize, Position, Size};
@@ -29,7 +30,8 @@ use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::shm::slot::{Buffer, CreateBufferError, SlotPool};
 use wayland_client::protocol::wl_shm::Format;
 use winit_core::data_transfer::DataTransferId;
-use winit_core::event_loop::ActiveEventLoop as CoreActiveEventLoop;
+use winit_core::error::{NotSupportedError, RequestError};
+use winit_core::event_loop::{ActiveEventLoop as CoreActiveEventLoop, FdReadiness, FdRegistration};
 use winit_core::window::{
     ActivationToken, PlatformWindowAttributes, Window as CoreWindow, WindowId,
 };
@@ -47,6 +49,8 @@ mod state;
 mod types;
 mod window;
 
+pub use winit_common::xkb::KeymapSource;
+
 pub use self::dnd::{DataOffer, DragSource, MimeData, MimeType};
 pub use self::event_loop::{ActiveEventLoop, EventLoop};
 pub use self::popup::Popup;
@@ -56,6 +60,20 @@ pub use self::window::Window;
 pub trait ActiveEventLoopExtWayland {
     /// True if the [`ActiveEventLoop`] uses Wayland.
     fn is_wayland(&self) -> bool;
+
+    /// Watch a file descriptor with the event loop, reporting its readiness with
+    /// [`ApplicationHandler::fd_ready()`] until the returned registration is dropped.
+    ///
+    /// `interest` selects whether to watch for [`FdReadiness::READABLE`] and
+    /// [`FdReadiness::WRITABLE`]. The file descriptor is duplicated, so the original may be
+    /// closed independently.
+    ///
+    /// [`ApplicationHandler::fd_ready()`]: winit_core::application::ApplicationHandler::fd_ready
+    fn register_fd(
+        &self,
+        fd: BorrowedFd<'_>,
+        interest: FdReadiness,
+    ) -> Result<FdRegistration, RequestError>;
 }
 
 impl ActiveEventLoopExtWayland for dyn CoreActiveEventLoop + '_ {
@@ -63,6 +81,17 @@ impl ActiveEventLoopExtWayland for dyn CoreActiveEventLoop + '_ {
     fn is_wayland(&self) -> bool {
         self.cast_ref::<ActiveEventLoop>().is_some()
     }
+
+    fn register_fd(
+        &self,
+        fd: BorrowedFd<'_>,
+        interest: FdReadiness,
+    ) -> Result<FdRegistration, RequestError> {
+        match self.cast_ref::<ActiveEventLoop>() {
+            Some(event_loop) => event_loop.register_fd(fd, interest),
+            None => Err(NotSupportedError::new("the event loop doesn't use Wayland").into()),
+        }
+    }
 }
 
 /// Additional methods on [`EventLoop`] that are specific to Wayland.
@@ -81,6 +110,12 @@ pub trait EventLoopBuilderExtWayland {
     /// By default, the window is only allowed to be created on the main
     /// thread, to make platform compatibility easier.
     fn with_any_thread(&mut self, any_thread: bool) -> &mut Self;
+
+    /// Interpret the keys with the given keymap instead of the one sent by the compositor.
+    ///
+    /// The compositor still provides the modifier and layout state, whose masks are applied to
+    /// the keymap as is. The keymap should thus map the real modifiers the usual way.
+    fn with_keymap(&mut self, keymap: KeymapSource) -> &mut Self;
 }
 
 /// Additional methods on [`Window`] that are specific to Wayland.
@@ -17,7 +17,7 @@ use winit_core::error::{NotSupportedError, RequestError};
 use winit_core::event::{Ime, WindowEvent};
 use winit_core::monitor::{Fullscreen, MonitorHandle as CoreMonitorHandle};
 use winit_core::window::{
-    CursorGrabMode, ImeCapabilities, ImeRequest, ImeRequestError, ResizeDirection, Theme,
+    CursorGrabMode, ImeCapabilities, ImeRequest, ImeRequestError, Rect, ResizeDirection, Theme,
     UserAttentionType, Window as CoreWindow, WindowAttributes, WindowButtons, WindowId,
     WindowLevel,
 };
@@ -228,6 +228,7 @@ impl Popup {
             let window_requests = WindowRequests {
                 redraw_requested: AtomicBool::new(true),
                 closed: AtomicBool::new(false),
+                cancel_compose: AtomicBool::new(false),
             };
             let window_requests = Arc::new(window_requests);
             state.window_requests.get_mut().insert(window_id, window_requests.clone());
@@ -271,8 +272,6 @@ impl Popup {
 
                     xdg_activation,
                     attention_requested: Arc::new(AtomicBool::new(false)),
-
-                    compositor: state.compositor_state.clone(),
                 },
             })
         } else {
@@ -311,6 +310,10 @@ impl CoreWindow for Popup {
         winit_common::xkb::reset_dead_keys()
     }
 
+    fn cancel_compose(&self) {
+        self.handles.cancel_compose();
+    }
+
     fn surface_position(&self) -> PhysicalPosition<i32> {
         (0, 0).into()
     }
@@ -598,11 +601,15 @@ impl CoreWindow for Popup {
     }
 
     fn set_cursor_hittest(&self, hittest: bool) -> Result<(), RequestError> {
+        self.set_input_region(if hittest { None } else { Some(&[]) })
+    }
+
+    fn set_input_region(&self, region: Option<&[Rect]>) -> Result<(), RequestError> {
         let Some(state) = self.popup_state.upgrade() else {
             return Err(RequestError::Ignored);
         };
 
-        self.handles.set_cursor_hittest(state.lock().unwrap().window.wl_surface(), hittest)
+        state.lock().unwrap().set_input_region(region)
     }
 
     fn current_monitor(&self) -> Option<CoreMonitorHandle> {
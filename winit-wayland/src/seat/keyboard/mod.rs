@@ -11,12 +11,15 @@ use sctk::reexports::client::protocol::wl_keyboard::{
 use sctk::reexports::client::protocol::wl_seat::WlSeat;
 use sctk::reexports::client::{Connection, Dispatch, Proxy, QueueHandle, WEnum};
 use tracing::warn;
-use winit_common::xkb::Context;
-use winit_core::event::{ElementState, WindowEvent};
+use winit_common::event_timestamp::event_timestamp;
+use winit_common::xkb::{Context, KeymapSource};
+use winit_core::event::{ElementState, EventTimestamp, WindowEvent};
+use winit_core::event_loop::KeyRepeat;
 use winit_core::keyboard::ModifiersState;
 
 use crate::WindowId;
 use crate::event_loop::sink::EventSink;
+use crate::seat::text_input::{self, TextInput};
 use crate::state::WinitState;
 
 impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
@@ -51,7 +54,20 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     },
                     WlKeymapFormat::XkbV1 => {
                         let context = &mut keyboard_state.xkb_context;
+                        let old_layouts = context.keyboard_layouts();
                         context.set_keymap_from_fd(fd, size as usize);
+
+                        let layouts = context
+                            .keyboard_layouts()
+                            .filter(|layouts| Some(layouts) != old_layouts.as_ref());
+                        if let (Some(layouts), Some(window_id)) =
+                            (layouts, *data.window_id.lock().unwrap())
+                        {
+                            state.events_sink.push_window_event(
+                                WindowEvent::KeyboardLayoutChanged(layouts),
+                                window_id,
+                            );
+                        }
                     },
                     _ => unreachable!(),
                 },
@@ -89,13 +105,22 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                 // HACK: this is just for GNOME not fixing their ordering issue of modifiers.
                 if std::mem::take(&mut seat_state.modifiers_pending) {
                     state.events_sink.push_window_event(
-                        WindowEvent::ModifiersChanged(seat_state.modifiers.into()),
+                        WindowEvent::ModifiersChanged(seat_state.modifiers),
                         window_id,
                     );
                 }
+
+                // The text-input-v1 is activated along with the keyboard focus.
+                if let Some(text_input) =
+                    seat_state.text_input.clone().filter(TextInput::follows_keyboard_focus)
+                {
+                    text_input::keyboard_entered(state, &text_input, surface);
+                }
             },
             WlKeyboardEvent::Leave { surface, .. } => {
                 let window_id = crate::make_wid(&surface);
+                let text_input =
+                    seat_state.text_input.clone().filter(TextInput::follows_keyboard_focus);
 
                 // NOTE: we should drop the repeat regardless whether it was for the present
                 // window of for the window which just went gone.
@@ -119,6 +144,10 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                 // anyway.
                 *data.window_id.lock().unwrap() = None;
 
+                if let Some(text_input) = text_input {
+                    text_input::keyboard_left(state, &text_input, surface);
+                }
+
                 if !focused {
                     // Notify that no modifiers are being pressed.
                     state.events_sink.push_window_event(
@@ -129,10 +158,16 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     state.events_sink.push_window_event(WindowEvent::Focused(false), window_id);
                 }
             },
-            WlKeyboardEvent::Key { serial, key, state: WEnum::Value(key_state), .. }
+            WlKeyboardEvent::Key { serial, time, key, state: WEnum::Value(key_state), .. }
                 if matches!(key_state, WlKeyState::Repeated | WlKeyState::Pressed) =>
             {
                 seat_state.latest_input_serial.set(Some(serial));
+
+                // The repeats of the compositor follow the system settings.
+                if key_state == WlKeyState::Repeated && state.key_repeat != KeyRepeat::System {
+                    return;
+                }
+
                 let key = key + 8;
                 key_input(
                     keyboard_state,
@@ -141,9 +176,10 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     key,
                     ElementState::Pressed,
                     key_state == WlKeyState::Repeated,
+                    Some(event_timestamp(time)),
                 );
 
-                let delay = match keyboard_state.repeat_info {
+                let delay = match keyboard_state.effective_repeat_info(state.key_repeat) {
                     RepeatInfo::Repeat { delay, .. } => delay,
                     // When compositor handles repeat, and thus we have `repeat = true`, we have
                     // repeat on our side disabled, if it's not true, it's a compositor bug.
@@ -195,10 +231,11 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                             repeat_keycode,
                             ElementState::Pressed,
                             true,
+                            None,
                         );
 
                         // NOTE: the gap could change dynamically while repeat is going.
-                        match keyboard_state.repeat_info {
+                        match keyboard_state.effective_repeat_info(state.key_repeat) {
                             RepeatInfo::Repeat { gap, .. } => TimeoutAction::ToDuration(gap),
                             RepeatInfo::Disable => TimeoutAction::Drop,
                         }
@@ -206,7 +243,11 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     .ok();
             },
             WlKeyboardEvent::Key {
-                serial, key, state: WEnum::Value(WlKeyState::Released), ..
+                serial,
+                time,
+                key,
+                state: WEnum::Value(WlKeyState::Released),
+                ..
             } => {
                 seat_state.latest_input_serial.set(Some(serial));
                 let key = key + 8;
@@ -218,16 +259,14 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     key,
                     ElementState::Released,
                     false,
+                    Some(event_timestamp(time)),
                 );
 
-                if keyboard_state.repeat_info != RepeatInfo::Disable
+                if keyboard_state.effective_repeat_info(state.key_repeat) != RepeatInfo::Disable
                     && keyboard_state.xkb_context.keymap_mut().unwrap().key_repeats(key)
                     && Some(key) == keyboard_state.current_repeat
                 {
-                    keyboard_state.current_repeat = None;
-                    if let Some(token) = keyboard_state.repeat_token.take() {
-                        keyboard_state.loop_handle.remove(token);
-                    }
+                    keyboard_state.stop_repeat();
                 }
             },
             WlKeyboardEvent::Modifiers {
@@ -239,8 +278,11 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     None => return,
                 };
 
+                let old_layout = xkb_state.active_layout();
                 xkb_state.update_modifiers(mods_depressed, mods_latched, mods_locked, 0, 0, group);
+                let layout_changed = xkb_state.active_layout() != old_layout;
                 seat_state.modifiers = xkb_state.modifiers().into();
+                let layouts = xkb_context.keyboard_layouts().filter(|_| layout_changed);
 
                 // HACK: part of the workaround from `WlKeyboardEvent::Enter`.
                 let window_id = match *data.window_id.lock().unwrap() {
@@ -251,17 +293,22 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     },
                 };
 
+                if let Some(layouts) = layouts {
+                    let event = WindowEvent::KeyboardLayoutChanged(layouts);
+                    state.events_sink.push_window_event(event, window_id);
+                }
+
                 state.events_sink.push_window_event(
-                    WindowEvent::ModifiersChanged(seat_state.modifiers.into()),
+                    WindowEvent::ModifiersChanged(seat_state.modifiers),
                     window_id,
                 );
             },
             WlKeyboardEvent::RepeatInfo { rate, delay } => {
+                let old_repeat_info = keyboard_state.repeat_info;
                 keyboard_state.repeat_info = if rate == 0 {
                     // Stop the repeat once we get a disable event.
-                    keyboard_state.current_repeat = None;
-                    if let Some(repeat_token) = keyboard_state.repeat_token.take() {
-                        keyboard_state.loop_handle.remove(repeat_token);
+                    if state.key_repeat == KeyRepeat::System {
+                        keyboard_state.stop_repeat();
                     }
                     RepeatInfo::Disable
                 } else {
@@ -269,6 +316,12 @@ impl Dispatch<WlKeyboard, KeyboardData, WinitState> for WinitState {
                     let delay = Duration::from_millis(delay as u64);
                     RepeatInfo::Repeat { gap, delay }
                 };
+
+                // The repeat rate is exposed through the system settings.
+                if keyboard_state.repeat_info != old_repeat_info {
+                    state.system_settings_changed = true;
+                    state.dispatched_events = true;
+                }
             },
             _ => unreachable!(),
         }
@@ -298,16 +351,61 @@ pub struct KeyboardState {
 }
 
 impl KeyboardState {
-    pub fn new(keyboard: WlKeyboard, loop_handle: LoopHandle<'static, WinitState>) -> Self {
+    pub fn new(
+        keyboard: WlKeyboard,
+        loop_handle: LoopHandle<'static, WinitState>,
+        keymap: Option<&KeymapSource>,
+    ) -> Self {
+        let mut xkb_context = Context::new().unwrap();
+        if let Some(keymap) = keymap {
+            xkb_context.set_fixed_keymap(keymap);
+        }
+
         Self {
             keyboard,
             loop_handle,
-            xkb_context: Context::new().unwrap(),
+            xkb_context,
             repeat_info: RepeatInfo::default(),
             repeat_token: None,
             current_repeat: None,
         }
     }
+
+    /// The repeat to apply with the key repeat policy of the application.
+    pub fn effective_repeat_info(&self, key_repeat: KeyRepeat) -> RepeatInfo {
+        match key_repeat {
+            KeyRepeat::System => self.repeat_info,
+            _ => match key_repeat.custom_interval() {
+                Some((delay, gap)) => RepeatInfo::Repeat { gap, delay },
+                None => RepeatInfo::Disable,
+            },
+        }
+    }
+
+    /// Stop repeating the held key.
+    pub fn stop_repeat(&mut self) {
+        self.current_repeat = None;
+        if let Some(token) = self.repeat_token.take() {
+            self.loop_handle.remove(token);
+        }
+    }
+
+    /// Whether the keyboard is focused on a window.
+    pub fn is_focused(&self) -> bool {
+        self.keyboard
+            .data::<KeyboardData>()
+            .is_some_and(|data| data.window_id.lock().unwrap().is_some())
+    }
+
+    /// Cancel the pending compose sequence when the keyboard is focused on the window, returning
+    /// whether there was one.
+    pub fn cancel_compose(&mut self, window_id: WindowId) -> bool {
+        let focused = self
+            .keyboard
+            .data::<KeyboardData>()
+            .is_some_and(|data| *data.window_id.lock().unwrap() == Some(window_id));
+        focused && self.xkb_context.cancel_compose()
+    }
 }
 
 impl Drop for KeyboardState {
@@ -370,15 +468,24 @@ fn key_input(
     keycode: u32,
     state: ElementState,
     repeat: bool,
+    timestamp: Option<EventTimestamp>,
 ) {
     let window_id = match *data.window_id.lock().unwrap() {
         Some(window_id) => window_id,
         None => return,
     };
 
-    if let Some(mut key_context) = keyboard_state.xkb_context.key_context() {
-        let event = key_context.process_key_event(keycode, state, repeat);
-        let event = WindowEvent::KeyboardInput { device_id: None, event, is_synthetic: false };
+    let xkb_context = &mut keyboard_state.xkb_context;
+    let event = xkb_context
+        .key_context()
+        .map(|mut key_context| key_context.process_key_event(keycode, state, repeat));
+    if let Some(event) = event {
+        if let Some(compose) = xkb_context.take_compose() {
+            event_sink.push_window_event(WindowEvent::Compose(compose), window_id);
+        }
+
+        let event =
+            WindowEvent::KeyboardInput { device_id: None, event, is_synthetic: false, timestamp };
         event_sink.push_window_event(event, window_id);
     }
 }
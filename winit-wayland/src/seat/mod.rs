@@ -2,6 +2,7 @@
 
 use std::cell::Cell;
 use std::sync::Arc;
+use std::time::Duration;
 
 use foldhash::HashMap;
 use sctk::data_device_manager::data_device::DataDevice;
@@ -10,16 +11,17 @@ use sctk::reexports::client::protocol::wl_seat::WlSeat;
 use sctk::reexports::client::protocol::wl_touch::WlTouch;
 use sctk::reexports::client::{Connection, Proxy, QueueHandle};
 use sctk::reexports::protocols::wp::relative_pointer::zv1::client::zwp_relative_pointer_v1::ZwpRelativePointerV1;
-use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::ZwpTextInputV3;
 use sctk::seat::pointer::{PointerData, ThemeSpec, ThemedPointer};
 use sctk::seat::{Capability as SeatCapability, SeatHandler, SeatState};
 use tracing::warn;
 use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_hold_v1::ZwpPointerGestureHoldV1;
 use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1;
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_seat_v2::ZwpTabletSeatV2;
-use winit_core::event::WindowEvent;
-use winit_core::keyboard::ModifiersState;
+use winit_common::xkb::KeyLookup;
+use winit_core::event::{Modifiers, WindowEvent};
+use winit_core::keyboard::KeyboardLayouts;
 
+use crate::WindowId;
 use crate::state::WinitState;
 
 mod keyboard;
@@ -27,16 +29,13 @@ mod pointer;
 mod text_input;
 mod touch;
 
-use keyboard::{KeyboardData, KeyboardState};
+use keyboard::{KeyboardData, KeyboardState, RepeatInfo};
 pub use pointer::pointer_gesture::{PointerGestureData, PointerGesturesState};
 pub use pointer::relative_pointer::RelativePointerState;
 pub use pointer::{PointerConstraintsState, WinitPointerData, WinitPointerDataExt};
-use text_input::TextInputData;
-pub use text_input::{ClientState as TextInputClientState, TextInputState};
+pub use text_input::{ClientState as TextInputClientState, TextInput, TextInputState};
 use touch::TouchPoint;
 
-pub(crate) use crate::seat::text_input::ZwpTextInputV3Ext;
-
 #[derive(Debug, Default)]
 pub struct WinitSeatState {
     /// The pointer bound on the seat.
@@ -52,7 +51,7 @@ pub struct WinitSeatState {
     first_touch_id: Option<i32>,
 
     /// The text input bound on the seat.
-    text_input: Option<Arc<ZwpTextInputV3>>,
+    text_input: Option<TextInput>,
 
     /// The tablet input bound on the seat.
     tablet: Option<Arc<ZwpTabletSeatV2>>,
@@ -73,7 +72,7 @@ pub struct WinitSeatState {
     keyboard_state: Option<KeyboardState>,
 
     /// The current modifiers state on the seat.
-    modifiers: ModifiersState,
+    modifiers: Modifiers,
 
     /// Whether we have pending modifiers.
     modifiers_pending: bool,
@@ -102,6 +101,40 @@ impl WinitSeatState {
     pub(crate) fn pointer_data(&self) -> Option<&PointerData<WinitPointerData>> {
         self.pointer.as_ref().and_then(|pointer| pointer.pointer().data())
     }
+
+    /// The key repeat delay and interval of the keyboard on this seat.
+    ///
+    /// Both are zero when key repeat is disabled.
+    pub(crate) fn key_repeat(&self) -> Option<(Duration, Duration)> {
+        let keyboard_state = self.keyboard_state.as_ref()?;
+        Some(match keyboard_state.repeat_info {
+            RepeatInfo::Repeat { gap, delay } => (delay, gap),
+            RepeatInfo::Disable => (Duration::ZERO, Duration::ZERO),
+        })
+    }
+
+    /// The keyboard layouts of the keymap on this seat.
+    pub(crate) fn keyboard_layouts(&self) -> Option<KeyboardLayouts> {
+        self.keyboard_state.as_ref()?.xkb_context.keyboard_layouts()
+    }
+
+    /// The keymap and active layout of the keyboard on this seat.
+    pub(crate) fn key_lookup(&self) -> Option<KeyLookup> {
+        self.keyboard_state.as_ref()?.xkb_context.key_lookup()
+    }
+
+    /// Stop repeating the held key, e.g. when the key repeat policy changed.
+    pub(crate) fn stop_key_repeat(&mut self) {
+        if let Some(keyboard_state) = self.keyboard_state.as_mut() {
+            keyboard_state.stop_repeat();
+        }
+    }
+
+    /// Cancel the pending compose sequence when the keyboard is focused on the window, returning
+    /// whether there was one.
+    pub(crate) fn cancel_compose(&mut self, window_id: WindowId) -> bool {
+        self.keyboard_state.as_mut().is_some_and(|state| state.cancel_compose(window_id))
+    }
 }
 
 impl SeatHandler for WinitState {
@@ -130,8 +163,11 @@ impl SeatHandler for WinitState {
             },
             SeatCapability::Keyboard if seat_state.keyboard_state.is_none() => {
                 let keyboard = seat.get_keyboard(queue_handle, KeyboardData::new(seat.clone()));
-                seat_state.keyboard_state =
-                    Some(KeyboardState::new(keyboard, self.loop_handle.clone()));
+                seat_state.keyboard_state = Some(KeyboardState::new(
+                    keyboard,
+                    self.loop_handle.clone(),
+                    self.keymap.as_ref(),
+                ));
             },
             SeatCapability::Pointer if seat_state.pointer.is_none() => {
                 let surface = self.compositor_state.create_surface(queue_handle);
@@ -195,11 +231,7 @@ impl SeatHandler for WinitState {
         if let Some(text_input_state) =
             seat_state.text_input.is_none().then_some(self.text_input_state.as_ref()).flatten()
         {
-            seat_state.text_input = Some(Arc::new(text_input_state.get_text_input(
-                &seat,
-                queue_handle,
-                TextInputData::default(),
-            )));
+            seat_state.text_input = Some(text_input_state.get_text_input(&seat, queue_handle));
         }
 
         if let Some(tablet_state) =
@@ -301,6 +333,15 @@ impl SeatHandler for WinitState {
 }
 
 impl WinitState {
+    /// The seat whose keyboard is focused on a window, or any seat with a keyboard when none is.
+    pub(crate) fn keyboard_seat(&self) -> Option<&WinitSeatState> {
+        let mut seats = self.seats.values().filter(|seat| seat.keyboard_state.is_some());
+        let first = seats.clone().next();
+        seats
+            .find(|seat| seat.keyboard_state.as_ref().is_some_and(KeyboardState::is_focused))
+            .or(first)
+    }
+
     fn on_keyboard_destroy(&mut self, seat: &ObjectId) {
         for (window_id, window) in self.windows.get_mut() {
             let mut window = window.lock().unwrap();
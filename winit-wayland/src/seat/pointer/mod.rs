@@ -1,5 +1,6 @@
 //! The pointer events.
 
+use std::mem;
 use std::ops::Deref;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -7,7 +8,7 @@ use std::time::Duration;
 use tracing::warn;
 
 use sctk::reexports::client::delegate_dispatch;
-use sctk::reexports::client::protocol::wl_pointer::WlPointer;
+use sctk::reexports::client::protocol::wl_pointer::{AxisSource, WlPointer};
 use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::reexports::client::{Connection, Proxy, QueueHandle, Dispatch};
 use sctk::reexports::protocols::wp::pointer_constraints::zv1::client::zwp_confined_pointer_v1::ZwpConfinedPointerV1;
@@ -22,9 +23,10 @@ use sctk::globals::GlobalData;
 use sctk::seat::pointer::{PointerData, PointerEvent, PointerEventKind, PointerHandler};
 
 use dpi::{LogicalPosition, PhysicalPosition};
+use winit_common::event_timestamp::event_timestamp;
 use winit_core::event::{
-    ButtonSource, ElementState, MouseButton, MouseScrollDelta, PointerKind, PointerSource,
-    TouchPhase, WindowEvent,
+    ButtonSource, ElementState, MouseButton, MouseScrollDelta, PointerKind, PointerSample,
+    PointerSource, ScrollSource, TouchPhase, WindowEvent,
 };
 
 use crate::WindowId;
@@ -58,7 +60,9 @@ impl PointerHandler for WinitState {
             },
         };
 
-        for event in events {
+        // The earlier motions of the frame, reported in the history of the latest one.
+        let mut history = Vec::new();
+        for (index, event) in events.iter().enumerate() {
             let surface = &event.surface;
 
             // The parent surface.
@@ -152,19 +156,32 @@ impl PointerHandler for WinitState {
                         window_id,
                     );
                 },
-                PointerEventKind::Motion { .. } => {
+                PointerEventKind::Motion { time } => {
+                    let timestamp = Some(event_timestamp(time));
+                    let next_is_motion = events.get(index + 1).is_some_and(|next| {
+                        next.surface == event.surface
+                            && matches!(next.kind, PointerEventKind::Motion { .. })
+                    });
+                    if next_is_motion {
+                        let source = PointerSource::Mouse;
+                        history.push(PointerSample { position, source, timestamp });
+                        continue;
+                    }
+
                     self.events_sink.push_window_event(
                         WindowEvent::PointerMoved {
                             primary: true,
                             device_id: None,
                             position,
                             source: PointerSource::Mouse,
+                            timestamp,
+                            history: mem::take(&mut history),
                         },
                         window_id,
                     );
                 },
-                ref kind @ PointerEventKind::Press { button, serial, .. }
-                | ref kind @ PointerEventKind::Release { button, serial, .. } => {
+                ref kind @ PointerEventKind::Press { button, serial, time }
+                | ref kind @ PointerEventKind::Release { button, serial, time } => {
                     // Update the last button serial.
 
                     pointer.winit_data().data().inner.lock().unwrap().latest_button_serial = serial;
@@ -188,11 +205,13 @@ impl PointerHandler for WinitState {
                             position,
                             button,
                             is_macos_activation_click: false,
+                            timestamp: Some(event_timestamp(time)),
+                            click_count: 1,
                         },
                         window_id,
                     );
                 },
-                PointerEventKind::Axis { horizontal, vertical, .. } => {
+                PointerEventKind::Axis { time, horizontal, vertical, source, .. } => {
                     // Get the current phase.
                     let mut pointer_data = pointer.winit_data().data().inner.lock().unwrap();
 
@@ -239,8 +258,34 @@ impl PointerHandler for WinitState {
                         )
                     };
 
+                    // NOTE: Wayland sign convention is the inverse of winit.
+                    let value120 = if has_value120_scroll {
+                        Some((-horizontal.value120, -vertical.value120))
+                    } else if has_discrete_scroll {
+                        Some((-horizontal.discrete * 120, -vertical.discrete * 120))
+                    } else {
+                        None
+                    };
+
+                    let source = match source {
+                        Some(AxisSource::Wheel) => ScrollSource::Wheel,
+                        Some(AxisSource::Finger) => ScrollSource::Finger,
+                        Some(AxisSource::Continuous) => ScrollSource::Continuous,
+                        Some(AxisSource::WheelTilt) => ScrollSource::WheelTilt,
+                        _ => ScrollSource::Unknown,
+                    };
+
+                    let timestamp = Some(event_timestamp(time));
                     self.events_sink.push_window_event(
-                        WindowEvent::MouseWheel { device_id: None, delta, phase },
+                        WindowEvent::MouseWheel {
+                            device_id: None,
+                            delta,
+                            phase,
+                            timestamp,
+                            source,
+                            value120,
+                            stopped: horizontal.stop || vertical.stop,
+                        },
                         window_id,
                     )
                 },
@@ -13,6 +13,7 @@ use sctk::reexports::protocols::wp::pointer_gestures::zv1::client::zwp_pointer_g
 use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_hold_v1::{
     Event as HoldEvent, ZwpPointerGestureHoldV1,
 };
+use winit_common::event_timestamp::event_timestamp;
 use winit_core::event::{TouchPhase, WindowEvent};
 use winit_core::window::WindowId;
 
@@ -83,8 +84,8 @@ impl Dispatch<ZwpPointerGestureHoldV1, PointerGestureData, WinitState> for Point
         _qhandle: &QueueHandle<WinitState>,
     ) {
         let mut pointer_gesture_data = data.inner.lock().unwrap();
-        let (window_id, phase) = match event {
-            HoldEvent::Begin { surface, fingers, .. } => {
+        let (window_id, phase, time) = match event {
+            HoldEvent::Begin { time, surface, fingers, .. } => {
                 if fingers < 2 {
                     return;
                 }
@@ -92,9 +93,9 @@ impl Dispatch<ZwpPointerGestureHoldV1, PointerGestureData, WinitState> for Point
                 let window_id = crate::make_wid(&surface);
                 pointer_gesture_data.window_id = Some(window_id);
 
-                (window_id, TouchPhase::Started)
+                (window_id, TouchPhase::Started, time)
             },
-            HoldEvent::End { cancelled, .. } => {
+            HoldEvent::End { time, cancelled, .. } => {
                 let window_id = match pointer_gesture_data.window_id {
                     Some(window_id) => window_id,
                     _ => return,
@@ -105,14 +106,16 @@ impl Dispatch<ZwpPointerGestureHoldV1, PointerGestureData, WinitState> for Point
 
                 let phase = if cancelled == 0 { TouchPhase::Ended } else { TouchPhase::Cancelled };
 
-                (window_id, phase)
+                (window_id, phase, time)
             },
             _ => return,
         };
 
-        state
-            .events_sink
-            .push_window_event(WindowEvent::HoldGesture { device_id: None, phase }, window_id);
+        let timestamp = Some(event_timestamp(time));
+        state.events_sink.push_window_event(
+            WindowEvent::HoldGesture { device_id: None, phase, timestamp },
+            window_id,
+        );
     }
 }
 
@@ -126,8 +129,8 @@ impl Dispatch<ZwpPointerGesturePinchV1, PointerGestureData, WinitState> for Poin
         _qhandle: &QueueHandle<WinitState>,
     ) {
         let mut pointer_gesture_data = data.inner.lock().unwrap();
-        let (window_id, phase, pan_delta, pinch_delta, rotation_delta) = match event {
-            PinchEvent::Begin { surface, fingers, .. } => {
+        let (window_id, phase, pan_delta, pinch_delta, rotation_delta, time) = match event {
+            PinchEvent::Begin { time, surface, fingers, .. } => {
                 // We only support two fingers for now.
                 if fingers != 2 {
                     return;
@@ -146,9 +149,9 @@ impl Dispatch<ZwpPointerGesturePinchV1, PointerGestureData, WinitState> for Poin
                 pointer_gesture_data.window_id = Some(window_id);
                 pointer_gesture_data.previous_pinch = 1.;
 
-                (window_id, TouchPhase::Started, PhysicalPosition::new(0., 0.), 0., 0.)
+                (window_id, TouchPhase::Started, PhysicalPosition::new(0., 0.), 0., 0., time)
             },
-            PinchEvent::Update { dx, dy, scale: pinch, rotation, .. } => {
+            PinchEvent::Update { time, dx, dy, scale: pinch, rotation, .. } => {
                 let window_id = match pointer_gesture_data.window_id {
                     Some(window_id) => window_id,
                     _ => return,
@@ -167,9 +170,9 @@ impl Dispatch<ZwpPointerGesturePinchV1, PointerGestureData, WinitState> for Poin
 
                 // Wayland provides rotation in degrees cw, opposite of winit's degrees ccw.
                 let rotation_delta = -rotation as f32;
-                (window_id, TouchPhase::Moved, pan_delta, pinch_delta, rotation_delta)
+                (window_id, TouchPhase::Moved, pan_delta, pinch_delta, rotation_delta, time)
             },
-            PinchEvent::End { cancelled, .. } => {
+            PinchEvent::End { time, cancelled, .. } => {
                 let window_id = match pointer_gesture_data.window_id {
                     Some(window_id) => window_id,
                     _ => return,
@@ -179,23 +182,29 @@ impl Dispatch<ZwpPointerGesturePinchV1, PointerGestureData, WinitState> for Poin
                 *pointer_gesture_data = Default::default();
 
                 let phase = if cancelled == 0 { TouchPhase::Ended } else { TouchPhase::Cancelled };
-                (window_id, phase, PhysicalPosition::new(0., 0.), 0., 0.)
+                (window_id, phase, PhysicalPosition::new(0., 0.), 0., 0., time)
             },
             _ => unreachable!("Unknown event {event:?}"),
         };
 
         // The chance of only one of these events being necessary is extremely small,
         // so it is easier to just send all three
+        let timestamp = Some(event_timestamp(time));
         state.events_sink.push_window_event(
-            WindowEvent::PanGesture { device_id: None, delta: pan_delta, phase },
+            WindowEvent::PanGesture { device_id: None, delta: pan_delta, phase, timestamp },
             window_id,
         );
         state.events_sink.push_window_event(
-            WindowEvent::PinchGesture { device_id: None, delta: pinch_delta, phase },
+            WindowEvent::PinchGesture { device_id: None, delta: pinch_delta, phase, timestamp },
             window_id,
         );
         state.events_sink.push_window_event(
-            WindowEvent::RotationGesture { device_id: None, delta: rotation_delta, phase },
+            WindowEvent::RotationGesture {
+                device_id: None,
+                delta: rotation_delta,
+                phase,
+                timestamp,
+            },
             window_id,
         );
     }
@@ -1,43 +1,280 @@
-use std::ops::Deref;
+use std::mem;
+use std::ops::Range;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU32;
 
 use dpi::{LogicalPosition, LogicalSize};
 use sctk::globals::GlobalData;
 use sctk::reexports::client::globals::{BindError, GlobalList};
+use sctk::reexports::client::protocol::wl_seat::WlSeat;
 use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::reexports::client::{Connection, Dispatch, Proxy, QueueHandle, delegate_dispatch};
+use sctk::reexports::protocols::wp::text_input::zv1::client::zwp_text_input_manager_v1::ZwpTextInputManagerV1;
+use sctk::reexports::protocols::wp::text_input::zv1::client::zwp_text_input_v1::ZwpTextInputV1;
 use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_manager_v3::ZwpTextInputManagerV3;
 use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::{
     ContentHint, ContentPurpose, Event as TextInputEvent, ZwpTextInputV3,
 };
 use tracing::warn;
-use winit_core::event::{Ime, WindowEvent};
+use wayland_protocols_plasma::text_input::v2::client::zwp_text_input_manager_v2::ZwpTextInputManagerV2;
+use wayland_protocols_plasma::text_input::v2::client::zwp_text_input_v2::ZwpTextInputV2;
+use winit_core::event::{Ime, PreeditStyle, WindowEvent};
 use winit_core::window::{
     ImeCapabilities, ImeHint, ImePurpose, ImeRequestData, ImeSurroundingText,
 };
 
+use self::v1::ZwpTextInputV1Ext;
+use self::v2::ZwpTextInputV2Ext;
 use crate::state::WinitState;
 
+mod v1;
+mod v2;
+
 #[derive(Debug)]
 pub struct TextInputState {
-    text_input_manager: ZwpTextInputManagerV3,
+    text_input_manager: TextInputManager,
 }
 
 impl TextInputState {
+    /// Bind the newest text input protocol advertised by the compositor.
     pub fn new(
         globals: &GlobalList,
         queue_handle: &QueueHandle<WinitState>,
     ) -> Result<Self, BindError> {
-        let text_input_manager = globals.bind(queue_handle, 1..=1, GlobalData)?;
+        let text_input_manager = globals
+            .bind(queue_handle, 1..=1, GlobalData)
+            .map(TextInputManager::V3)
+            .or_else(|_| globals.bind(queue_handle, 1..=1, GlobalData).map(TextInputManager::V2))
+            .or_else(|_| globals.bind(queue_handle, 1..=1, GlobalData).map(TextInputManager::V1))?;
         Ok(Self { text_input_manager })
     }
+
+    /// Create the text input for the seat.
+    pub fn get_text_input(
+        &self,
+        seat: &WlSeat,
+        queue_handle: &QueueHandle<WinitState>,
+    ) -> TextInput {
+        let data = TextInputData::default();
+        match &self.text_input_manager {
+            TextInputManager::V3(manager) => {
+                TextInput::V3(manager.get_text_input(seat, queue_handle, data))
+            },
+            TextInputManager::V2(manager) => {
+                TextInput::V2(manager.get_text_input(seat, queue_handle, data))
+            },
+            TextInputManager::V1(manager) => TextInput::V1 {
+                text_input: manager.create_text_input(queue_handle, data),
+                seat: seat.clone(),
+            },
+        }
+    }
+}
+
+/// The manager of the text input protocol in use.
+#[derive(Debug)]
+enum TextInputManager {
+    V3(ZwpTextInputManagerV3),
+    V2(ZwpTextInputManagerV2),
+    V1(ZwpTextInputManagerV1),
+}
+
+/// The text input of a seat.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextInput {
+    V3(ZwpTextInputV3),
+    V2(ZwpTextInputV2),
+    /// The text-input-v1 isn't created for a seat, but activated on it.
+    V1 {
+        text_input: ZwpTextInputV1,
+        seat: WlSeat,
+    },
+}
+
+impl TextInput {
+    /// Applies the entire state atomically to the input method on the `surface`. It will skip the
+    /// "enable" request if `send_enable` is `false`.
+    pub fn set_state(&self, surface: &WlSurface, state: Option<&ClientState>, send_enable: bool) {
+        match self {
+            Self::V3(text_input) => text_input.set_state(state, send_enable),
+            Self::V2(text_input) => text_input.set_state(surface, state, send_enable),
+            Self::V1 { text_input, seat } => {
+                text_input.set_state(seat, surface, state, send_enable)
+            },
+        }
+    }
+
+    /// Whether the text input focus follows the keyboard focus, instead of being announced by
+    /// the compositor.
+    pub fn follows_keyboard_focus(&self) -> bool {
+        matches!(self, Self::V1 { .. })
+    }
+
+    pub fn destroy(&self) {
+        match self {
+            Self::V3(text_input) => text_input.destroy(),
+            Self::V2(text_input) => text_input.destroy(),
+            // The text-input-v1 has no destructor.
+            Self::V1 { text_input, seat } => text_input.deactivate(seat),
+        }
+    }
+
+    fn data(&self) -> &TextInputData {
+        match self {
+            Self::V3(text_input) => text_input.data(),
+            Self::V2(text_input) => text_input.data(),
+            Self::V1 { text_input, .. } => text_input.data(),
+        }
+        .expect("text input without data")
+    }
 }
 
-impl Deref for TextInputState {
-    type Target = ZwpTextInputManagerV3;
+/// The keyboard of the seat entered the `surface`.
+pub fn keyboard_entered(state: &mut WinitState, text_input: &TextInput, surface: WlSurface) {
+    let mut text_input_data = text_input.data().inner.lock().unwrap();
+    enter(state, text_input, &mut text_input_data, surface);
+}
+
+/// The keyboard of the seat left the `surface`.
+pub fn keyboard_left(state: &mut WinitState, text_input: &TextInput, surface: WlSurface) {
+    let mut text_input_data = text_input.data().inner.lock().unwrap();
+    leave(state, text_input, &mut text_input_data, surface);
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.text_input_manager
+/// The text input focus entered the `surface`.
+fn enter(
+    state: &mut WinitState,
+    text_input: &TextInput,
+    text_input_data: &mut TextInputDataInner,
+    surface: WlSurface,
+) {
+    let window_id = crate::make_wid(&surface);
+    text_input_data.surface = Some(surface.clone());
+
+    let mut window = match state.windows.get_mut().get(&window_id) {
+        Some(window) => window.lock().unwrap(),
+        None => return,
+    };
+
+    if let Some(text_input_state) = window.text_input_state() {
+        text_input.set_state(&surface, Some(text_input_state), true);
+        // The input method doesn't have to reply anything, so a synthetic event
+        // carrying an empty state notifies the application about its presence.
+        state.events_sink.push_window_event(WindowEvent::Ime(Ime::Enabled), window_id);
     }
+
+    window.text_input_entered(text_input);
+}
+
+/// The text input focus left the `surface`.
+fn leave(
+    state: &mut WinitState,
+    text_input: &TextInput,
+    text_input_data: &mut TextInputDataInner,
+    surface: WlSurface,
+) {
+    text_input_data.surface = None;
+    text_input_data.last_preedit_empty = true;
+
+    // Always issue a disable.
+    text_input.set_state(&surface, None, false);
+
+    let window_id = crate::make_wid(&surface);
+
+    // XXX this check is essential, because `leave` could have a
+    // reference to nil surface...
+    let mut window = match state.windows.get_mut().get(&window_id) {
+        Some(window) => window.lock().unwrap(),
+        None => return,
+    };
+
+    window.text_input_left(text_input);
+
+    state.events_sink.push_window_event(WindowEvent::Ime(Ime::Disabled), window_id);
+}
+
+/// Send the pending input method changes to the focused window.
+fn flush(state: &mut WinitState, text_input_data: &mut TextInputDataInner) {
+    let window_id = match text_input_data.surface.as_ref() {
+        Some(surface) => crate::make_wid(surface),
+        None => return,
+    };
+
+    // Just in case some IME sends an event for the disabled window.
+    if let Some(window) = state.windows.get_mut().get(&window_id) {
+        if window.lock().unwrap().text_input_state().is_none() {
+            return;
+        }
+    };
+
+    // The events are sent to the user separately, so
+    // CAUTION: events must always arrive in the order compatible with the application
+    // order specified by the text-input-v3 protocol:
+    //
+    // As of version 1:
+    // 1. Replace existing preedit string with the cursor.
+    // 2. Delete requested surrounding text.
+    // 3. Insert commit string with the cursor at its end.
+    // 4. Calculate surrounding text to send.
+    // 5. Insert new preedit text in cursor position.
+    // 6. Place cursor inside preedit text.
+
+    if let Some(DeleteSurroundingText { before, after }) = text_input_data.pending_delete.take() {
+        state.events_sink.push_window_event(
+            WindowEvent::Ime(Ime::DeleteSurrounding { before_bytes: before, after_bytes: after }),
+            window_id,
+        );
+    }
+
+    // Clear preedit, unless all we'll be doing next is sending a new preedit and
+    // the last preedit wasn't empty.
+    if text_input_data.pending_commit.is_some()
+        || (text_input_data.pending_preedit.is_none() && !text_input_data.last_preedit_empty)
+    {
+        state.events_sink.push_window_event(
+            WindowEvent::Ime(Ime::Preedit(String::new(), None, Vec::new())),
+            window_id,
+        );
+        text_input_data.last_preedit_empty = true;
+    }
+
+    // Send `Commit`.
+    if let Some(text) = text_input_data.pending_commit.take() {
+        state.events_sink.push_window_event(WindowEvent::Ime(Ime::Commit(text)), window_id);
+    }
+
+    // Send preedit.
+    if let Some(preedit) = text_input_data.pending_preedit.take() {
+        let cursor_range = preedit.cursor_begin.map(|b| (b, preedit.cursor_end.unwrap_or(b)));
+
+        text_input_data.last_preedit_empty = false;
+        state.events_sink.push_window_event(
+            WindowEvent::Ime(Ime::Preedit(preedit.text, cursor_range, preedit.styles)),
+            window_id,
+        );
+    }
+}
+
+/// Apply a preedit string of text-input-v1 or v2 with the cursor and the styles received ahead of
+/// it. Unlike text-input-v3, there's no `done`, so each string is applied right away.
+fn legacy_preedit_string(
+    state: &mut WinitState,
+    text_input_data: &mut TextInputDataInner,
+    text: String,
+) {
+    text_input_data.stage_preedit(text);
+    flush(state, text_input_data);
+}
+
+/// Apply a commit string of text-input-v1 or v2 right away.
+fn legacy_commit_string(
+    state: &mut WinitState,
+    text_input_data: &mut TextInputDataInner,
+    text: String,
+) {
+    text_input_data.pending_preedit = None;
+    text_input_data.pending_commit = Some(text);
+    flush(state, text_input_data);
 }
 
 impl Dispatch<ZwpTextInputManagerV3, GlobalData, WinitState> for TextInputState {
@@ -61,47 +298,15 @@ impl Dispatch<ZwpTextInputV3, TextInputData, WinitState> for TextInputState {
         _conn: &Connection,
         _qhandle: &QueueHandle<WinitState>,
     ) {
-        let windows = state.windows.get_mut();
         let mut text_input_data = data.inner.lock().unwrap();
         match event {
             TextInputEvent::Enter { surface } => {
-                let window_id = crate::make_wid(&surface);
-                text_input_data.surface = Some(surface);
-
-                let mut window = match windows.get(&window_id) {
-                    Some(window) => window.lock().unwrap(),
-                    None => return,
-                };
-
-                if let Some(text_input_state) = window.text_input_state() {
-                    text_input.set_state(Some(text_input_state), true);
-                    // The input method doesn't have to reply anything, so a synthetic event
-                    // carrying an empty state notifies the application about its presence.
-                    state.events_sink.push_window_event(WindowEvent::Ime(Ime::Enabled), window_id);
-                }
-
-                window.text_input_entered(text_input);
+                let text_input = TextInput::V3(text_input.clone());
+                enter(state, &text_input, &mut text_input_data, surface);
             },
             TextInputEvent::Leave { surface } => {
-                text_input_data.surface = None;
-                text_input_data.last_preedit_empty = true;
-
-                // Always issue a disable.
-                text_input.disable();
-                text_input.commit();
-
-                let window_id = crate::make_wid(&surface);
-
-                // XXX this check is essential, because `leave` could have a
-                // reference to nil surface...
-                let mut window = match windows.get(&window_id) {
-                    Some(window) => window.lock().unwrap(),
-                    None => return,
-                };
-
-                window.text_input_left(text_input);
-
-                state.events_sink.push_window_event(WindowEvent::Ime(Ime::Disabled), window_id);
+                let text_input = TextInput::V3(text_input.clone());
+                leave(state, &text_input, &mut text_input_data, surface);
             },
             TextInputEvent::PreeditString { text, cursor_begin, cursor_end } => {
                 let text = text.unwrap_or_default();
@@ -112,7 +317,8 @@ impl Dispatch<ZwpTextInputV3, TextInputData, WinitState> for TextInputState {
                     .ok()
                     .and_then(|idx| text.is_char_boundary(idx).then_some(idx));
 
-                text_input_data.pending_preedit = Some(Preedit { text, cursor_begin, cursor_end })
+                text_input_data.pending_preedit =
+                    Some(Preedit { text, cursor_begin, cursor_end, styles: Vec::new() })
             },
             TextInputEvent::CommitString { text } => {
                 text_input_data.pending_preedit = None;
@@ -124,81 +330,13 @@ impl Dispatch<ZwpTextInputV3, TextInputData, WinitState> for TextInputState {
                     after: after_length as usize,
                 });
             },
-            TextInputEvent::Done { .. } => {
-                let window_id = match text_input_data.surface.as_ref() {
-                    Some(surface) => crate::make_wid(surface),
-                    None => return,
-                };
-
-                // Just in case some IME sends an event for the disabled window.
-                if let Some(window) = windows.get(&window_id) {
-                    if window.lock().unwrap().text_input_state().is_none() {
-                        return;
-                    }
-                };
-
-                // The events are sent to the user separately, so
-                // CAUTION: events must always arrive in the order compatible with the application
-                // order specified by the text-input-v3 protocol:
-                //
-                // As of version 1:
-                // 1. Replace existing preedit string with the cursor.
-                // 2. Delete requested surrounding text.
-                // 3. Insert commit string with the cursor at its end.
-                // 4. Calculate surrounding text to send.
-                // 5. Insert new preedit text in cursor position.
-                // 6. Place cursor inside preedit text.
-
-                if let Some(DeleteSurroundingText { before, after }) =
-                    text_input_data.pending_delete.take()
-                {
-                    state.events_sink.push_window_event(
-                        WindowEvent::Ime(Ime::DeleteSurrounding {
-                            before_bytes: before,
-                            after_bytes: after,
-                        }),
-                        window_id,
-                    );
-                }
-
-                // Clear preedit, unless all we'll be doing next is sending a new preedit and
-                // the last preedit wasn't empty.
-                if text_input_data.pending_commit.is_some()
-                    || (text_input_data.pending_preedit.is_none()
-                        && !text_input_data.last_preedit_empty)
-                {
-                    state.events_sink.push_window_event(
-                        WindowEvent::Ime(Ime::Preedit(String::new(), None)),
-                        window_id,
-                    );
-                    text_input_data.last_preedit_empty = true;
-                }
-
-                // Send `Commit`.
-                if let Some(text) = text_input_data.pending_commit.take() {
-                    state
-                        .events_sink
-                        .push_window_event(WindowEvent::Ime(Ime::Commit(text)), window_id);
-                }
-
-                // Send preedit.
-                if let Some(preedit) = text_input_data.pending_preedit.take() {
-                    let cursor_range =
-                        preedit.cursor_begin.map(|b| (b, preedit.cursor_end.unwrap_or(b)));
-
-                    text_input_data.last_preedit_empty = false;
-                    state.events_sink.push_window_event(
-                        WindowEvent::Ime(Ime::Preedit(preedit.text, cursor_range)),
-                        window_id,
-                    );
-                }
-            },
+            TextInputEvent::Done { .. } => flush(state, &mut text_input_data),
             _ => {},
         }
     }
 }
 
-pub trait ZwpTextInputV3Ext {
+trait ZwpTextInputV3Ext {
     /// Applies the entire state atomically to the input method. It will skip the "enable" request
     /// if `already_enabled` is `true`.
     fn set_state(&self, state: Option<&ClientState>, send_enable: bool);
@@ -247,7 +385,10 @@ impl ZwpTextInputV3Ext for ZwpTextInputV3 {
 /// The Data associated with the text input.
 #[derive(Default)]
 pub struct TextInputData {
-    inner: std::sync::Mutex<TextInputDataInner>,
+    inner: Mutex<TextInputDataInner>,
+
+    /// The serial of the state committed to the input method.
+    serial: AtomicU32,
 }
 
 pub struct TextInputDataInner {
@@ -263,6 +404,12 @@ pub struct TextInputDataInner {
     /// The text around the cursor to delete on `done`
     pending_delete: Option<DeleteSurroundingText>,
 
+    /// The preedit cursor received ahead of the preedit string.
+    pending_preedit_cursor: Option<i32>,
+
+    /// The preedit styles received ahead of the preedit string.
+    pending_preedit_styles: Vec<(Range<usize>, PreeditStyle)>,
+
     /// Last preedit empty.
     last_preedit_empty: bool,
 }
@@ -274,17 +421,55 @@ impl Default for TextInputDataInner {
             pending_commit: None,
             pending_preedit: None,
             pending_delete: None,
+            pending_preedit_cursor: None,
+            pending_preedit_styles: Vec::new(),
             last_preedit_empty: true,
         }
     }
 }
 
+impl TextInputDataInner {
+    /// Stage a `preedit_styling` of text-input-v1 or v2.
+    fn push_legacy_preedit_style(&mut self, index: u32, length: u32, style: PreeditStyle) {
+        let range = index as usize..index as usize + length as usize;
+        self.pending_preedit_styles.push((range, style));
+    }
+
+    /// Stage the preedit with the cursor and the styles received ahead of it.
+    ///
+    /// The cursor defaults to the end of the preedit when none was received.
+    fn stage_preedit(&mut self, text: String) {
+        let cursor = match self.pending_preedit_cursor.take() {
+            Some(index) => usize::try_from(index).ok(),
+            None => Some(text.len()),
+        }
+        .filter(|&index| text.is_char_boundary(index));
+
+        let styles = mem::take(&mut self.pending_preedit_styles)
+            .into_iter()
+            .filter(|(range, _)| {
+                !range.is_empty()
+                    && text.is_char_boundary(range.start)
+                    && text.is_char_boundary(range.end)
+            })
+            .collect();
+
+        self.pending_preedit = (!text.is_empty()).then_some(Preedit {
+            text,
+            cursor_begin: cursor,
+            cursor_end: cursor,
+            styles,
+        });
+    }
+}
+
 /// The state of the preedit.
 #[derive(Clone)]
 struct Preedit {
     text: String,
     cursor_begin: Option<usize>,
     cursor_end: Option<usize>,
+    styles: Vec<(Range<usize>, PreeditStyle)>,
 }
 
 /// The delete request
@@ -306,7 +491,7 @@ struct DeleteSurroundingText {
 #[derive(Debug, PartialEq, Clone)]
 pub struct ClientState {
     capabilities: ImeCapabilities,
-    content_type: ContentType,
+    hint_and_purpose: (ImeHint, ImePurpose),
     /// The IME cursor area which should not be covered by the input method popup.
     cursor_area: (LogicalPosition<u32>, LogicalSize<u32>),
 
@@ -323,7 +508,7 @@ impl ClientState {
     ) -> Self {
         let mut this = Self {
             capabilities,
-            content_type: Default::default(),
+            hint_and_purpose: Default::default(),
             cursor_area: Default::default(),
             surrounding_text: ImeSurroundingText::new(String::new(), 0, 0).unwrap(),
         };
@@ -353,7 +538,7 @@ impl ClientState {
         if let Some((hint, purpose)) =
             request_data.hint_and_purpose.filter(|_| self.capabilities.hint_and_purpose())
         {
-            self.content_type = (hint, purpose).into();
+            self.hint_and_purpose = (hint, purpose);
         }
 
         if let Some((position, size)) = request_data.cursor_area {
@@ -375,8 +560,12 @@ impl ClientState {
         }
     }
 
+    pub fn hint_and_purpose(&self) -> Option<(ImeHint, ImePurpose)> {
+        self.capabilities.hint_and_purpose().then_some(self.hint_and_purpose)
+    }
+
     pub fn content_type(&self) -> Option<ContentType> {
-        self.capabilities.hint_and_purpose().then_some(self.content_type)
+        self.hint_and_purpose().map(ContentType::from)
     }
 
     pub fn cursor_area(&self) -> Option<(LogicalPosition<u32>, LogicalSize<u32>)> {
@@ -457,11 +646,5 @@ impl From<(ImeHint, ImePurpose)> for ContentType {
     }
 }
 
-impl Default for ContentType {
-    fn default() -> Self {
-        ContentType { purpose: ContentPurpose::Normal, hint: ContentHint::None }
-    }
-}
-
 delegate_dispatch!(WinitState: [ZwpTextInputManagerV3: GlobalData] => TextInputState);
 delegate_dispatch!(WinitState: [ZwpTextInputV3: TextInputData] => TextInputState);
@@ -0,0 +1,193 @@
+//! The text-input-unstable-v1 fallback, advertised by Weston and older compositors.
+
+use std::sync::atomic::Ordering;
+
+use sctk::globals::GlobalData;
+use sctk::reexports::client::protocol::wl_seat::WlSeat;
+use sctk::reexports::client::protocol::wl_surface::WlSurface;
+use sctk::reexports::client::{Connection, Dispatch, Proxy, QueueHandle, WEnum, delegate_dispatch};
+use sctk::reexports::protocols::wp::text_input::zv1::client::zwp_text_input_manager_v1::ZwpTextInputManagerV1;
+use sctk::reexports::protocols::wp::text_input::zv1::client::zwp_text_input_v1::{
+    ContentHint, ContentPurpose, Event as TextInputEvent, PreeditStyle as TextInputPreeditStyle,
+    ZwpTextInputV1,
+};
+use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::{
+    ContentHint as V3ContentHint, ContentPurpose as V3ContentPurpose,
+};
+use tracing::warn;
+use winit_core::event::PreeditStyle;
+
+use super::{ClientState, ContentType, DeleteSurroundingText, TextInputData, TextInputState};
+use crate::state::WinitState;
+
+impl Dispatch<ZwpTextInputManagerV1, GlobalData, WinitState> for TextInputState {
+    fn event(
+        _state: &mut WinitState,
+        _proxy: &ZwpTextInputManagerV1,
+        _event: <ZwpTextInputManagerV1 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTextInputV1, TextInputData, WinitState> for TextInputState {
+    fn event(
+        state: &mut WinitState,
+        _text_input: &ZwpTextInputV1,
+        event: <ZwpTextInputV1 as Proxy>::Event,
+        data: &TextInputData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+        let mut text_input_data = data.inner.lock().unwrap();
+        match event {
+            // The focus follows the keyboard, since the text input is activated on the keyboard
+            // focus.
+            TextInputEvent::Enter { .. } | TextInputEvent::Leave => {},
+            TextInputEvent::PreeditStyling { index, length, style: WEnum::Value(style) } => {
+                if let Some(style) = preedit_style(style) {
+                    text_input_data.push_legacy_preedit_style(index, length, style);
+                }
+            },
+            TextInputEvent::PreeditCursor { index } => {
+                text_input_data.pending_preedit_cursor = Some(index);
+            },
+            TextInputEvent::PreeditString { text, .. } => {
+                super::legacy_preedit_string(state, &mut text_input_data, text);
+            },
+            TextInputEvent::CommitString { text, .. } => {
+                super::legacy_commit_string(state, &mut text_input_data, text);
+            },
+            TextInputEvent::DeleteSurroundingText { index, length } => {
+                // The deleted text starts `index` bytes from the cursor, only the text around the
+                // cursor can be deleted.
+                match index.checked_neg().and_then(|before| usize::try_from(before).ok()) {
+                    Some(before) => {
+                        let after = (length as usize).saturating_sub(before);
+                        text_input_data.pending_delete =
+                            Some(DeleteSurroundingText { before, after });
+                    },
+                    None => warn!("ignoring the deletion of text after the cursor at {index}"),
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+pub trait ZwpTextInputV1Ext {
+    /// Applies the entire state to the input method for the `seat` on the `surface`. It will skip
+    /// the "activate" request if `send_enable` is `false`.
+    fn set_state(
+        &self,
+        seat: &WlSeat,
+        surface: &WlSurface,
+        state: Option<&ClientState>,
+        send_enable: bool,
+    );
+}
+
+impl ZwpTextInputV1Ext for ZwpTextInputV1 {
+    fn set_state(
+        &self,
+        seat: &WlSeat,
+        surface: &WlSurface,
+        state: Option<&ClientState>,
+        send_enable: bool,
+    ) {
+        let state = match state {
+            Some(state) => state,
+            None => {
+                self.hide_input_panel();
+                self.deactivate(seat);
+                return;
+            },
+        };
+
+        if send_enable {
+            self.activate(seat, surface);
+            self.show_input_panel();
+        }
+
+        if let Some(content_type) = state.content_type() {
+            let (hint, purpose) = legacy_content_type(content_type);
+            self.set_content_type(hint, purpose);
+        }
+
+        if let Some((position, size)) = state.cursor_area() {
+            let (x, y) = (position.x as i32, position.y as i32);
+            let (width, height) = (size.width as i32, size.height as i32);
+            self.set_cursor_rectangle(x, y, width, height);
+        }
+
+        if let Some(surrounding) = state.surrounding_text() {
+            self.set_surrounding_text(
+                surrounding.text().into(),
+                surrounding.cursor() as u32,
+                surrounding.anchor() as u32,
+            );
+        }
+
+        // Each committed state gets a new serial.
+        let serial = self
+            .data::<TextInputData>()
+            .map_or(0, |data| data.serial.fetch_add(1, Ordering::Relaxed).wrapping_add(1));
+        self.commit_state(serial);
+    }
+}
+
+fn preedit_style(style: TextInputPreeditStyle) -> Option<PreeditStyle> {
+    match style {
+        TextInputPreeditStyle::Underline
+        | TextInputPreeditStyle::Inactive
+        | TextInputPreeditStyle::Incorrect => Some(PreeditStyle::UNDERLINE),
+        TextInputPreeditStyle::Active | TextInputPreeditStyle::Highlight => {
+            Some(PreeditStyle::HIGHLIGHT)
+        },
+        TextInputPreeditStyle::Selection => Some(PreeditStyle::REVERSE),
+        _ => None,
+    }
+}
+
+/// Map the text-input-v3 content type, the `pin` purpose missing here becomes `digits` with the
+/// sensitive hint it already carries.
+fn legacy_content_type(content_type: ContentType) -> (ContentHint, ContentPurpose) {
+    let purpose = match content_type.purpose {
+        V3ContentPurpose::Alpha => ContentPurpose::Alpha,
+        V3ContentPurpose::Digits | V3ContentPurpose::Pin => ContentPurpose::Digits,
+        V3ContentPurpose::Number => ContentPurpose::Number,
+        V3ContentPurpose::Phone => ContentPurpose::Phone,
+        V3ContentPurpose::Url => ContentPurpose::Url,
+        V3ContentPurpose::Email => ContentPurpose::Email,
+        V3ContentPurpose::Name => ContentPurpose::Name,
+        V3ContentPurpose::Password => ContentPurpose::Password,
+        V3ContentPurpose::Date => ContentPurpose::Date,
+        V3ContentPurpose::Time => ContentPurpose::Time,
+        V3ContentPurpose::Datetime => ContentPurpose::Datetime,
+        V3ContentPurpose::Terminal => ContentPurpose::Terminal,
+        _ => ContentPurpose::Normal,
+    };
+
+    let hint = [
+        (V3ContentHint::Completion, ContentHint::AutoCompletion),
+        (V3ContentHint::Spellcheck, ContentHint::AutoCorrection),
+        (V3ContentHint::AutoCapitalization, ContentHint::AutoCapitalization),
+        (V3ContentHint::Lowercase, ContentHint::Lowercase),
+        (V3ContentHint::Uppercase, ContentHint::Uppercase),
+        (V3ContentHint::Titlecase, ContentHint::Titlecase),
+        (V3ContentHint::HiddenText, ContentHint::HiddenText),
+        (V3ContentHint::SensitiveData, ContentHint::SensitiveData),
+        (V3ContentHint::Latin, ContentHint::Latin),
+        (V3ContentHint::Multiline, ContentHint::Multiline),
+    ]
+    .into_iter()
+    .filter(|&(v3_hint, _)| content_type.hint.contains(v3_hint))
+    .fold(ContentHint::None, |hint, (_, legacy_hint)| hint | legacy_hint);
+
+    (hint, purpose)
+}
+
+delegate_dispatch!(WinitState: [ZwpTextInputManagerV1: GlobalData] => TextInputState);
+delegate_dispatch!(WinitState: [ZwpTextInputV1: TextInputData] => TextInputState);
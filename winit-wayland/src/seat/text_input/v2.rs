@@ -0,0 +1,182 @@
+//! The text-input-unstable-v2 fallback, advertised by KWin.
+
+use std::sync::atomic::Ordering;
+
+use sctk::globals::GlobalData;
+use sctk::reexports::client::protocol::wl_surface::WlSurface;
+use sctk::reexports::client::{Connection, Dispatch, Proxy, QueueHandle, WEnum, delegate_dispatch};
+use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::{
+    ContentHint as V3ContentHint, ContentPurpose as V3ContentPurpose,
+};
+use wayland_protocols_plasma::text_input::v2::client::zwp_text_input_manager_v2::ZwpTextInputManagerV2;
+use wayland_protocols_plasma::text_input::v2::client::zwp_text_input_v2::{
+    ContentHint, ContentPurpose, Event as TextInputEvent, PreeditStyle as TextInputPreeditStyle,
+    UpdateReason, ZwpTextInputV2,
+};
+use winit_core::event::PreeditStyle;
+
+use super::{
+    ClientState, ContentType, DeleteSurroundingText, TextInput, TextInputData, TextInputState,
+};
+use crate::state::WinitState;
+
+impl Dispatch<ZwpTextInputManagerV2, GlobalData, WinitState> for TextInputState {
+    fn event(
+        _state: &mut WinitState,
+        _proxy: &ZwpTextInputManagerV2,
+        _event: <ZwpTextInputManagerV2 as Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTextInputV2, TextInputData, WinitState> for TextInputState {
+    fn event(
+        state: &mut WinitState,
+        text_input: &ZwpTextInputV2,
+        event: <ZwpTextInputV2 as Proxy>::Event,
+        data: &TextInputData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<WinitState>,
+    ) {
+        let mut text_input_data = data.inner.lock().unwrap();
+        match event {
+            TextInputEvent::Enter { serial, surface } => {
+                data.serial.store(serial, Ordering::Relaxed);
+                let text_input = TextInput::V2(text_input.clone());
+                super::enter(state, &text_input, &mut text_input_data, surface);
+            },
+            TextInputEvent::Leave { serial, surface } => {
+                data.serial.store(serial, Ordering::Relaxed);
+                let text_input = TextInput::V2(text_input.clone());
+                super::leave(state, &text_input, &mut text_input_data, surface);
+            },
+            TextInputEvent::PreeditStyling { index, length, style: WEnum::Value(style) } => {
+                if let Some(style) = preedit_style(style) {
+                    text_input_data.push_legacy_preedit_style(index, length, style);
+                }
+            },
+            TextInputEvent::PreeditCursor { index } => {
+                text_input_data.pending_preedit_cursor = Some(index);
+            },
+            TextInputEvent::PreeditString { text, .. } => {
+                super::legacy_preedit_string(state, &mut text_input_data, text);
+            },
+            TextInputEvent::CommitString { text } => {
+                super::legacy_commit_string(state, &mut text_input_data, text);
+            },
+            TextInputEvent::DeleteSurroundingText { before_length, after_length } => {
+                text_input_data.pending_delete = Some(DeleteSurroundingText {
+                    before: before_length as usize,
+                    after: after_length as usize,
+                });
+            },
+            _ => {},
+        }
+    }
+}
+
+pub trait ZwpTextInputV2Ext {
+    /// Applies the entire state to the input method on the `surface`. It will skip the "enable"
+    /// request if `send_enable` is `false`.
+    fn set_state(&self, surface: &WlSurface, state: Option<&ClientState>, send_enable: bool);
+}
+
+impl ZwpTextInputV2Ext for ZwpTextInputV2 {
+    fn set_state(&self, surface: &WlSurface, state: Option<&ClientState>, send_enable: bool) {
+        let state = match state {
+            Some(state) => state,
+            None => {
+                self.hide_input_panel();
+                self.disable(surface);
+                return;
+            },
+        };
+
+        if send_enable {
+            self.enable(surface);
+            self.show_input_panel();
+        }
+
+        if let Some(content_type) = state.content_type() {
+            let (hint, purpose) = legacy_content_type(content_type);
+            self.set_content_type(hint, purpose);
+        }
+
+        if let Some((position, size)) = state.cursor_area() {
+            let (x, y) = (position.x as i32, position.y as i32);
+            let (width, height) = (size.width as i32, size.height as i32);
+            self.set_cursor_rectangle(x, y, width, height);
+        }
+
+        if let Some(surrounding) = state.surrounding_text() {
+            self.set_surrounding_text(
+                surrounding.text().into(),
+                surrounding.cursor() as i32,
+                surrounding.anchor() as i32,
+            );
+        }
+
+        // The serial must be the one of the latest `enter`.
+        let serial =
+            self.data::<TextInputData>().map_or(0, |data| data.serial.load(Ordering::Relaxed));
+        let reason = if send_enable { UpdateReason::Full } else { UpdateReason::Change };
+        self.update_state(serial, reason);
+    }
+}
+
+fn preedit_style(style: TextInputPreeditStyle) -> Option<PreeditStyle> {
+    match style {
+        TextInputPreeditStyle::Underline
+        | TextInputPreeditStyle::Inactive
+        | TextInputPreeditStyle::Incorrect => Some(PreeditStyle::UNDERLINE),
+        TextInputPreeditStyle::Active | TextInputPreeditStyle::Highlight => {
+            Some(PreeditStyle::HIGHLIGHT)
+        },
+        TextInputPreeditStyle::Selection => Some(PreeditStyle::REVERSE),
+        _ => None,
+    }
+}
+
+/// Map the text-input-v3 content type, the `pin` purpose missing here becomes `digits` with the
+/// sensitive hint it already carries.
+fn legacy_content_type(content_type: ContentType) -> (ContentHint, ContentPurpose) {
+    let purpose = match content_type.purpose {
+        V3ContentPurpose::Alpha => ContentPurpose::Alpha,
+        V3ContentPurpose::Digits | V3ContentPurpose::Pin => ContentPurpose::Digits,
+        V3ContentPurpose::Number => ContentPurpose::Number,
+        V3ContentPurpose::Phone => ContentPurpose::Phone,
+        V3ContentPurpose::Url => ContentPurpose::Url,
+        V3ContentPurpose::Email => ContentPurpose::Email,
+        V3ContentPurpose::Name => ContentPurpose::Name,
+        V3ContentPurpose::Password => ContentPurpose::Password,
+        V3ContentPurpose::Date => ContentPurpose::Date,
+        V3ContentPurpose::Time => ContentPurpose::Time,
+        V3ContentPurpose::Datetime => ContentPurpose::Datetime,
+        V3ContentPurpose::Terminal => ContentPurpose::Terminal,
+        _ => ContentPurpose::Normal,
+    };
+
+    let hint = [
+        (V3ContentHint::Completion, ContentHint::AutoCompletion),
+        (V3ContentHint::Spellcheck, ContentHint::AutoCorrection),
+        (V3ContentHint::AutoCapitalization, ContentHint::AutoCapitalization),
+        (V3ContentHint::Lowercase, ContentHint::Lowercase),
+        (V3ContentHint::Uppercase, ContentHint::Uppercase),
+        (V3ContentHint::Titlecase, ContentHint::Titlecase),
+        (V3ContentHint::HiddenText, ContentHint::HiddenText),
+        (V3ContentHint::SensitiveData, ContentHint::SensitiveData),
+        (V3ContentHint::Latin, ContentHint::Latin),
+        (V3ContentHint::Multiline, ContentHint::Multiline),
+    ]
+    .into_iter()
+    .filter(|&(v3_hint, _)| content_type.hint.contains(v3_hint))
+    .fold(ContentHint::None, |hint, (_, legacy_hint)| hint | legacy_hint);
+
+    (hint, purpose)
+}
+
+delegate_dispatch!(WinitState: [ZwpTextInputManagerV2: GlobalData] => TextInputState);
+delegate_dispatch!(WinitState: [ZwpTextInputV2: TextInputData] => TextInputState);
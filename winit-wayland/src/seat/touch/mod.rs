@@ -7,6 +7,7 @@ use sctk::reexports::client::protocol::wl_touch::WlTouch;
 use sctk::reexports::client::{Connection, Proxy, QueueHandle};
 use sctk::seat::touch::{TouchData, TouchHandler};
 use tracing::warn;
+use winit_common::event_timestamp::event_timestamp;
 use winit_core::event::{
     ButtonSource, ElementState, FingerId, PointerKind, PointerSource, WindowEvent,
 };
@@ -20,7 +21,7 @@ impl TouchHandler for WinitState {
         _: &QueueHandle<Self>,
         touch: &WlTouch,
         _: u32,
-        _: u32,
+        time: u32,
         surface: WlSurface,
         id: i32,
         position: (f64, f64),
@@ -68,6 +69,8 @@ impl TouchHandler for WinitState {
                 position,
                 button: ButtonSource::Touch { finger_id, force: None },
                 is_macos_activation_click: false,
+                timestamp: Some(event_timestamp(time)),
+                click_count: 1,
             },
             window_id,
         );
@@ -79,7 +82,7 @@ impl TouchHandler for WinitState {
         _: &QueueHandle<Self>,
         touch: &WlTouch,
         _: u32,
-        _: u32,
+        time: u32,
         id: i32,
     ) {
         let seat_state = match self.seats.get_mut(&touch.seat().id()) {
@@ -122,6 +125,8 @@ impl TouchHandler for WinitState {
                 position,
                 button: ButtonSource::Touch { finger_id, force: None },
                 is_macos_activation_click: false,
+                timestamp: Some(event_timestamp(time)),
+                click_count: 1,
             },
             window_id,
         );
@@ -141,7 +146,7 @@ impl TouchHandler for WinitState {
         _: &Connection,
         _: &QueueHandle<Self>,
         touch: &WlTouch,
-        _: u32,
+        time: u32,
         id: i32,
         position: (f64, f64),
     ) {
@@ -178,6 +183,8 @@ impl TouchHandler for WinitState {
                     finger_id: FingerId::from_raw(id as usize),
                     force: None,
                 },
+                timestamp: Some(event_timestamp(time)),
+                history: Vec::new(),
             },
             window_id,
         );
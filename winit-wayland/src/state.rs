@@ -22,7 +22,11 @@ use sctk::shell::xdg::window::{Window, WindowConfigure, WindowHandler};
 use sctk::shm::slot::SlotPool;
 use sctk::shm::{Shm, ShmHandler};
 use sctk::subcompositor::SubcompositorState;
+use winit_common::app_sources::AppSources;
+use winit_common::dbus::SettingsPortal;
+use winit_common::xkb::KeymapSource;
 use winit_core::error::OsError;
+use winit_core::event_loop::{KeyRepeat, PointerCoalescing};
 
 use crate::WindowId;
 use crate::dnd::DndState;
@@ -138,6 +142,24 @@ pub struct WinitState {
 
     /// Whether the user initiated a wake up.
     pub proxy_wake_up: bool,
+
+    /// The connection to the settings portal, when available.
+    pub settings_portal: Option<SettingsPortal>,
+
+    /// Whether the system settings changed since the last loop iteration.
+    pub system_settings_changed: bool,
+
+    /// The keymap supplied by the application, used instead of the one of the compositor.
+    pub keymap: Option<KeymapSource>,
+
+    /// How the application wants held keys to repeat.
+    pub key_repeat: KeyRepeat,
+
+    /// How the application wants the pointer events to be merged.
+    pub pointer_coalescing: PointerCoalescing,
+
+    /// The timers and the file descriptors of the application.
+    pub sources: AppSources<WinitState>,
 }
 
 impl WinitState {
@@ -145,6 +167,7 @@ impl WinitState {
         globals: &GlobalList,
         queue_handle: &QueueHandle<Self>,
         loop_handle: LoopHandle<'static, WinitState>,
+        keymap: Option<KeymapSource>,
     ) -> Result<Self, OsError> {
         let registry_state = RegistryState::new(globals);
         let compositor_state =
@@ -192,6 +215,8 @@ impl WinitState {
         let shm = Shm::bind(globals, queue_handle).map_err(|err| os_error!(err))?;
         let image_pool = Arc::new(Mutex::new(SlotPool::new(2, &shm).unwrap()));
 
+        let sources = AppSources::new(loop_handle.clone(), |state| state.dispatched_events = true);
+
         Ok(Self {
             registry_state,
             compositor_state: Arc::new(compositor_state),
@@ -235,6 +260,12 @@ impl WinitState {
             // Make it true by default.
             dispatched_events: true,
             proxy_wake_up: false,
+            settings_portal: None,
+            system_settings_changed: false,
+            keymap,
+            key_repeat: KeyRepeat::System,
+            pointer_coalescing: PointerCoalescing::Disabled,
+            sources,
         })
     }
 
@@ -326,14 +357,21 @@ impl WindowHandler for WinitState {
         };
 
         // Populate the configure to the window.
-        self.window_compositor_updates[index].resized |= self
+        let mut window = self
             .windows
             .get_mut()
             .get_mut(&window_id)
             .expect("got configure for dead window.")
             .lock()
-            .unwrap()
-            .configure_window(configure, &self.shm, &self.subcompositor_state);
+            .unwrap();
+        let old_states = window.states();
+        let resized = window.configure_window(configure, &self.shm, &self.subcompositor_state);
+        let states_changed = window.states() != old_states;
+        drop(window);
+
+        let update = &mut self.window_compositor_updates[index];
+        update.resized |= resized;
+        update.states_changed |= states_changed;
 
         // NOTE: configure demands wl_surface::commit, however winit doesn't commit on behalf of the
         // users, since it can break a lot of things, thus it'll ask users to redraw instead.
@@ -510,13 +548,22 @@ pub struct WindowCompositorUpdate {
     /// New scale factor.
     pub scale_changed: bool,
 
+    /// New toplevel states.
+    pub states_changed: bool,
+
     /// Close the window.
     pub close_window: bool,
 }
 
 impl WindowCompositorUpdate {
     fn new(window_id: WindowId) -> Self {
-        Self { window_id, resized: false, scale_changed: false, close_window: false }
+        Self {
+            window_id,
+            resized: false,
+            scale_changed: false,
+            states_changed: false,
+            close_window: false,
+        }
     }
 }
 
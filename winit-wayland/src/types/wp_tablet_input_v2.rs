@@ -26,6 +26,7 @@ use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_group_v2::{
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2;
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2;
 use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_pad_v2;
+use winit_common::event_timestamp::event_timestamp;
 use winit_core::event::{
     ButtonSource, ElementState, Force, PointerKind, PointerSource, TabletToolButton,
     TabletToolData as CoreTabletToolData, TabletToolKind, TabletToolTilt, WindowEvent,
@@ -171,8 +172,9 @@ impl Dispatch2<ZwpTabletToolV2, WinitState> for TabletToolData {
                 let event = TabletEvent::Button { button, state, serial: Some(serial) };
                 data.pending.push(event);
             },
-            ToolEvent::Frame { .. } => {
+            ToolEvent::Frame { time } => {
                 let kind = data.ty;
+                let timestamp = Some(event_timestamp(time));
                 for event in std::mem::take(&mut data.pending) {
                     if let TabletEvent::Enter { surface, serial } = &event {
                         data.latest_enter_serial = Some(*serial);
@@ -218,6 +220,8 @@ impl Dispatch2<ZwpTabletToolV2, WinitState> for TabletToolData {
                                 kind,
                                 data: data.tool_state.clone(),
                             },
+                            timestamp,
+                            history: Vec::new(),
                         },
                         TabletEvent::Button { button, state, serial } => {
                             // Update serial if we have it.
@@ -236,6 +240,8 @@ impl Dispatch2<ZwpTabletToolV2, WinitState> for TabletToolData {
                                     data: data.tool_state.clone(),
                                 },
                                 is_macos_activation_click: false,
+                                timestamp,
+                                click_count: 1,
                             }
                         },
                         TabletEvent::Left => WindowEvent::PointerLeft {
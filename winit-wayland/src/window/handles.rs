@@ -1,12 +1,10 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
-use sctk::compositor::{CompositorState, Region};
 use sctk::reexports::client::QueueHandle;
 use sctk::reexports::client::protocol::wl_surface::WlSurface;
 use sctk::reexports::protocols::xdg::activation::v1::client::xdg_activation_v1::XdgActivationV1;
 use tracing::warn;
-use winit_core::error::RequestError;
 use winit_core::event::WindowEvent;
 use winit_core::monitor::MonitorHandle as CoreMonitorHandle;
 use winit_core::window::{UserAttentionType, WindowId};
@@ -38,9 +36,6 @@ pub struct Handles {
 
     /// The state of the requested attention from the `xdg_activation`.
     pub(crate) attention_requested: Arc<AtomicBool>,
-
-    /// Compositor to handle WlRegion stuff.
-    pub(crate) compositor: Arc<CompositorState>,
 }
 
 impl Handles {
@@ -59,6 +54,11 @@ impl Handles {
         }
     }
 
+    pub(crate) fn cancel_compose(&self) {
+        self.window_requests.cancel_compose.store(true, Ordering::Relaxed);
+        self.event_loop_awakener.ping();
+    }
+
     pub(crate) fn push_window_event(&self, event: WindowEvent, id: WindowId) {
         self.window_events_sink.lock().unwrap().push_window_event(event, id);
         self.event_loop_awakener.ping();
@@ -103,22 +103,6 @@ impl Handles {
         xdg_activation_token.set_surface(surface);
         xdg_activation_token.commit();
     }
-
-    pub(crate) fn set_cursor_hittest(
-        &self,
-        surface: &WlSurface,
-        hittest: bool,
-    ) -> Result<(), RequestError> {
-        if hittest {
-            surface.set_input_region(None);
-            Ok(())
-        } else {
-            let region = Region::new(&*self.compositor).map_err(|err| os_error!(err))?;
-            region.add(0, 0, 0, 0);
-            surface.set_input_region(Some(region.wl_region()));
-            Ok(())
-        }
-    }
 }
 
 /// The request from the window to the event loop.
@@ -129,6 +113,9 @@ pub struct WindowRequests {
 
     /// Redraw Requested.
     pub redraw_requested: AtomicBool,
+
+    /// Cancelling the pending compose sequence was requested.
+    pub cancel_compose: AtomicBool,
 }
 
 impl WindowRequests {
@@ -139,4 +126,8 @@ impl WindowRequests {
     pub fn take_redraw_requested(&self) -> bool {
         self.redraw_requested.swap(false, Ordering::Relaxed)
     }
+
+    pub fn take_cancel_compose(&self) -> bool {
+        self.cancel_compose.swap(false, Ordering::Relaxed)
+    }
 }
@@ -20,9 +20,9 @@ use winit_core::event::{Ime, WindowEvent};
 use winit_core::event_loop::AsyncRequestSerial;
 use winit_core::monitor::{Fullscreen, MonitorHandle as CoreMonitorHandle};
 use winit_core::window::{
-    CursorGrabMode, ImeCapabilities, ImeRequest, ImeRequestError, ResizeDirection, Theme,
+    CursorGrabMode, ImeCapabilities, ImeRequest, ImeRequestError, Rect, ResizeDirection, Theme,
     UserAttentionType, Window as CoreWindow, WindowAttributes, WindowButtons, WindowId,
-    WindowLevel,
+    WindowLevel, WindowStates,
 };
 
 use super::ActiveEventLoop;
@@ -67,7 +67,6 @@ impl Window {
         let monitors = state.monitors.clone();
 
         let surface = state.compositor_state.create_surface(&queue_handle);
-        let compositor = state.compositor_state.clone();
         let xdg_activation =
             state.xdg_activation.as_ref().map(|activation_state| activation_state.global().clone());
         let display = event_loop_window_target.handle.connection.display();
@@ -185,6 +184,7 @@ impl Window {
         let window_requests = WindowRequests {
             redraw_requested: AtomicBool::new(true),
             closed: AtomicBool::new(false),
+            cancel_compose: AtomicBool::new(false),
         };
         let window_requests = Arc::new(window_requests);
         state.window_requests.get_mut().insert(window_id, window_requests.clone());
@@ -221,8 +221,6 @@ impl Window {
                 event_loop_awakener,
                 window_events_sink,
 
-                compositor,
-
                 xdg_activation,
                 attention_requested: Arc::new(AtomicBool::new(false)),
             },
@@ -313,6 +311,10 @@ impl CoreWindow for Window {
         winit_common::xkb::reset_dead_keys()
     }
 
+    fn cancel_compose(&self) {
+        self.handles.cancel_compose();
+    }
+
     fn surface_position(&self) -> PhysicalPosition<i32> {
         (0, 0).into()
     }
@@ -440,15 +442,11 @@ impl CoreWindow for Window {
     }
 
     fn is_maximized(&self) -> bool {
-        if let WindowType::Window { last_configure, .. } = &self.window_state.lock().unwrap().window
-        {
-            last_configure
-                .as_ref()
-                .map(|last_configure| last_configure.is_maximized())
-                .unwrap_or_default()
-        } else {
-            false
-        }
+        self.states().contains(WindowStates::MAXIMIZED)
+    }
+
+    fn states(&self) -> WindowStates {
+        self.window_state.lock().unwrap().states()
     }
 
     fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
@@ -595,7 +593,11 @@ impl CoreWindow for Window {
     }
 
     fn set_cursor_hittest(&self, hittest: bool) -> Result<(), RequestError> {
-        self.handles.set_cursor_hittest(self.surface(), hittest)
+        self.set_input_region(if hittest { None } else { Some(&[]) })
+    }
+
+    fn set_input_region(&self, region: Option<&[Rect]>) -> Result<(), RequestError> {
+        self.window_state.lock().unwrap().set_input_region(region)
     }
 
     fn current_monitor(&self) -> Option<CoreMonitorHandle> {
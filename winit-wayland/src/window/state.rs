@@ -17,7 +17,6 @@ use sctk::reexports::csd_frame::{
     DecorationsFrame, FrameAction, FrameClick, ResizeEdge, WindowState as XdgWindowState,
 };
 use sctk::reexports::protocols::wp::fractional_scale::v1::client::wp_fractional_scale_v1::WpFractionalScaleV1;
-use sctk::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::ZwpTextInputV3;
 use sctk::reexports::protocols::wp::viewporter::client::wp_viewport::WpViewport;
 use sctk::reexports::protocols::xdg::shell::client::xdg_toplevel::ResizeEdge as XdgResizeEdge;
 use sctk::seat::pointer::{PointerData, ThemedPointer};
@@ -33,13 +32,13 @@ use wayland_protocols::xdg::toplevel_icon::v1::client::xdg_toplevel_icon_manager
 use winit_core::cursor::{CursorIcon, CustomCursor as CoreCustomCursor};
 use winit_core::error::{NotSupportedError, RequestError};
 use winit_core::window::{
-    CursorGrabMode, ImeCapabilities, ImeRequest, ImeRequestError, ResizeDirection, Theme, WindowId,
+    CursorGrabMode, ImeCapabilities, ImeRequest, ImeRequestError, Rect, ResizeDirection, Theme,
+    WindowId, WindowStates,
 };
 
 use crate::event_loop::OwnedDisplayHandle;
 use crate::seat::{
-    PointerConstraintsState, TextInputClientState, WinitPointerData, WinitPointerDataExt,
-    ZwpTextInputV3Ext,
+    PointerConstraintsState, TextInput, TextInputClientState, WinitPointerData, WinitPointerDataExt,
 };
 use crate::state::{WindowCompositorUpdate, WinitState};
 use crate::types::bgr_effects::{BgrEffectManager, SurfaceBlurEffect};
@@ -163,7 +162,7 @@ pub struct WindowState {
     text_input_state: Option<TextInputClientState>,
 
     /// The text inputs observed on the window.
-    text_inputs: Vec<ZwpTextInputV3>,
+    text_inputs: Vec<TextInput>,
 
     /// The surface size of the window, as in without client side decorations.
     size: LogicalSize<u32>,
@@ -204,6 +203,9 @@ pub struct WindowState {
     /// The value is the serial of the event triggered moved.
     has_pending_move: Option<u32>,
 
+    /// The region set with `set_input_region`, reapplied when the scale factor changes.
+    input_region: Option<Vec<Rect>>,
+
     /// The underlying SCTK window.
     pub window: WindowType,
 
@@ -270,6 +272,7 @@ impl WindowState {
             frame_callback_state: FrameCallbackState::None,
             seat_focus: Default::default(),
             has_pending_move: None,
+            input_region: None,
             text_input_state: None,
             max_surface_size: None,
             min_surface_size: MIN_WINDOW_SIZE,
@@ -550,6 +553,30 @@ impl WindowState {
         }
     }
 
+    /// The toplevel states from the last configure.
+    pub fn states(&self) -> WindowStates {
+        let WindowType::Window { last_configure: Some(configure), .. } = &self.window else {
+            return WindowStates::empty();
+        };
+
+        let flags = [
+            (XdgWindowState::MAXIMIZED, WindowStates::MAXIMIZED),
+            (XdgWindowState::FULLSCREEN, WindowStates::FULLSCREEN),
+            (XdgWindowState::ACTIVATED, WindowStates::ACTIVATED),
+            (XdgWindowState::TILED_LEFT, WindowStates::TILED_LEFT),
+            (XdgWindowState::TILED_RIGHT, WindowStates::TILED_RIGHT),
+            (XdgWindowState::TILED_TOP, WindowStates::TILED_TOP),
+            (XdgWindowState::TILED_BOTTOM, WindowStates::TILED_BOTTOM),
+            (XdgWindowState::SUSPENDED, WindowStates::SUSPENDED),
+        ];
+        let mut states = WindowStates::empty();
+        for (xdg_state, state) in flags {
+            states.set(state, configure.state.contains(xdg_state));
+        }
+
+        states
+    }
+
     #[inline]
     fn is_stateless(configure: &WindowConfigure) -> bool {
         !(configure.is_maximized() || configure.is_fullscreen() || configure.is_tiled())
@@ -1278,8 +1305,9 @@ impl WindowState {
         //
         // WARNING: this doesn't actually handle different seats with independent cursors. There's
         // no API to set a per-seat input method state, so they all share a single state.
+        let surface = self.window.wl_surface();
         for text_input in &self.text_inputs {
-            text_input.set_state(self.text_input_state.as_ref(), state_change);
+            text_input.set_state(surface, self.text_input_state.as_ref(), state_change);
         }
 
         if state_change { Ok(Some(self.text_input_state.is_some())) } else { Ok(None) }
@@ -1298,6 +1326,35 @@ impl WindowState {
         if let Some(frame) = self.frame.as_mut() {
             frame.set_scaling_factor(scale_factor);
         }
+
+        if self.input_region.is_some() {
+            let _ = self.reload_input_region();
+        }
+    }
+
+    /// Set the region of the surface accepting pointer and touch input.
+    pub fn set_input_region(&mut self, region: Option<&[Rect]>) -> Result<(), RequestError> {
+        self.input_region = region.map(<[Rect]>::to_vec);
+        self.reload_input_region()
+    }
+
+    /// Send the input region to the compositor, its rectangles may be in physical coordinates.
+    fn reload_input_region(&self) -> Result<(), RequestError> {
+        let surface = self.window.wl_surface();
+        let Some(input_region) = self.input_region.as_ref() else {
+            surface.set_input_region(None);
+            return Ok(());
+        };
+
+        let region = Region::new(&*self.compositor).map_err(|err| os_error!(err))?;
+        for rect in input_region {
+            let position: LogicalPosition<i32> = rect.position.to_logical(self.scale_factor);
+            let size: LogicalSize<i32> = rect.size.to_logical(self.scale_factor);
+            region.add(position.x, position.y, size.width, size.height);
+        }
+        surface.set_input_region(Some(region.wl_region()));
+
+        Ok(())
     }
 
     /// Make window background blurred.
@@ -1410,7 +1467,7 @@ impl WindowState {
 
     /// Register text input on the top-level.
     #[inline]
-    pub fn text_input_entered(&mut self, text_input: &ZwpTextInputV3) {
+    pub fn text_input_entered(&mut self, text_input: &TextInput) {
         if !self.text_inputs.iter().any(|t| t == text_input) {
             self.text_inputs.push(text_input.clone());
         }
@@ -1418,7 +1475,7 @@ impl WindowState {
 
     /// The text input left the top-level.
     #[inline]
-    pub fn text_input_left(&mut self, text_input: &ZwpTextInputV3) {
+    pub fn text_input_left(&mut self, text_input: &TextInput) {
         if let Some(position) = self.text_inputs.iter().position(|t| t == text_input) {
             self.text_inputs.remove(position);
         }
@@ -14,7 +14,7 @@ use winit_core::application::ApplicationHandler;
 use winit_core::event::{
     DeviceEvent, DeviceId, ElementState, RawKeyEvent, StartCause, WindowEvent,
 };
-use winit_core::event_loop::{ControlFlow, DeviceEvents};
+use winit_core::event_loop::{ControlFlow, DeviceEvents, proxy_channel};
 use winit_core::window::WindowId;
 
 use super::proxy::EventLoopProxy;
@@ -154,7 +154,10 @@ impl Runner {
             Event::DeviceEvent { device_id, event } => {
                 self.app.device_event(&self.event_loop, device_id, event)
             },
-            Event::UserWakeUp => self.app.proxy_wake_up(&self.event_loop),
+            Event::UserWakeUp => {
+                proxy_channel::dispatch_handlers();
+                self.app.proxy_wake_up(&self.event_loop)
+            },
             Event::Suspended => self.app.suspended(&self.event_loop),
             Event::Resumed => self.app.resumed(&self.event_loop),
             Event::CreateSurfaces => self.app.can_create_surfaces(&self.event_loop),
@@ -704,7 +707,7 @@ impl Shared {
             State::Exit
         } else {
             match self.control_flow() {
-                ControlFlow::Poll => {
+                ControlFlow::Poll | ControlFlow::Animate => {
                     let cloned = self.clone();
                     State::Poll {
                         _request: backend::Schedule::new(
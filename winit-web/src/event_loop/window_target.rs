@@ -8,7 +8,7 @@ use web_sys::Element;
 use winit_core::application::ApplicationHandler;
 use winit_core::cursor::{CustomCursor as CoreCustomCursor, CustomCursorSource};
 use winit_core::error::{NotSupportedError, RequestError};
-use winit_core::event::{ElementState, KeyEvent, TouchPhase, WindowEvent};
+use winit_core::event::{ElementState, KeyEvent, ScrollSource, TouchPhase, WindowEvent};
 use winit_core::event_loop::{
     ActiveEventLoop as RootActiveEventLoop, ControlFlow, DeviceEvents,
     EventLoopProxy as RootEventLoopProxy, OwnedDisplayHandle as CoreOwnedDisplayHandle,
@@ -149,6 +149,7 @@ impl ActiveEventLoop {
                                 key_without_modifiers: logical_key,
                             },
                             is_synthetic: false,
+                            timestamp: None,
                         },
                     })
                     .chain(modifiers_changed),
@@ -184,6 +185,7 @@ impl ActiveEventLoop {
                                 key_without_modifiers: logical_key,
                             },
                             is_synthetic: false,
+                            timestamp: None,
                         },
                     })
                     .chain(modifiers_changed),
@@ -267,6 +269,8 @@ impl ActiveEventLoop {
                                     primary,
                                     position,
                                     source,
+                                    timestamp: None,
+                                    history: Vec::new(),
                                 },
                             }))
                         },
@@ -297,6 +301,8 @@ impl ActiveEventLoop {
                             position,
                             button,
                             is_macos_activation_click: false,
+                            timestamp: None,
+                            click_count: 1,
                         },
                     }]));
                 }
@@ -325,6 +331,8 @@ impl ActiveEventLoop {
                         position,
                         button,
                         is_macos_activation_click: false,
+                        timestamp: None,
+                        click_count: 1,
                     },
                 })));
             }
@@ -354,6 +362,8 @@ impl ActiveEventLoop {
                         position,
                         button,
                         is_macos_activation_click: false,
+                        timestamp: None,
+                        click_count: 1,
                     },
                 })));
             }
@@ -378,6 +388,10 @@ impl ActiveEventLoop {
                         device_id: None,
                         delta,
                         phase: TouchPhase::Moved,
+                        timestamp: None,
+                        source: ScrollSource::Unknown,
+                        value120: None,
+                        stopped: false,
                     },
                 },
             )));
@@ -68,8 +68,8 @@ use winit_core::data_transfer::{
 };
 use winit_core::error::{EventLoopError, NotSupportedError, RequestError};
 use winit_core::event::{
-    DeviceEvent, DeviceId, FingerId, Force, Ime, RawKeyEvent, SurfaceSizeWriter, TabletToolButton,
-    TabletToolData, TabletToolKind, TabletToolTilt, TouchPhase, WindowEvent,
+    DeviceEvent, DeviceId, FingerId, Force, Ime, RawKeyEvent, ScrollSource, SurfaceSizeWriter,
+    TabletToolButton, TabletToolData, TabletToolKind, TabletToolTilt, TouchPhase, WindowEvent,
 };
 use winit_core::event_loop::pump_events::PumpStatus;
 use winit_core::event_loop::{
@@ -776,7 +776,7 @@ fn wait_for_messages_impl(
     let timeout = {
         let control_flow_timeout = match control_flow {
             ControlFlow::Wait => None,
-            ControlFlow::Poll => Some(Duration::ZERO),
+            ControlFlow::Poll | ControlFlow::Animate => Some(Duration::ZERO),
             ControlFlow::WaitUntil(wait_deadline) => {
                 let start = Instant::now();
                 Some(wait_deadline.saturating_duration_since(start))
@@ -1208,6 +1208,7 @@ unsafe fn public_window_callback_inner(
                 device_id: None,
                 event: event.event,
                 is_synthetic: event.is_synthetic,
+                timestamp: None,
             });
         }
     };
@@ -1597,7 +1598,7 @@ unsafe fn public_window_callback_inner(
                 if lparam == 0 {
                     userdata.send_window_event(
                         window,
-                        WindowEvent::Ime(Ime::Preedit(String::new(), None)),
+                        WindowEvent::Ime(Ime::Preedit(String::new(), None, Vec::new())),
                     );
                 }
 
@@ -1609,7 +1610,7 @@ unsafe fn public_window_callback_inner(
 
                         userdata.send_window_event(
                             window,
-                            WindowEvent::Ime(Ime::Preedit(String::new(), None)),
+                            WindowEvent::Ime(Ime::Preedit(String::new(), None, Vec::new())),
                         );
                         userdata.send_window_event(window, WindowEvent::Ime(Ime::Commit(text)));
                     }
@@ -1625,7 +1626,7 @@ unsafe fn public_window_callback_inner(
 
                         userdata.send_window_event(
                             window,
-                            WindowEvent::Ime(Ime::Preedit(text, cursor_range)),
+                            WindowEvent::Ime(Ime::Preedit(text, cursor_range, Vec::new())),
                         );
                     }
                 }
@@ -1648,7 +1649,7 @@ unsafe fn public_window_callback_inner(
                     if let Some(text) = unsafe { ime_context.get_composed_text() } {
                         userdata.send_window_event(
                             window,
-                            WindowEvent::Ime(Ime::Preedit(String::new(), None)),
+                            WindowEvent::Ime(Ime::Preedit(String::new(), None, Vec::new())),
                         );
                         userdata.send_window_event(window, WindowEvent::Ime(Ime::Commit(text)));
                     }
@@ -1776,6 +1777,8 @@ unsafe fn public_window_callback_inner(
                     primary: true,
                     position,
                     source: PointerSource::Mouse,
+                    timestamp: None,
+                    history: Vec::new(),
                 });
             }
 
@@ -1832,6 +1835,10 @@ unsafe fn public_window_callback_inner(
                 device_id: None,
                 delta: LineDelta(0.0, value * scroll_lines_multiplier as f32),
                 phase: TouchPhase::Moved,
+                timestamp: None,
+                source: ScrollSource::Unknown,
+                value120: None,
+                stopped: false,
             });
 
             result = ProcResult::Value(0);
@@ -1865,6 +1872,10 @@ unsafe fn public_window_callback_inner(
                 device_id: None,
                 delta: LineDelta(value * scroll_characters_multiplier as f32, 0.0),
                 phase: TouchPhase::Moved,
+                timestamp: None,
+                source: ScrollSource::Unknown,
+                value120: None,
+                stopped: false,
             });
 
             result = ProcResult::Value(0);
@@ -1910,6 +1921,8 @@ unsafe fn public_window_callback_inner(
                 }
                 .into(),
                 is_macos_activation_click: false,
+                timestamp: None,
+                click_count: 1,
             });
             result = ProcResult::Value(0);
         },
@@ -1940,6 +1953,8 @@ unsafe fn public_window_callback_inner(
                 }
                 .into(),
                 is_macos_activation_click: false,
+                timestamp: None,
+                click_count: 1,
             });
             result = ProcResult::Value(0);
         },
@@ -1969,6 +1984,8 @@ unsafe fn public_window_callback_inner(
                 // 1 is defined as back, 2 as forward; other codes are unexpected.
                 button: MouseButton::try_from_u8(b).unwrap().into(),
                 is_macos_activation_click: false,
+                timestamp: None,
+                click_count: 1,
             });
             result = ProcResult::Value(0);
         },
@@ -1999,6 +2016,8 @@ unsafe fn public_window_callback_inner(
                 // 1 is defined as back, 2 as forward; other codes are unexpected.
                 button: MouseButton::try_from_u8(b).unwrap().into(),
                 is_macos_activation_click: false,
+                timestamp: None,
+                click_count: 1,
             });
             result = ProcResult::Value(0);
         },
@@ -2059,6 +2078,8 @@ unsafe fn public_window_callback_inner(
                             position,
                             button: Touch { finger_id, force: None },
                             is_macos_activation_click: false,
+                            timestamp: None,
+                            click_count: 1,
                         });
                     } else if util::has_flag(input.dwFlags, TOUCHEVENTF_UP) {
                         userdata.send_window_event(window, WindowEvent::PointerButton {
@@ -2068,6 +2089,8 @@ unsafe fn public_window_callback_inner(
                             position,
                             button: Touch { finger_id, force: None },
                             is_macos_activation_click: false,
+                            timestamp: None,
+                            click_count: 1,
                         });
                         userdata.send_window_event(window, WindowEvent::PointerLeft {
                             device_id: None,
@@ -2081,6 +2104,8 @@ unsafe fn public_window_callback_inner(
                             primary,
                             position,
                             source: PointerSource::Touch { finger_id, force: None },
+                            timestamp: None,
+                            history: Vec::new(),
                         });
                     } else {
                         continue;
@@ -2230,6 +2255,8 @@ unsafe fn public_window_callback_inner(
                                 position,
                                 button,
                                 is_macos_activation_click: false,
+                                timestamp: None,
+                                click_count: 1,
                             });
                         } else {
                             userdata.send_window_event(window, WindowEvent::PointerButton {
@@ -2239,6 +2266,8 @@ unsafe fn public_window_callback_inner(
                                 position,
                                 button,
                                 is_macos_activation_click: false,
+                                timestamp: None,
+                                click_count: 1,
                             });
                             userdata.send_window_event(window, WindowEvent::PointerLeft {
                                 device_id: None,
@@ -2265,6 +2294,8 @@ unsafe fn public_window_callback_inner(
                             primary,
                             position,
                             source,
+                            timestamp: None,
+                            history: Vec::new(),
                         });
                     } else {
                         continue;
@@ -14,7 +14,7 @@ use windows_sys::Win32::System::Ole::{
 use winit_core::application::ApplicationHandler;
 use winit_core::data_transfer::DataTransferId;
 use winit_core::event::{DeviceEvent, DeviceId, StartCause, SurfaceSizeWriter, WindowEvent};
-use winit_core::event_loop::{ActiveEventLoop as RootActiveEventLoop, DndAction};
+use winit_core::event_loop::{ActiveEventLoop as RootActiveEventLoop, DndAction, proxy_channel};
 use winit_core::window::WindowId;
 
 use super::{ActiveEventLoop, ControlFlow, EventLoopThreadExecutor};
@@ -539,7 +539,7 @@ impl EventLoopRunner {
     fn call_new_events(self: &Rc<Self>, init: bool) {
         let start_cause = match (init, self.control_flow(), self.exit.get()) {
             (true, ..) => StartCause::Init,
-            (false, ControlFlow::Poll, None) => StartCause::Poll,
+            (false, ControlFlow::Poll | ControlFlow::Animate, None) => StartCause::Poll,
             (false, _, Some(_)) | (false, ControlFlow::Wait, None) => StartCause::WaitCancelled {
                 requested_resume: None,
                 start: self.last_events_cleared.get(),
@@ -620,7 +620,10 @@ impl Event {
                     window_flags.set_size(window, surface_size);
                 }
             },
-            Self::WakeUp => app.proxy_wake_up(event_loop),
+            Self::WakeUp => {
+                proxy_channel::dispatch_handlers();
+                app.proxy_wake_up(event_loop)
+            },
         }
     }
 }
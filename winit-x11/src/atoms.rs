@@ -36,6 +36,7 @@ atom_manager! {
     CARD32,
     STRING,
     UTF8_STRING,
+    MANAGER,
     WM_CHANGE_STATE,
     WM_CLIENT_MACHINE,
     WM_DELETE_WINDOW,
@@ -51,13 +52,17 @@ atom_manager! {
     _NET_WM_PING,
     _NET_WM_SYNC_REQUEST,
     _NET_WM_SYNC_REQUEST_COUNTER,
+    _NET_WM_FRAME_DRAWN,
     _NET_WM_STATE,
     _NET_WM_STATE_ABOVE,
     _NET_WM_STATE_BELOW,
+    _NET_WM_STATE_FOCUSED,
     _NET_WM_STATE_FULLSCREEN,
     _NET_WM_STATE_HIDDEN,
     _NET_WM_STATE_MAXIMIZED_HORZ,
     _NET_WM_STATE_MAXIMIZED_VERT,
+    _NET_WM_STATE_SHADED,
+    _NET_WM_STATE_STICKY,
     _NET_WM_WINDOW_TYPE,
 
     // Activation atoms.
@@ -2,6 +2,7 @@ use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::ffi::CStr;
 use std::mem::MaybeUninit;
+use std::num::NonZeroU32;
 use std::ops::Deref;
 use std::os::raw::*;
 use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
@@ -16,18 +17,22 @@ use calloop::ping::Ping;
 use calloop::{EventLoop as Loop, Readiness};
 use libc::{LC_CTYPE, setlocale};
 use tracing::warn;
-use winit_common::xkb::Context;
+use winit_common::app_sources::AppSources;
+use winit_common::coalescing::CoalescingHandler;
+use winit_common::xkb::{Context, KeyLookup, KeymapSource};
 use winit_core::application::ApplicationHandler;
 use winit_core::cursor::{CustomCursor as CoreCustomCursor, CustomCursorSource};
 use winit_core::data_transfer::{DataTransfer, DataTransferId, TransferType};
 use winit_core::error::{EventLoopError, NotSupportedError, RequestError};
-use winit_core::event::{DeviceId, StartCause, WindowEvent};
+use winit_core::event::{Compose, DeviceId, StartCause, WindowEvent};
 use winit_core::event_loop::pump_events::PumpStatus;
 use winit_core::event_loop::{
     ActiveEventLoop as RootActiveEventLoop, AsyncRequestSerial, ControlFlow, DeviceEvents,
     DndAction, EventLoopProvider, EventLoopProxy as CoreEventLoopProxy, EventLoopProxyProvider,
-    OwnedDisplayHandle as CoreOwnedDisplayHandle,
+    FdReadiness, FdRegistration, KeyRepeat, OwnedDisplayHandle as CoreOwnedDisplayHandle,
+    PointerCoalescing, SystemSettings, TimerId, TimerSchedule, proxy_channel,
 };
+use winit_core::keyboard::{Key, KeyboardLayouts, ModifiersState, PhysicalKey};
 use winit_core::monitor::MonitorHandle as CoreMonitorHandle;
 use winit_core::window::{Theme, Window as CoreWindow, WindowAttributes, WindowId};
 use x11rb::connection::RequestConnection;
@@ -43,7 +48,7 @@ use crate::atoms::{
 };
 use crate::dnd::Dnd;
 use crate::event_processor::{EventProcessor, MAX_MOD_REPLAY_LEN};
-use crate::ime::{self, Ime, ImeCreationError, ImeSender};
+use crate::ime::{self, DBusIme, Ime, ImeCreationError, ImeSender};
 use crate::util::{self, CustomCursor};
 use crate::window::{UnownedWindow, Window};
 use crate::xdisplay::{XConnection, XError, XNotSupported};
@@ -59,6 +64,9 @@ type X11rbConnection = x11rb::xcb_ffi::XCBConnection;
 
 type X11Source = Generic<BorrowedFd<'static>>;
 
+/// How long to wait for `_NET_WM_FRAME_DRAWN` before redrawing an animated window anyway.
+const FRAME_DRAWN_TIMEOUT: Duration = Duration::from_secs(1);
+
 pub(crate) static X11_BACKEND: LazyLock<Mutex<Result<Arc<XConnection>, XNotSupported>>> =
     LazyLock::new(|| Mutex::new(XConnection::new(Some(x_error_callback)).map(Arc::new)));
 
@@ -182,10 +190,24 @@ pub struct ActiveEventLoop {
     pub(crate) root: xproto::Window,
     pub(crate) ime: Option<RefCell<Ime>>,
     pub(crate) windows: RefCell<HashMap<WindowId, Weak<UnownedWindow>>>,
+    /// The animated windows whose frame the compositor didn't draw yet, with since when and the
+    /// value of their frame counter.
+    pub(crate) frames_pending: RefCell<HashMap<WindowId, (Instant, u64)>>,
     pub(crate) redraw_sender: WakeSender<WindowId>,
+    pub(crate) cancel_compose_sender: WakeSender<WindowId>,
     pub(crate) activation_sender: WakeSender<ActivationItem>,
+    /// Whether the server supports XInput 2.4 touchpad gesture events.
+    pub(crate) xi2_gestures: bool,
+    /// The keyboard layouts last reported with `WindowEvent::KeyboardLayoutChanged`.
+    pub(crate) keyboard_layouts: RefCell<Option<KeyboardLayouts>>,
+    /// The keymap and layout used by `key_for_physical` and `physical_for_key`.
+    pub(crate) key_lookup: RefCell<Option<KeyLookup>>,
     event_loop_proxy: CoreEventLoopProxy,
     device_events: Cell<DeviceEvents>,
+    pub(crate) key_repeat: Cell<KeyRepeat>,
+    pointer_coalescing: Cell<PointerCoalescing>,
+    /// The timers and the file descriptors of the application.
+    sources: AppSources<EventLoopState>,
 }
 
 #[derive(Debug)]
@@ -195,6 +217,13 @@ pub struct EventLoop {
     event_processor: EventProcessor,
     redraw_receiver: PeekableReceiver<WindowId>,
     activation_receiver: PeekableReceiver<ActivationItem>,
+    cancel_compose_receiver: PeekableReceiver<WindowId>,
+
+    /// When to redraw the windows next with [`ControlFlow::Animate`].
+    next_frame: Option<Instant>,
+
+    /// Whether the connection to the X server was lost.
+    connection_lost: bool,
 
     /// The current state of the event loop.
     state: EventLoopState,
@@ -209,10 +238,13 @@ struct EventLoopState {
 
     /// User requested a wake up.
     proxy_wake_up: bool,
+
+    /// The D-Bus input method sent something.
+    dbus_ime_ready: bool,
 }
 
 impl EventLoop {
-    pub fn new() -> Result<EventLoop, EventLoopError> {
+    pub fn new(keymap: Option<KeymapSource>) -> Result<EventLoop, EventLoopError> {
         static EVENT_LOOP_CREATED: AtomicBool = AtomicBool::new(false);
         if EVENT_LOOP_CREATED.swap(true, Ordering::Relaxed) {
             // Required?
@@ -259,14 +291,22 @@ impl EventLoop {
             }
         }
 
-        let ime = Ime::new(Arc::clone(&xconn), ime_event_sender);
-        if let Err(ImeCreationError::OpenFailure(state)) = ime.as_ref() {
-            warn!("Failed to open input method: {state:#?}");
-        } else if let Err(err) = ime.as_ref() {
-            warn!("Failed to set input method destruction callback: {err:?}");
-        }
+        // Talk to IBus or Fcitx directly when the user selected them, XIM otherwise.
+        let dbus_ime = DBusIme::new(Arc::clone(&xconn), root);
+        let mut xim_event_sender = None;
+        let ime = if dbus_ime.is_some() {
+            xim_event_sender = Some(ime_event_sender);
+            None
+        } else {
+            let ime = Ime::new(Arc::clone(&xconn), ime_event_sender);
+            if let Err(ImeCreationError::OpenFailure(state)) = ime.as_ref() {
+                warn!("Failed to open input method: {state:#?}");
+            } else if let Err(err) = ime.as_ref() {
+                warn!("Failed to set input method destruction callback: {err:?}");
+            }
 
-        let ime = ime.ok().map(RefCell::new);
+            ime.ok().map(RefCell::new)
+        };
 
         let randr_event_offset =
             xconn.select_xrandr_input(root).expect("Failed to query XRandR extension");
@@ -283,13 +323,16 @@ impl EventLoop {
             .expect("X server missing XKB extension");
 
         // Check for XInput2 support.
-        xconn
+        let xi2_version = xconn
             .xcb_connection()
-            .xinput_xi_query_version(2, 3)
+            .xinput_xi_query_version(2, 4)
             .expect("Failed to send XInput2 query version request")
             .reply()
             .expect("Error while checking for XInput2 query version reply");
 
+        // Touchpad gestures were added in XInput 2.4.
+        let xi2_gestures = (xi2_version.major_version, xi2_version.minor_version) >= (2, 4);
+
         xconn.update_cached_wm_info(root);
 
         // Create an event loop.
@@ -311,6 +354,22 @@ impl EventLoop {
             })
             .expect("Failed to register the X11 event dispatcher");
 
+        // Wake up when the D-Bus input method sends something, it's processed with the X events.
+        if let Some(dbus_ime) = dbus_ime.as_ref() {
+            match dbus_ime.as_fd().try_clone_to_owned() {
+                Ok(fd) => {
+                    let source = Generic::new(fd, calloop::Interest::READ, calloop::Mode::Level);
+                    handle
+                        .insert_source(source, |_, _, state: &mut EventLoopState| {
+                            state.dbus_ime_ready = true;
+                            Ok(calloop::PostAction::Continue)
+                        })
+                        .expect("Failed to register the input method source");
+                },
+                Err(err) => warn!("Failed to watch the input method connection: {err}"),
+            }
+        }
+
         let (waker, waker_source) =
             calloop::ping::make_ping().expect("Failed to create event loop waker");
         event_loop
@@ -326,6 +385,9 @@ impl EventLoop {
         // Create a channel for sending activation tokens.
         let (activation_token_sender, activation_token_channel) = mpsc::channel();
 
+        // Create a channel for cancelling compose sequences.
+        let (cancel_compose_sender, cancel_compose_channel) = mpsc::channel();
+
         // Create a channel for sending user events.
         let (user_waker, user_waker_source) =
             calloop::ping::make_ping().expect("Failed to create user event loop waker.");
@@ -338,8 +400,11 @@ impl EventLoop {
             .expect("Failed to register the event loop waker source");
         let event_loop_proxy = EventLoopProxy::new(user_waker);
 
-        let xkb_context =
+        let mut xkb_context =
             Context::from_x11_xkb(xconn.xcb_connection().get_raw_xcb_connection()).unwrap();
+        if let Some(keymap) = &keymap {
+            xkb_context.set_fixed_keymap(keymap);
+        }
 
         let mut xmodmap = util::ModifierKeymap::new();
         xmodmap.reload_from_x_connection(&xconn);
@@ -351,6 +416,7 @@ impl EventLoop {
             control_flow: Cell::new(ControlFlow::default()),
             exit: Cell::new(None),
             windows: Default::default(),
+            frames_pending: Default::default(),
             ime_sender,
             xconn,
             wm_delete_window,
@@ -364,8 +430,18 @@ impl EventLoop {
                 sender: activation_token_sender, // not used again so no clone
                 waker: waker.clone(),
             },
+            cancel_compose_sender: WakeSender {
+                sender: cancel_compose_sender, // not used again so no clone
+                waker: waker.clone(),
+            },
+            xi2_gestures,
+            keyboard_layouts: RefCell::new(xkb_context.keyboard_layouts()),
+            key_lookup: RefCell::new(xkb_context.key_lookup()),
             event_loop_proxy: event_loop_proxy.into(),
             device_events: Default::default(),
+            key_repeat: Default::default(),
+            pointer_coalescing: Default::default(),
+            sources: AppSources::new(event_loop.handle(), |_| ()),
         };
 
         // Set initial device event filter.
@@ -377,6 +453,8 @@ impl EventLoop {
             randr_event_offset,
             ime_receiver,
             ime_event_receiver,
+            dbus_ime,
+            xim_event_sender,
             xi2ext,
             xfiltered_modifiers: VecDeque::with_capacity(MAX_MOD_REPLAY_LEN),
             xmodmap,
@@ -384,10 +462,14 @@ impl EventLoop {
             xkb_context,
             num_touch: 0,
             held_key_press: None,
+            key_repeater: Default::default(),
             first_touch: None,
             active_window: None,
             modifiers: Default::default(),
             is_composing: false,
+            previous_pinch_scale: 1.0,
+            click_counter: Default::default(),
+            motion_history: Default::default(),
         };
 
         // Register for device hotplug events
@@ -409,7 +491,8 @@ impl EventLoop {
                 0x100, // Use the "core keyboard device"
                 xkb::EventType::NEW_KEYBOARD_NOTIFY
                     | xkb::EventType::MAP_NOTIFY
-                    | xkb::EventType::STATE_NOTIFY,
+                    | xkb::EventType::STATE_NOTIFY
+                    | xkb::EventType::CONTROLS_NOTIFY,
             )
             .unwrap();
 
@@ -421,7 +504,14 @@ impl EventLoop {
             event_processor,
             redraw_receiver: PeekableReceiver::from_recv(redraw_channel),
             activation_receiver: PeekableReceiver::from_recv(activation_token_channel),
-            state: EventLoopState { x11_readiness: Readiness::EMPTY, proxy_wake_up: false },
+            cancel_compose_receiver: PeekableReceiver::from_recv(cancel_compose_channel),
+            next_frame: None,
+            connection_lost: false,
+            state: EventLoopState {
+                x11_readiness: Readiness::EMPTY,
+                proxy_wake_up: false,
+                dbus_ime_ready: false,
+            },
         };
 
         Ok(event_loop)
@@ -450,6 +540,10 @@ impl EventLoop {
             }
         };
 
+        if self.connection_lost {
+            return Err(EventLoopError::ConnectionLost);
+        }
+
         // Applications aren't allowed to carry windows between separate
         // `run_on_demand` calls but if they have only just dropped their
         // windows we need to make sure those last requests are sent to the
@@ -480,6 +574,15 @@ impl EventLoop {
         if !self.exiting() {
             self.poll_events_with_timeout(timeout, &mut app);
         }
+
+        // Xlib notices the loss of the connection when reading the events.
+        if !self.connection_lost && self.event_processor.target.xconn.is_disconnected() {
+            tracing::error!("lost the connection to the X server");
+            self.connection_lost = true;
+            app.connection_lost(&self.event_processor.target);
+            self.set_exit_code(1);
+        }
+
         if let Some(code) = self.exit_code() {
             self.loop_running = false;
 
@@ -492,7 +595,10 @@ impl EventLoop {
     fn has_pending(&mut self) -> bool {
         self.event_processor.poll()
             || self.state.proxy_wake_up
+            || self.state.dbus_ime_ready
+            || self.event_processor.target.sources.has_pending()
             || self.redraw_receiver.has_incoming()
+            || self.cancel_compose_receiver.has_incoming()
     }
 
     fn poll_events_with_timeout<A: ApplicationHandler>(
@@ -514,9 +620,23 @@ impl EventLoop {
                 ControlFlow::WaitUntil(wait_deadline) => {
                     Some(wait_deadline.saturating_duration_since(start))
                 },
+                ControlFlow::Animate => Some(
+                    self.next_animation_frame()
+                        .map_or(Duration::ZERO, |frame| frame.saturating_duration_since(start)),
+                ),
             };
 
-            min_timeout(control_flow_timeout, timeout)
+            // Wake up to repeat the held key, and to stop waiting for the input method.
+            let repeat_timeout = [
+                self.event_processor.key_repeat_deadline(),
+                self.event_processor.ime_key_deadline(),
+            ]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|deadline| deadline.saturating_duration_since(start));
+
+            min_timeout(min_timeout(control_flow_timeout, repeat_timeout), timeout)
         };
 
         self.state.x11_readiness = Readiness::EMPTY;
@@ -533,7 +653,9 @@ impl EventLoop {
         // to be considered here
         let cause = match self.control_flow() {
             ControlFlow::Poll => StartCause::Poll,
-            ControlFlow::Wait => StartCause::WaitCancelled { start, requested_resume: None },
+            ControlFlow::Wait | ControlFlow::Animate => {
+                StartCause::WaitCancelled { start, requested_resume: None }
+            },
             ControlFlow::WaitUntil(deadline) => {
                 if Instant::now() < deadline {
                     StartCause::WaitCancelled { start, requested_resume: Some(deadline) }
@@ -571,7 +693,22 @@ impl EventLoop {
         }
 
         // Process all pending events
+        self.state.dbus_ime_ready = false;
         self.drain_events(app);
+        if self.event_processor.target.xconn.is_disconnected() {
+            return;
+        }
+        self.event_processor.repeat_key(app);
+
+        // Fire the timers, and report the ready file descriptors. Both may be unregistered by the
+        // application meanwhile.
+        while let Some(timer_id) = self.event_processor.target.sources.take_fired_timer() {
+            app.timer_fired(&self.event_processor.target, timer_id);
+        }
+        while let Some((source_id, readiness)) = self.event_processor.target.sources.take_ready_fd()
+        {
+            app.fd_ready(&self.event_processor.target, source_id, readiness);
+        }
 
         // Empty activation tokens.
         while let Ok((window_id, serial)) = self.activation_receiver.try_recv() {
@@ -596,8 +733,19 @@ impl EventLoop {
             }
         }
 
+        // Cancel the compose sequences, which only concern the focused window.
+        while let Ok(window_id) = self.cancel_compose_receiver.try_recv() {
+            let event_processor = &mut self.event_processor;
+            let focused = event_processor.active_window == Some(window_id.into_raw() as _);
+            if focused && event_processor.xkb_context.cancel_compose() {
+                let event = WindowEvent::Compose(Compose::Cancelled);
+                app.window_event(&event_processor.target, window_id, event);
+            }
+        }
+
         // Empty the user event buffer
         if mem::take(&mut self.state.proxy_wake_up) {
+            proxy_channel::dispatch_handlers();
             app.proxy_wake_up(&self.event_processor.target);
         }
 
@@ -609,12 +757,28 @@ impl EventLoop {
                 windows.insert(window_id);
             }
 
+            let animate = self.control_flow() == ControlFlow::Animate;
+            if animate {
+                self.animation_frame(&mut windows);
+            } else {
+                self.next_frame = None;
+                self.event_processor.target.frames_pending.borrow_mut().clear();
+            }
+
             for window_id in windows {
-                app.window_event(
-                    &self.event_processor.target,
-                    window_id,
-                    WindowEvent::RedrawRequested,
-                );
+                let target = &self.event_processor.target;
+                let synced_window = animate
+                    .then(|| target.windows.borrow().get(&window_id)?.upgrade())
+                    .flatten()
+                    .filter(|window| window.has_frame_counter());
+
+                if let Some(window) = &synced_window {
+                    window.begin_frame();
+                }
+                app.window_event(target, window_id, WindowEvent::RedrawRequested);
+                if let Some(value) = synced_window.and_then(|window| window.end_frame()) {
+                    target.frames_pending.borrow_mut().insert(window_id, (Instant::now(), value));
+                }
             }
         }
 
@@ -622,12 +786,101 @@ impl EventLoop {
         app.about_to_wait(&self.event_processor.target);
     }
 
+    /// Add the animated windows due for a redraw.
+    ///
+    /// The windows with a frame counter are redrawn once the compositor drew their previous frame,
+    /// the others are paced by a timer at the refresh rate of the monitors.
+    fn animation_frame(&mut self, windows: &mut HashSet<WindowId>) {
+        let now = Instant::now();
+        let timer_due = self.next_frame.is_none_or(|frame| frame <= now);
+        let mut timed = false;
+
+        let target = &self.event_processor.target;
+        let mut frames_pending = target.frames_pending.borrow_mut();
+        let target_windows = target.windows.borrow();
+        frames_pending.retain(|window_id, _| target_windows.contains_key(window_id));
+        for (&window_id, window) in target_windows.iter() {
+            if window.upgrade().is_some_and(|window| window.has_frame_counter()) {
+                let drawn = frames_pending
+                    .get(&window_id)
+                    .is_none_or(|&(since, _)| since + FRAME_DRAWN_TIMEOUT <= now);
+                if drawn {
+                    windows.insert(window_id);
+                }
+            } else {
+                timed = true;
+                if timer_due {
+                    windows.insert(window_id);
+                }
+            }
+        }
+        drop((frames_pending, target_windows));
+
+        if !timed && !target.windows.borrow().is_empty() {
+            self.next_frame = None;
+        } else if timer_due {
+            let interval = self.frame_interval();
+            // Keep the frames aligned unless we're late by more than one of them.
+            self.next_frame = match self.next_frame {
+                Some(frame) if frame + interval > now => Some(frame + interval),
+                _ => Some(now + interval),
+            };
+        }
+    }
+
+    /// When to redraw the next animated window, `None` when one is due now.
+    fn next_animation_frame(&self) -> Option<Instant> {
+        let target = &self.event_processor.target;
+        let frames_pending = target.frames_pending.borrow();
+        let mut next = None;
+        let mut timed = false;
+        for (window_id, window) in target.windows.borrow().iter() {
+            if window.upgrade().is_some_and(|window| window.has_frame_counter()) {
+                let &(since, _) = frames_pending.get(window_id)?;
+                next = Some(next.map_or(since + FRAME_DRAWN_TIMEOUT, |next: Instant| {
+                    next.min(since + FRAME_DRAWN_TIMEOUT)
+                }));
+            } else {
+                timed = true;
+            }
+        }
+
+        if timed || next.is_none() {
+            let frame = self.next_frame?;
+            next = Some(next.map_or(frame, |next| next.min(frame)));
+        }
+        next
+    }
+
+    /// The interval between the frames of the fastest monitor displaying a window.
+    fn frame_interval(&self) -> Duration {
+        let refresh_rate = self
+            .event_processor
+            .target
+            .windows
+            .borrow()
+            .values()
+            .filter_map(|window| window.upgrade()?.refresh_rate_millihertz())
+            .max()
+            .map_or(60_000, NonZeroU32::get);
+        Duration::from_secs(1000) / refresh_rate
+    }
+
     fn drain_events<A: ApplicationHandler>(&mut self, app: &mut A) {
         let mut xev = MaybeUninit::uninit();
-
-        while let Some(xev) = self.event_processor.poll_one_event(&mut xev) {
-            self.event_processor.process_event(xev, app);
+        let coalescing = self.event_processor.target.pointer_coalescing.get();
+        let mut coalescing_app = CoalescingHandler::new(coalescing, &mut *app);
+
+        // The queued events are left once the connection is lost, the requests made to handle
+        // them failing.
+        while !self.event_processor.target.xconn.is_disconnected() {
+            let Some(xev) = self.event_processor.poll_one_event(&mut xev) else { break };
+            self.event_processor.process_event(xev, &mut coalescing_app);
         }
+        coalescing_app.flush(&self.event_processor.target);
+
+        // Requests and input method events may come without any X event.
+        self.event_processor.process_ime(app);
     }
 
     fn control_flow(&self) -> ControlFlow {
@@ -720,6 +973,15 @@ impl ActiveEventLoop {
             .expect_then_ignore_error("Failed to update device event filter");
     }
 
+    /// Watch a file descriptor of the application.
+    pub(crate) fn register_fd(
+        &self,
+        fd: BorrowedFd<'_>,
+        interest: FdReadiness,
+    ) -> Result<FdRegistration, RequestError> {
+        self.sources.register_fd(fd, interest)
+    }
+
     pub(crate) fn clear_exit(&self) {
         self.exit.set(None)
     }
@@ -767,13 +1029,54 @@ impl RootActiveEventLoop for ActiveEventLoop {
     }
 
     fn system_theme(&self) -> Option<Theme> {
-        None
+        self.xconn.xsettings().theme()
+    }
+
+    fn system_settings(&self) -> SystemSettings {
+        let mut settings = self.xconn.xsettings().system_settings();
+        match self.xconn.query_key_repeat() {
+            Ok((delay, interval)) => {
+                settings.key_repeat_delay = Some(delay);
+                settings.key_repeat_interval = Some(interval);
+            },
+            Err(err) => warn!("failed to query the key repeat controls: {err}"),
+        }
+
+        settings
+    }
+
+    fn keyboard_layouts(&self) -> Option<KeyboardLayouts> {
+        self.keyboard_layouts.borrow().clone()
+    }
+
+    fn key_for_physical(&self, key: PhysicalKey, modifiers: ModifiersState) -> Option<Key> {
+        self.key_lookup.borrow().as_ref()?.key_for_physical(key, modifiers)
+    }
+
+    fn physical_for_key(&self, key: &Key) -> Option<(PhysicalKey, ModifiersState)> {
+        self.key_lookup.borrow().as_ref()?.physical_for_key(key)
     }
 
     fn listen_device_events(&self, allowed: DeviceEvents) {
         self.device_events.set(allowed);
     }
 
+    fn add_timer(&self, schedule: TimerSchedule) -> Result<TimerId, RequestError> {
+        self.sources.add_timer(schedule)
+    }
+
+    fn cancel_timer(&self, timer_id: TimerId) {
+        self.sources.cancel_timer(timer_id)
+    }
+
+    fn set_key_repeat(&self, key_repeat: KeyRepeat) {
+        self.key_repeat.set(key_repeat);
+    }
+
+    fn set_pointer_coalescing(&self, coalescing: PointerCoalescing) {
+        self.pointer_coalescing.set(coalescing);
+    }
+
     fn set_control_flow(&self, control_flow: ControlFlow) {
         self.control_flow.set(control_flow)
     }
@@ -1,21 +1,23 @@
 use std::cell::{Cell, RefCell};
-use std::collections::{HashMap, VecDeque};
-use std::mem::MaybeUninit;
-use std::os::raw::{c_char, c_int, c_long, c_ulong};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem::{self, MaybeUninit};
+use std::os::raw::{c_char, c_int, c_long, c_uint, c_ulong};
 use std::slice;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use dpi::{PhysicalPosition, PhysicalSize};
 use tracing::warn;
+use winit_common::click_count::ClickCounter;
+use winit_common::event_timestamp::event_timestamp;
 use winit_common::xkb::{self, Context, XkbState};
 use winit_core::application::ApplicationHandler;
 use winit_core::event::{
-    ButtonSource, DeviceEvent, DeviceId, ElementState, FingerId, Ime, MouseButton,
-    MouseScrollDelta, PointerKind, PointerSource, RawKeyEvent, SurfaceSizeWriter, TouchPhase,
-    WindowEvent,
+    ButtonSource, DeviceEvent, DeviceId, ElementState, EventTimestamp, FingerId, Ime, Modifiers,
+    MouseButton, MouseScrollDelta, PointerKind, PointerSample, PointerSource, RawKeyEvent,
+    ScrollSource, SurfaceSizeWriter, TouchPhase, WindowEvent,
 };
 use winit_core::event_loop::DndAction;
-use winit_core::keyboard::ModifiersState;
 use winit_core::window::WindowId;
 use x11_dl::xinput2::{
     self, XIDeviceEvent, XIEnterEvent, XIFocusInEvent, XIFocusOutEvent, XIHierarchyEvent,
@@ -39,10 +41,14 @@ use crate::event_loop::{
     ALL_DEVICES, ActiveEventLoop, CookieResultExt, Device, DeviceInfo, DeviceType,
     ScrollOrientation, mkdid, mkwid,
 };
-use crate::ime::{ImeEvent, ImeEventReceiver, ImeReceiver, ImeRequest};
-use crate::util;
+use crate::ime::{
+    DBusIme, DBusImeEvent, Ime as XimIme, ImeEvent, ImeEventReceiver, ImeEventSender, ImeReceiver,
+    ImeRequest,
+};
+use crate::key_repeat::KeyRepeater;
 use crate::util::cookie::GenericEventCookie;
 use crate::window::UnownedWindow;
+use crate::{ffi, util};
 
 /// The maximum amount of X modifiers to replay.
 pub const MAX_MOD_REPLAY_LEN: usize = 32;
@@ -54,6 +60,10 @@ const KEYCODE_OFFSET: u8 = 8;
 pub struct EventProcessor {
     pub ime_receiver: ImeReceiver,
     pub ime_event_receiver: ImeEventReceiver,
+    /// The IBus or Fcitx input context, used instead of XIM when available.
+    pub dbus_ime: Option<DBusIme>,
+    /// The sender of the XIM events, kept to open XIM when the D-Bus input method is lost.
+    pub xim_event_sender: Option<ImeEventSender>,
     pub randr_event_offset: u8,
     pub devices: RefCell<HashMap<DeviceId, Device>>,
     pub xi2ext: ExtensionInformation,
@@ -67,25 +77,49 @@ pub struct EventProcessor {
     //
     // Used to detect key repeats.
     pub held_key_press: Option<u32>,
+    /// The key repeat of winit, for the key repeats other than the system one.
+    pub key_repeater: KeyRepeater,
     pub first_touch: Option<u32>,
     // Currently focused window belonging to this process
     pub active_window: Option<xproto::Window>,
     /// Latest modifiers we've sent for the user to trigger change in event.
-    pub modifiers: Cell<ModifiersState>,
+    pub modifiers: Cell<Modifiers>,
     // Track modifiers based on keycodes. NOTE: that serials generally don't work for tracking
     // since they are not unique and could be duplicated in case of sequence of key events is
     // delivered at near the same time.
     pub xfiltered_modifiers: VecDeque<u8>,
     pub xmodmap: util::ModifierKeymap,
     pub is_composing: bool,
+    /// Scale of the ongoing touchpad pinch gesture, used to compute the per-update delta.
+    pub previous_pinch_scale: f64,
+    pub click_counter: RefCell<ClickCounter>,
+    /// The samples of the pointer motion over a window, waiting for the queued motion following
+    /// them to be reported in its history.
+    pub motion_history: RefCell<Option<(xproto::Window, c_int, Vec<PointerSample>)>>,
 }
 
 impl EventProcessor {
     pub(crate) fn process_event(&mut self, xev: &mut XEvent, app: &mut dyn ApplicationHandler) {
         self.process_xevent(xev, app);
+        self.process_ime(app);
+    }
 
+    /// Handle the IME requests of the windows and send the resulting IME events.
+    pub(crate) fn process_ime(&mut self, app: &mut dyn ApplicationHandler) {
         // Handle IME requests.
         while let Ok(request) = self.ime_receiver.try_recv() {
+            if let Some(dbus_ime) = self.dbus_ime.as_mut() {
+                match dbus_ime.request(request) {
+                    Ok(Some((window, event))) => {
+                        let window_id = mkwid(window as xproto::Window);
+                        app.window_event(&self.target, window_id, WindowEvent::Ime(event));
+                    },
+                    Ok(None) => (),
+                    Err(err) => self.drop_dbus_ime(err, app),
+                }
+                continue;
+            }
+
             let ime = match self.target.ime.as_mut() {
                 Some(ime) => ime,
                 None => continue,
@@ -99,6 +133,8 @@ impl EventProcessor {
                 ImeRequest::Allow(window_id, allowed) => {
                     ime.set_ime_allowed(window_id, allowed);
                 },
+                // XIM has no notion of these.
+                ImeRequest::ContentType(..) | ImeRequest::SurroundingText(..) => (),
             }
         }
 
@@ -109,15 +145,15 @@ impl EventProcessor {
                 ImeEvent::Enabled => WindowEvent::Ime(Ime::Enabled),
                 ImeEvent::Start => {
                     self.is_composing = true;
-                    WindowEvent::Ime(Ime::Preedit("".to_owned(), None))
+                    WindowEvent::Ime(Ime::Preedit("".to_owned(), None, Vec::new()))
                 },
-                ImeEvent::Update(text, position) if self.is_composing => {
-                    WindowEvent::Ime(Ime::Preedit(text, Some((position, position))))
+                ImeEvent::Update(text, position, styles) if self.is_composing => {
+                    WindowEvent::Ime(Ime::Preedit(text, Some((position, position)), styles))
                 },
                 ImeEvent::End => {
                     self.is_composing = false;
                     // Issue empty preedit on `Done`.
-                    WindowEvent::Ime(Ime::Preedit(String::new(), None))
+                    WindowEvent::Ime(Ime::Preedit(String::new(), None, Vec::new()))
                 },
                 ImeEvent::Disabled => {
                     self.is_composing = false;
@@ -128,6 +164,91 @@ impl EventProcessor {
 
             app.window_event(&self.target, window_id, event);
         }
+
+        // Drain the D-Bus input method events.
+        let events = match self.dbus_ime.as_mut().map(DBusIme::dispatch) {
+            Some(Ok(events)) => events,
+            Some(Err(err)) => {
+                self.drop_dbus_ime(err, app);
+                return;
+            },
+            None => return,
+        };
+        for event in events {
+            match event {
+                DBusImeEvent::Ime(window, event) => {
+                    let window_id = mkwid(window as xproto::Window);
+                    app.window_event(&self.target, window_id, WindowEvent::Ime(event));
+                },
+                DBusImeEvent::Key(mut xev) => self.dbus_ime_key_input(&mut xev, app),
+            }
+        }
+    }
+
+    /// Handle a key the D-Bus input method held and didn't consume.
+    fn dbus_ime_key_input(&mut self, xev: &mut XKeyEvent, app: &mut dyn ApplicationHandler) {
+        let state = if xev.type_ == xlib::KeyPress {
+            ElementState::Pressed
+        } else {
+            ElementState::Released
+        };
+        self.xinput_key_input(xev, state, app);
+    }
+
+    /// Stop using the D-Bus input method after it failed, disabling the IME of the windows until
+    /// XIM takes over.
+    fn drop_dbus_ime(&mut self, err: winit_common::dbus::Error, app: &mut dyn ApplicationHandler) {
+        let Some(mut dbus_ime) = self.dbus_ime.take() else {
+            return;
+        };
+
+        warn!("lost the connection to the input method: {err}");
+        for window in dbus_ime.allowed_windows() {
+            let window_id = mkwid(window as xproto::Window);
+            app.window_event(&self.target, window_id, WindowEvent::Ime(Ime::Disabled));
+        }
+
+        // Fall back to XIM, like when no D-Bus input method runs, keeping the allowed windows.
+        if let Some(event_sender) = self.xim_event_sender.take() {
+            match XimIme::new(Arc::clone(&self.target.xconn), event_sender) {
+                Ok(mut ime) => {
+                    let allowed: HashSet<_> = dbus_ime.allowed_windows().collect();
+                    for window_id in self.target.windows.borrow().keys() {
+                        let window = window_id.into_raw() as XWindow;
+                        if let Err(err) = ime.create_context(window, allowed.contains(&window)) {
+                            warn!("Failed to create the input context: {err:?}");
+                        }
+                    }
+                    if let Some(window) = self.active_window {
+                        let _ = ime.focus(window as XWindow);
+                    }
+                    self.target.ime = Some(RefCell::new(ime));
+                },
+                Err(err) => warn!("Failed to open the XIM input method: {err:?}"),
+            }
+        }
+
+        // Don't lose the keys it was processing.
+        for mut xev in dbus_ime.take_pending_keys() {
+            self.dbus_ime_key_input(&mut xev, app);
+        }
+    }
+
+    /// Let the D-Bus input method process a key, returning whether it holds it until it answers.
+    fn filter_dbus_ime_key(&mut self, xev: &XKeyEvent, app: &mut dyn ApplicationHandler) -> bool {
+        let Some(dbus_ime) = self.dbus_ime.as_mut() else {
+            return false;
+        };
+
+        let keysym =
+            self.xkb_context.state_mut().map_or(0, |state| state.get_one_sym_raw(xev.keycode));
+        match dbus_ime.process_key(xev, keysym) {
+            Ok(held) => held,
+            Err(err) => {
+                self.drop_dbus_ime(err, app);
+                false
+            },
+        }
     }
 
     /// XFilterEvent tells us when an event has been discarded by the input method.
@@ -144,14 +265,24 @@ impl EventProcessor {
         }
     }
 
-    fn process_xevent(&mut self, xev: &mut XEvent, app: &mut dyn ApplicationHandler) {
-        let event_type = xev.get_type();
+    /// Track the key event of the server before the input method filters it, returning whether
+    /// it's a repeat of the server to drop since winit repeats the keys.
+    fn track_server_key(&mut self, xev: &XKeyEvent) -> bool {
+        let key_repeats =
+            self.xkb_context.keymap_mut().is_some_and(|keymap| keymap.key_repeats(xev.keycode));
+        let key_repeat = self.target.key_repeat.get();
+        self.key_repeater.server_key(key_repeat, xev, key_repeats, Instant::now())
+    }
 
+    /// Let the input method filter the key event, then handle it when it wasn't consumed.
+    fn key_input(&mut self, xev: &mut XEvent, app: &mut dyn ApplicationHandler) {
         // If we have IME disabled, don't try to `filter_event`, since only IME can consume them
         // and forward back. This is not desired for e.g. games since some IMEs may delay the input
         // and game can toggle IME back when e.g. typing into some field where latency won't really
         // matter.
-        let filtered = if event_type == xlib::KeyPress || event_type == xlib::KeyRelease {
+        let filtered = if self.dbus_ime.is_some() {
+            self.filter_dbus_ime_key(xev.as_ref(), app)
+        } else {
             let ime = self.target.ime.as_ref();
             let window = self.active_window.map(|window| window as XWindow);
             let forward_to_ime = ime
@@ -172,35 +303,54 @@ impl EventProcessor {
             }
 
             filtered
+        };
+
+        if filtered {
+            return;
+        }
+
+        // Note that in compose/pre-edit sequences, we'll always receive KeyRelease events.
+        let state = if xev.get_type() == xlib::KeyPress {
+            ElementState::Pressed
         } else {
-            self.filter_event(xev)
+            ElementState::Released
         };
+        self.xinput_key_input(xev.as_mut(), state, app);
+    }
+
+    fn process_xevent(&mut self, xev: &mut XEvent, app: &mut dyn ApplicationHandler) {
+        let event_type = xev.get_type();
+
+        if event_type == xlib::KeyPress || event_type == xlib::KeyRelease {
+            if !self.track_server_key(xev.as_ref()) {
+                self.key_input(xev, app);
+            }
+            return;
+        }
 
         // Don't process event if it was filtered.
-        if filtered {
+        if self.filter_event(xev) {
             return;
         }
 
         match event_type {
             xlib::ClientMessage => self.client_message(xev.as_ref(), app),
             xlib::SelectionNotify => self.selection_notify(xev.as_ref(), app),
-            xlib::ConfigureNotify => self.configure_notify(xev.as_ref(), app),
+            xlib::ConfigureNotify => {
+                let xev: &XConfigureEvent = xev.as_ref();
+                self.configure_notify(xev, app);
+                if let Some(Err(err)) =
+                    self.dbus_ime.as_mut().map(|ime| ime.window_moved(xev.window))
+                {
+                    self.drop_dbus_ime(err, app);
+                }
+            },
             xlib::ReparentNotify => self.reparent_notify(xev.as_ref()),
             xlib::MapNotify => self.map_notify(xev.as_ref(), app),
             xlib::DestroyNotify => self.destroy_notify(xev.as_ref(), app),
             xlib::PropertyNotify => self.property_notify(xev.as_ref(), app),
             xlib::VisibilityNotify => self.visibility_notify(xev.as_ref(), app),
             xlib::Expose => self.expose(xev.as_ref()),
-            // Note that in compose/pre-edit sequences, we'll always receive KeyRelease events.
-            ty @ xlib::KeyPress | ty @ xlib::KeyRelease => {
-                let state = if ty == xlib::KeyPress {
-                    ElementState::Pressed
-                } else {
-                    ElementState::Released
-                };
-
-                self.xinput_key_input(xev.as_mut(), state, app);
-            },
             xlib::GenericEvent => {
                 let xev: GenericEventCookie =
                     match GenericEventCookie::from_event(self.target.xconn.clone(), *xev) {
@@ -210,6 +360,11 @@ impl EventProcessor {
 
                 let evtype = xev.evtype();
 
+                // Report the pointer motion before anything else happens to the pointer.
+                if evtype != xinput2::XI_Motion {
+                    self.flush_motion_history(app);
+                }
+
                 match evtype {
                     ty @ xinput2::XI_ButtonPress | ty @ xinput2::XI_ButtonRelease => {
                         let state = if ty == xinput2::XI_ButtonPress {
@@ -277,6 +432,20 @@ impl EventProcessor {
                         let xev: &XIHierarchyEvent = unsafe { xev.as_event() };
                         self.xinput2_hierarchy_changed(xev);
                     },
+                    ffi::XI_GesturePinchBegin
+                    | ffi::XI_GesturePinchUpdate
+                    | ffi::XI_GesturePinchEnd => {
+                        let xev: &ffi::XIGesturePinchEvent = unsafe { xev.as_event() };
+                        self.update_mods_from_xinput2_event(&xev.mods, &xev.group, false, app);
+                        self.xinput2_gesture_pinch(xev, app);
+                    },
+                    ffi::XI_GestureSwipeBegin
+                    | ffi::XI_GestureSwipeUpdate
+                    | ffi::XI_GestureSwipeEnd => {
+                        let xev: &ffi::XIGestureSwipeEvent = unsafe { xev.as_event() };
+                        self.update_mods_from_xinput2_event(&xev.mods, &xev.group, false, app);
+                        self.xinput2_gesture_swipe(xev, app);
+                    },
                     _ => {},
                 }
             },
@@ -369,6 +538,14 @@ impl EventProcessor {
         let window = xev.window as xproto::Window;
         let window_id = mkwid(window);
 
+        // A new XSettings manager took over.
+        if xev.message_type as xproto::Atom == atoms[MANAGER]
+            && Some(xev.data.get_long(1) as xproto::Atom) == self.target.xconn.xsettings_screen()
+        {
+            self.process_xsettings_change(app);
+            return;
+        }
+
         if xev.data.get_long(0) as xproto::Atom == self.target.wm_delete_window {
             app.window_event(&self.target, window_id, WindowEvent::CloseRequested);
             return;
@@ -401,6 +578,26 @@ impl EventProcessor {
             return;
         }
 
+        if xev.message_type as xproto::Atom == atoms[_NET_WM_FRAME_DRAWN] {
+            let value =
+                (xev.data.get_long(0) as u32 as u64) | (xev.data.get_long(1) as u32 as u64) << 32;
+            let mut frames_pending = self.target.frames_pending.borrow_mut();
+            if frames_pending.get(&window_id).is_some_and(|&(_, pending)| pending <= value) {
+                frames_pending.remove(&window_id);
+            }
+            return;
+        }
+
+        // The window manager uses the extended counter, see `UnownedWindow::frame_sync_request`.
+        if xev.data.get_long(0) as xproto::Atom == self.target.net_wm_sync_request
+            && xev.data.get_long(4) != 0
+        {
+            let value =
+                (xev.data.get_long(2) as u32 as u64) | (xev.data.get_long(3) as u32 as u64) << 32;
+            self.with_window(window, |window| window.frame_sync_request(value));
+            return;
+        }
+
         if xev.data.get_long(0) as xproto::Atom == self.target.net_wm_sync_request {
             let sync_counter_id = match self
                 .with_window(xev.window as xproto::Window, |window| window.sync_counter_id())
@@ -485,11 +682,13 @@ impl EventProcessor {
             let x = (packed_coordinates >> 16) as i16;
             let y = (packed_coordinates & 0xffff) as i16;
 
-            let coords = self
-                .target
-                .xconn
-                .translate_coords(self.target.root, window, x, y)
-                .expect("Failed to translate window coordinates");
+            let coords = match self.target.xconn.translate_coords(self.target.root, window, x, y) {
+                Ok(coords) => coords,
+                Err(err) => {
+                    warn!("Failed to translate window coordinates: {err}");
+                    return;
+                },
+            };
 
             // Cautiously limit the scope of the `dnd` lock so we don't rely on `app.window_event`
             // never contending the lock.
@@ -511,13 +710,9 @@ impl EventProcessor {
                 // Log this timestamp.
                 self.target.xconn.set_timestamp(time);
 
-                unsafe {
-                    dnd.send_status(
-                        window,
-                        source_window,
-                        if state.accepted { DndState::Accepted } else { DndState::Rejected },
-                    )
-                    .expect("Failed to send `XdndStatus` message.");
+                let status = if state.accepted { DndState::Accepted } else { DndState::Rejected };
+                if let Err(err) = unsafe { dnd.send_status(window, source_window, status) } {
+                    warn!("Failed to send `XdndStatus` message: {err}");
                 }
 
                 state.transfer_id
@@ -572,9 +767,8 @@ impl EventProcessor {
             {
                 state.finished = Some((window, source_window));
             } else {
-                unsafe {
-                    dnd.send_finished(window, source_window)
-                        .expect("Failed to send `XdndFinished` message.");
+                if let Err(err) = unsafe { dnd.send_finished(window, source_window) } {
+                    warn!("Failed to send `XdndFinished` message: {err}");
                 }
             }
 
@@ -684,9 +878,8 @@ impl EventProcessor {
         } else if let Some((this_window, target_window)) =
             dnd.state().and_then(|state| state.finished)
         {
-            unsafe {
-                dnd.send_finished(this_window, target_window)
-                    .expect("Failed to send `XdndFinished` message.");
+            if let Err(err) = unsafe { dnd.send_finished(this_window, target_window) } {
+                warn!("Failed to send `XdndFinished` message: {err}");
             }
         }
     }
@@ -775,18 +968,17 @@ impl EventProcessor {
             let last_scale_factor = shared_state_lock.last_monitor.scale_factor;
             let new_scale_factor = {
                 let window_rect = util::AaRect::new(new_outer_position, new_surface_size);
-                let monitor = self
-                    .target
-                    .xconn
-                    .get_monitor_for_window(Some(window_rect))
-                    .expect("Failed to find monitor for window");
-
-                if monitor.is_dummy() {
+                match self.target.xconn.get_monitor_for_window(Some(window_rect)) {
                     // Avoid updating monitor using a dummy monitor handle
-                    last_scale_factor
-                } else {
-                    shared_state_lock.last_monitor = monitor.clone();
-                    monitor.scale_factor
+                    Ok(monitor) if monitor.is_dummy() => last_scale_factor,
+                    Ok(monitor) => {
+                        shared_state_lock.last_monitor = monitor.clone();
+                        monitor.scale_factor
+                    },
+                    Err(err) => {
+                        warn!("Failed to find monitor for window: {err}");
+                        last_scale_factor
+                    },
                 }
             };
             if last_scale_factor != new_scale_factor {
@@ -828,9 +1020,9 @@ impl EventProcessor {
 
         // NOTE: Ensure that the lock is dropped before handling the resized and
         // sending the event back to user.
-        let hittest = {
+        let input_region = {
             let mut shared_state_lock = window.shared_state_lock();
-            let hittest = shared_state_lock.cursor_hittest;
+            let input_region = shared_state_lock.input_region.clone();
 
             // This is a hack to ensure that the DPI adjusted resize is actually
             // applied on all WMs. KWin doesn't need this, but Xfwm does. The hack
@@ -849,12 +1041,12 @@ impl EventProcessor {
                 }
             }
 
-            hittest
+            input_region
         };
 
-        // Reload hittest.
-        if hittest.unwrap_or(false) {
-            let _ = window.set_cursor_hittest(true);
+        // Reload the input region, its rectangles may be in logical coordinates.
+        if let Some(input_region) = input_region {
+            let _ = window.set_input_region(Some(&input_region));
         }
 
         if resized {
@@ -889,20 +1081,30 @@ impl EventProcessor {
         app.window_event(&self.target, window_id, WindowEvent::Focused(focus));
     }
 
-    fn destroy_notify(&self, xev: &XDestroyWindowEvent, app: &mut dyn ApplicationHandler) {
+    fn destroy_notify(&mut self, xev: &XDestroyWindowEvent, app: &mut dyn ApplicationHandler) {
         let window = xev.window as xproto::Window;
         let window_id = mkwid(window);
 
+        // The XSettings manager went away.
+        if Some(window) == self.target.xconn.xsettings_owner() {
+            self.process_xsettings_change(app);
+            return;
+        }
+
         // In the event that the window's been destroyed without being dropped first, we
         // cleanup again here.
         self.target.windows.borrow_mut().remove(&WindowId::from_raw(window as _));
 
         // Since all XIM stuff needs to happen from the same thread, we destroy the input
         // context here instead of when dropping the window.
-        if let Some(ime) = self.target.ime.as_ref() {
-            ime.borrow_mut()
-                .remove_context(window as XWindow)
-                .expect("Failed to destroy input context");
+        if let Some(Err(err)) =
+            self.target.ime.as_ref().map(|ime| ime.borrow_mut().remove_context(window as XWindow))
+        {
+            warn!("Failed to destroy input context: {err}");
+        }
+
+        if let Some(dbus_ime) = self.dbus_ime.as_mut() {
+            dbus_ime.remove_window(window as XWindow);
         }
 
         app.window_event(&self.target, window_id, WindowEvent::Destroyed);
@@ -912,10 +1114,22 @@ impl EventProcessor {
         let atoms = self.target.x_connection().atoms();
         let atom = xev.atom as xproto::Atom;
 
-        if atom == xproto::Atom::from(xproto::AtomEnum::RESOURCE_MANAGER)
-            || atom == atoms[_XSETTINGS_SETTINGS]
-        {
+        if atom == xproto::Atom::from(xproto::AtomEnum::RESOURCE_MANAGER) {
             self.process_dpi_change(app);
+        } else if atom == atoms[_XSETTINGS_SETTINGS] {
+            self.process_xsettings_change(app);
+        } else if atom == atoms[_NET_WM_STATE] {
+            let xwindow = xev.window as xproto::Window;
+            let states = self.with_window(xwindow, |window| {
+                let states = window.states();
+                let mut shared_state_lock = window.shared_state_lock();
+                (mem::replace(&mut shared_state_lock.states, states) != states).then_some(states)
+            });
+
+            if let Some(Some(states)) = states {
+                let event = WindowEvent::StateChanged(states);
+                app.window_event(&self.target, mkwid(xwindow), event);
+            }
         }
     }
 
@@ -1015,12 +1229,8 @@ impl EventProcessor {
                 self.send_synthic_modifier_from_core(window_id, xev.state as u16, app);
             }
 
-            if let Some(mut key_processor) = self.xkb_context.key_context() {
-                let event = key_processor.process_key_event(keycode, state, repeat);
-                let event =
-                    WindowEvent::KeyboardInput { device_id: None, event, is_synthetic: false };
-                app.window_event(&self.target, window_id, event);
-            }
+            let timestamp = Some(event_timestamp(xev.time as xproto::Timestamp));
+            self.send_key_event(window_id, keycode, state, repeat, timestamp, app);
 
             // Restore the client's modifiers state after replay.
             if replay {
@@ -1035,7 +1245,7 @@ impl EventProcessor {
         {
             let written = self.target.xconn.lookup_utf8(ic, xev);
             if !written.is_empty() {
-                let event = WindowEvent::Ime(Ime::Preedit(String::new(), None));
+                let event = WindowEvent::Ime(Ime::Preedit(String::new(), None, Vec::new()));
                 app.window_event(&self.target, window_id, event);
 
                 let event = WindowEvent::Ime(Ime::Commit(written));
@@ -1045,6 +1255,57 @@ impl EventProcessor {
         }
     }
 
+    /// Send the key event of a key, preceded by the progress of the compose sequence.
+    fn send_key_event(
+        &mut self,
+        window_id: WindowId,
+        keycode: u32,
+        state: ElementState,
+        repeat: bool,
+        timestamp: Option<EventTimestamp>,
+        app: &mut dyn ApplicationHandler,
+    ) {
+        let event = self
+            .xkb_context
+            .key_context()
+            .map(|mut key_processor| key_processor.process_key_event(keycode, state, repeat));
+        if let Some(event) = event {
+            if let Some(compose) = self.xkb_context.take_compose() {
+                app.window_event(&self.target, window_id, WindowEvent::Compose(compose));
+            }
+
+            let event = WindowEvent::KeyboardInput {
+                device_id: None,
+                event,
+                is_synthetic: false,
+                timestamp,
+            };
+            app.window_event(&self.target, window_id, event);
+        }
+    }
+
+    /// Repeat the held key once its time came, for a custom key repeat.
+    pub(crate) fn repeat_key(&mut self, app: &mut dyn ApplicationHandler) {
+        let key_repeat = self.target.key_repeat.get();
+        let Some(mut xev) = self.key_repeater.next_repeat(key_repeat, Instant::now()) else {
+            return;
+        };
+
+        // The modifiers may have changed since the key was pressed.
+        xev.state = self.core_state_from_xkb();
+        self.key_input(&mut XEvent::from(xev), app);
+    }
+
+    /// When to stop waiting for the D-Bus input method to process the held keys.
+    pub(crate) fn ime_key_deadline(&self) -> Option<Instant> {
+        self.dbus_ime.as_ref().and_then(DBusIme::key_deadline)
+    }
+
+    /// When the held key is next repeated by winit.
+    pub(crate) fn key_repeat_deadline(&self) -> Option<Instant> {
+        self.key_repeater.deadline()
+    }
+
     fn send_synthic_modifier_from_core(
         &mut self,
         window_id: winit_core::window::WindowId,
@@ -1065,11 +1326,16 @@ impl EventProcessor {
             None => return,
         };
 
+        // The core state doesn't tell locked modifiers apart, so report the lock ones as locked.
+        let mods_indices = keymap.mods_indices();
+        let lock_mask = [mods_indices.caps, mods_indices.num]
+            .into_iter()
+            .flatten()
+            .fold(0, |lock_mask, index| lock_mask | 1 << index);
         let mask = self.xkb_mod_mask_from_core(state);
-        xkb_state.update_modifiers(mask, 0, 0, 0, 0, Self::core_keyboard_group(state));
-        let mods: ModifiersState = xkb_state.modifiers().into();
-
-        let event = WindowEvent::ModifiersChanged(mods.into());
+        let group = Self::core_keyboard_group(state);
+        xkb_state.update_modifiers(mask & !lock_mask, 0, mask & lock_mask, 0, 0, group);
+        let event = WindowEvent::ModifiersChanged(xkb_state.modifiers().into());
         app.window_event(&self.target, window_id, event);
     }
 
@@ -1100,8 +1366,9 @@ impl EventProcessor {
         }
 
         let position = PhysicalPosition::new(event.event_x, event.event_y);
+        let timestamp = Some(event_timestamp(event.time as xproto::Timestamp));
 
-        let event = match event.detail as u32 {
+        let mut event = match event.detail as u32 {
             xlib::Button1 => WindowEvent::PointerButton {
                 device_id,
                 primary: true,
@@ -1109,6 +1376,8 @@ impl EventProcessor {
                 position,
                 button: MouseButton::Left.into(),
                 is_macos_activation_click: false,
+                timestamp,
+                click_count: 1,
             },
             xlib::Button2 => WindowEvent::PointerButton {
                 device_id,
@@ -1117,6 +1386,8 @@ impl EventProcessor {
                 position,
                 button: MouseButton::Middle.into(),
                 is_macos_activation_click: false,
+                timestamp,
+                click_count: 1,
             },
             xlib::Button3 => WindowEvent::PointerButton {
                 device_id,
@@ -1125,6 +1396,8 @@ impl EventProcessor {
                 position,
                 button: MouseButton::Right.into(),
                 is_macos_activation_click: false,
+                timestamp,
+                click_count: 1,
             },
 
             // Suppress emulated scroll wheel clicks, since we handle the real motion events for
@@ -1132,16 +1405,25 @@ impl EventProcessor {
             // evdev (and XInput2 in turn) as axis motion, so we don't otherwise
             // special-case these button presses.
             4..=7 => match state {
-                ElementState::Pressed => WindowEvent::MouseWheel {
-                    device_id,
-                    delta: match event.detail {
-                        4 => MouseScrollDelta::LineDelta(0.0, 1.0),
-                        5 => MouseScrollDelta::LineDelta(0.0, -1.0),
-                        6 => MouseScrollDelta::LineDelta(1.0, 0.0),
-                        7 => MouseScrollDelta::LineDelta(-1.0, 0.0),
+                ElementState::Pressed => {
+                    let (x, y) = match event.detail {
+                        4 => (0, 1),
+                        5 => (0, -1),
+                        6 => (1, 0),
+                        7 => (-1, 0),
                         _ => unreachable!(),
-                    },
-                    phase: TouchPhase::Moved,
+                    };
+                    // Buttons 6 and 7 are produced by tilting the wheel.
+                    let source = if x == 0 { ScrollSource::Wheel } else { ScrollSource::WheelTilt };
+                    WindowEvent::MouseWheel {
+                        device_id,
+                        delta: MouseScrollDelta::LineDelta(x as f32, y as f32),
+                        phase: TouchPhase::Moved,
+                        timestamp,
+                        source,
+                        value120: Some((x * 120, y * 120)),
+                        stopped: false,
+                    }
                 },
                 ElementState::Released => return,
             },
@@ -1155,6 +1437,8 @@ impl EventProcessor {
                 // 255 is the largest code yielded on X11 (tested).
                 button: MouseButton::try_from_u8((x - 5) as u8).unwrap().into(),
                 is_macos_activation_click: false,
+                timestamp,
+                click_count: 1,
             },
             x @ 37..=0xff => WindowEvent::PointerButton {
                 device_id,
@@ -1164,13 +1448,22 @@ impl EventProcessor {
                 // 255 is the largest code yielded on X11 (tested).
                 button: ButtonSource::Unknown(x as u16),
                 is_macos_activation_click: false,
+                timestamp,
+                click_count: 1,
             },
             _ => return,
         };
 
+        self.count_click(window_id, &mut event);
         app.window_event(&self.target, window_id, event);
     }
 
+    /// Set the `click_count` of a button event from the double click settings.
+    fn count_click(&self, window_id: WindowId, event: &mut WindowEvent) {
+        let settings = self.target.xconn.xsettings().system_settings();
+        self.click_counter.borrow_mut().button(window_id, event, &settings);
+    }
+
     fn xinput2_mouse_motion(&self, event: &XIDeviceEvent, app: &mut dyn ApplicationHandler) {
         // Set the timestamp.
         self.target.xconn.set_timestamp(event.time as xproto::Timestamp);
@@ -1188,26 +1481,103 @@ impl EventProcessor {
         let window = event.event as xproto::Window;
         let window_id = mkwid(window);
         let new_cursor_pos = (event.event_x, event.event_y);
+        let timestamp = Some(event_timestamp(event.time as xproto::Timestamp));
 
         let cursor_moved = self.with_window(window, |window| {
             let mut shared_state_lock = window.shared_state_lock();
             util::maybe_change(&mut shared_state_lock.cursor_pos, new_cursor_pos)
         });
+        let Some(cursor_moved) = cursor_moved else {
+            return;
+        };
 
-        if cursor_moved == Some(true) {
+        let events = self.xinput2_scroll_events(event, device_id, timestamp);
+
+        // The samples of another pointer or window are reported first.
+        let other_pointer =
+            self.motion_history.borrow().as_ref().is_some_and(|&(history_window, deviceid, _)| {
+                history_window != window || deviceid != event.deviceid
+            });
+        if other_pointer {
+            self.flush_motion_history(app);
+        }
+
+        let mut history =
+            self.motion_history.take().map(|(_, _, history)| history).unwrap_or_default();
+        if cursor_moved {
             let position = PhysicalPosition::new(event.event_x, event.event_y);
+            history.push(PointerSample { position, source: PointerSource::Mouse, timestamp });
+        }
+        self.motion_history.replace(Some((window, event.deviceid, history)));
 
-            let event = WindowEvent::PointerMoved {
-                device_id,
-                primary: true,
-                position,
-                source: PointerSource::Mouse,
-            };
+        // The samples are kept for the next motion of the same pointer over the same window.
+        if events.is_empty() && self.next_is_motion(event) {
+            return;
+        }
+
+        // Report the samples before scrolling.
+        self.flush_motion_history(app);
+        for event in events {
             app.window_event(&self.target, window_id, event);
-        } else if cursor_moved.is_none() {
+        }
+    }
+
+    /// Report the samples of the pointer motion kept for the next motion, at the latest of them.
+    fn flush_motion_history(&self, app: &mut dyn ApplicationHandler) {
+        let Some((window, deviceid, mut history)) = self.motion_history.take() else {
             return;
+        };
+        let Some(sample) = history.pop() else {
+            return;
+        };
+
+        let window_id = mkwid(window);
+        let event = WindowEvent::PointerMoved {
+            device_id: Some(mkdid(deviceid as xinput::DeviceId)),
+            primary: true,
+            position: sample.position,
+            source: sample.source,
+            timestamp: sample.timestamp,
+            history,
+        };
+        self.click_counter.borrow_mut().moved(window_id, &event);
+        app.window_event(&self.target, window_id, event);
+    }
+
+    /// Whether the next queued event is a motion of the same pointer over the same window.
+    fn next_is_motion(&self, event: &XIDeviceEvent) -> bool {
+        let xconn = &self.target.xconn;
+        if unsafe { (xconn.xlib.XEventsQueued)(xconn.display, ffi::QueuedAlready) } == 0 {
+            return false;
+        }
+
+        // The data of a peeked event is copied, it can be retrieved without dequeuing it.
+        let mut next = MaybeUninit::uninit();
+        let next = unsafe {
+            (xconn.xlib.XPeekEvent)(xconn.display, next.as_mut_ptr());
+            next.assume_init()
+        };
+        match GenericEventCookie::from_event(xconn.clone(), next) {
+            Some(next)
+                if next.extension() == self.xi2ext.major_opcode
+                    && next.evtype() == xinput2::XI_Motion =>
+            {
+                let next: &XIDeviceEvent = unsafe { next.as_event() };
+                next.deviceid == event.deviceid
+                    && next.sourceid == event.sourceid
+                    && next.event == event.event
+            },
+            _ => false,
         }
+    }
 
+    /// The scroll events of the valuators of a motion event.
+    fn xinput2_scroll_events(
+        &self,
+        event: &XIDeviceEvent,
+        device_id: Option<DeviceId>,
+        timestamp: Option<EventTimestamp>,
+    ) -> Vec<WindowEvent> {
         // More gymnastics, for self.devices
         let mask = unsafe {
             slice::from_raw_parts(event.valuators.mask, event.valuators.mask_len as usize)
@@ -1215,7 +1585,7 @@ impl EventProcessor {
         let mut devices = self.devices.borrow_mut();
         let physical_device = match devices.get_mut(&mkdid(event.sourceid as xinput::DeviceId)) {
             Some(device) => device,
-            None => return,
+            None => return Vec::new(),
         };
 
         let mut events = Vec::new();
@@ -1233,23 +1603,32 @@ impl EventProcessor {
                 let delta = (x - info.position) / info.increment;
                 info.position = x;
                 // X11 vertical scroll coordinates are opposite to winit's
-                let delta = match info.orientation {
+                let value120 = (-delta * 120.).round() as i32;
+                let (delta, value120) = match info.orientation {
                     ScrollOrientation::Horizontal => {
-                        MouseScrollDelta::LineDelta(-delta as f32, 0.0)
+                        (MouseScrollDelta::LineDelta(-delta as f32, 0.0), (value120, 0))
+                    },
+                    ScrollOrientation::Vertical => {
+                        (MouseScrollDelta::LineDelta(0.0, -delta as f32), (0, value120))
                     },
-                    ScrollOrientation::Vertical => MouseScrollDelta::LineDelta(0.0, -delta as f32),
                 };
 
-                let event = WindowEvent::MouseWheel { device_id, delta, phase: TouchPhase::Moved };
+                let event = WindowEvent::MouseWheel {
+                    device_id,
+                    delta,
+                    phase: TouchPhase::Moved,
+                    timestamp,
+                    source: ScrollSource::Unknown,
+                    value120: Some(value120),
+                    stopped: false,
+                };
                 events.push(event);
             }
 
             value = unsafe { value.offset(1) };
         }
 
-        for event in events {
-            app.window_event(&self.target, window_id, event);
-        }
+        events
     }
 
     fn xinput2_mouse_enter(&self, event: &XIEnterEvent, app: &mut dyn ApplicationHandler) {
@@ -1317,8 +1696,14 @@ impl EventProcessor {
         // Set the timestamp.
         self.target.xconn.set_timestamp(xev.time as xproto::Timestamp);
 
-        if let Some(ime) = self.target.ime.as_ref() {
-            ime.borrow_mut().focus(xev.event).expect("Failed to focus input context");
+        if let Some(Err(err)) =
+            self.target.ime.as_ref().map(|ime| ime.borrow_mut().focus(xev.event))
+        {
+            warn!("Failed to focus input context: {err}");
+        }
+
+        if let Some(Err(err)) = self.dbus_ime.as_mut().map(|ime| ime.focus(xev.event)) {
+            self.drop_dbus_ime(err, app);
         }
 
         if self.active_window == Some(window) {
@@ -1362,6 +1747,8 @@ impl EventProcessor {
             primary: true,
             position,
             source: PointerSource::Mouse,
+            timestamp: None,
+            history: Vec::new(),
         };
         app.window_event(&self.target, window_id, event);
     }
@@ -1376,8 +1763,14 @@ impl EventProcessor {
             return;
         }
 
-        if let Some(ime) = self.target.ime.as_ref() {
-            ime.borrow_mut().unfocus(xev.event).expect("Failed to unfocus input context");
+        if let Some(Err(err)) =
+            self.target.ime.as_ref().map(|ime| ime.borrow_mut().unfocus(xev.event))
+        {
+            warn!("Failed to unfocus input context: {err}");
+        }
+
+        if let Some(Err(err)) = self.dbus_ime.as_mut().map(|ime| ime.unfocus(xev.event)) {
+            self.drop_dbus_ime(err, app);
         }
 
         if self.active_window.take() == Some(window) {
@@ -1404,6 +1797,7 @@ impl EventProcessor {
             // Clear this so detecting key repeats is consistently handled when the
             // window regains focus.
             self.held_key_press = None;
+            self.key_repeater.clear();
 
             if let Some(window) = self.with_window(window, Arc::clone) {
                 window.shared_state_lock().has_focus = false;
@@ -1422,6 +1816,7 @@ impl EventProcessor {
             let window_id = mkwid(window);
             let id = xev.detail as u32;
             let position = PhysicalPosition::new(xev.event_x, xev.event_y);
+            let timestamp = Some(event_timestamp(xev.time as xproto::Timestamp));
 
             // Mouse cursor position changes when touch events are received.
             // Only the first concurrently active touch ID moves the mouse cursor.
@@ -1433,6 +1828,8 @@ impl EventProcessor {
                     primary: true,
                     position: position.cast(),
                     source: PointerSource::Mouse,
+                    timestamp,
+                    history: Vec::new(),
                 };
                 app.window_event(&self.target, window_id, event);
             }
@@ -1449,14 +1846,17 @@ impl EventProcessor {
                         kind: PointerKind::Touch(finger_id),
                     };
                     app.window_event(&self.target, window_id, event);
-                    let event = WindowEvent::PointerButton {
+                    let mut event = WindowEvent::PointerButton {
                         device_id,
                         primary: is_first_touch,
                         state: ElementState::Pressed,
                         position,
                         button: ButtonSource::Touch { finger_id, force: None },
                         is_macos_activation_click: false,
+                        timestamp,
+                        click_count: 1,
                     };
+                    self.count_click(window_id, &mut event);
                     app.window_event(&self.target, window_id, event);
                 },
                 xinput2::XI_TouchUpdate => {
@@ -1465,18 +1865,24 @@ impl EventProcessor {
                         primary: is_first_touch,
                         position,
                         source: PointerSource::Touch { finger_id, force: None },
+                        timestamp,
+                        history: Vec::new(),
                     };
+                    self.click_counter.borrow_mut().moved(window_id, &event);
                     app.window_event(&self.target, window_id, event);
                 },
                 xinput2::XI_TouchEnd => {
-                    let event = WindowEvent::PointerButton {
+                    let mut event = WindowEvent::PointerButton {
                         device_id,
                         primary: is_first_touch,
                         state: ElementState::Released,
                         position,
                         button: ButtonSource::Touch { finger_id, force: None },
                         is_macos_activation_click: false,
+                        timestamp,
+                        click_count: 1,
                     };
+                    self.count_click(window_id, &mut event);
                     app.window_event(&self.target, window_id, event);
                     let event = WindowEvent::PointerLeft {
                         device_id,
@@ -1491,6 +1897,101 @@ impl EventProcessor {
         }
     }
 
+    fn xinput2_gesture_pinch(
+        &mut self,
+        xev: &ffi::XIGesturePinchEvent,
+        app: &mut dyn ApplicationHandler,
+    ) {
+        // Set the timestamp.
+        self.target.xconn.set_timestamp(xev.time as xproto::Timestamp);
+
+        // We only support two fingers for now.
+        if xev.detail != 2 {
+            return;
+        }
+
+        let window = xev.event as xproto::Window;
+        if !self.window_exists(window) {
+            return;
+        }
+
+        let window_id = mkwid(window);
+        let device_id = Some(mkdid(xev.deviceid as xinput::DeviceId));
+
+        let (phase, pan_delta, pinch_delta, rotation_delta) = match xev.evtype {
+            ffi::XI_GesturePinchBegin => {
+                self.previous_pinch_scale = xev.scale;
+                (TouchPhase::Started, PhysicalPosition::new(0., 0.), 0., 0.)
+            },
+            ffi::XI_GesturePinchUpdate => {
+                let pan_delta = PhysicalPosition::new(xev.delta_x as f32, xev.delta_y as f32);
+                let pinch_delta = xev.scale - self.previous_pinch_scale;
+                self.previous_pinch_scale = xev.scale;
+                // X11 provides rotation in degrees cw, opposite of winit's degrees ccw.
+                let rotation_delta = -xev.delta_angle as f32;
+                (TouchPhase::Moved, pan_delta, pinch_delta, rotation_delta)
+            },
+            ffi::XI_GesturePinchEnd => {
+                self.previous_pinch_scale = 1.0;
+                let phase = if xev.flags & ffi::XIGesturePinchEventCancelled == 0 {
+                    TouchPhase::Ended
+                } else {
+                    TouchPhase::Cancelled
+                };
+                (phase, PhysicalPosition::new(0., 0.), 0., 0.)
+            },
+            _ => unreachable!(),
+        };
+
+        // The chance of only one of these events being necessary is extremely small,
+        // so it is easier to just send all three.
+        let timestamp = Some(event_timestamp(xev.time as xproto::Timestamp));
+        let event = WindowEvent::PanGesture { device_id, delta: pan_delta, phase, timestamp };
+        app.window_event(&self.target, window_id, event);
+        let event = WindowEvent::PinchGesture { device_id, delta: pinch_delta, phase, timestamp };
+        app.window_event(&self.target, window_id, event);
+        let event =
+            WindowEvent::RotationGesture { device_id, delta: rotation_delta, phase, timestamp };
+        app.window_event(&self.target, window_id, event);
+    }
+
+    fn xinput2_gesture_swipe(
+        &self,
+        xev: &ffi::XIGestureSwipeEvent,
+        app: &mut dyn ApplicationHandler,
+    ) {
+        // Set the timestamp.
+        self.target.xconn.set_timestamp(xev.time as xproto::Timestamp);
+
+        let window = xev.event as xproto::Window;
+        if !self.window_exists(window) {
+            return;
+        }
+
+        let window_id = mkwid(window);
+        let device_id = Some(mkdid(xev.deviceid as xinput::DeviceId));
+
+        let (phase, delta) = match xev.evtype {
+            ffi::XI_GestureSwipeBegin => (TouchPhase::Started, PhysicalPosition::new(0., 0.)),
+            ffi::XI_GestureSwipeUpdate => {
+                (TouchPhase::Moved, PhysicalPosition::new(xev.delta_x as f32, xev.delta_y as f32))
+            },
+            ffi::XI_GestureSwipeEnd => {
+                let phase = if xev.flags & ffi::XIGestureSwipeEventCancelled == 0 {
+                    TouchPhase::Ended
+                } else {
+                    TouchPhase::Cancelled
+                };
+                (phase, PhysicalPosition::new(0., 0.))
+            },
+            _ => unreachable!(),
+        };
+
+        let timestamp = Some(event_timestamp(xev.time as xproto::Timestamp));
+        let event = WindowEvent::PanGesture { device_id, delta, phase, timestamp };
+        app.window_event(&self.target, window_id, event);
+    }
+
     fn xinput2_raw_button_input(
         &self,
         xev: &XIRawEvent,
@@ -1610,6 +2111,7 @@ impl EventProcessor {
                     let xcb = self.target.xconn.xcb_connection().get_raw_xcb_connection();
                     self.xkb_context.set_keymap_from_x11(xcb);
                     self.xmodmap.reload_from_x_connection(&self.target.xconn);
+                    self.update_keyboard_layouts(true, app);
 
                     let window_id = match self.active_window.map(mkwid) {
                         Some(window_id) => window_id,
@@ -1626,6 +2128,8 @@ impl EventProcessor {
                 let xcb = self.target.xconn.xcb_connection().get_raw_xcb_connection();
                 self.xkb_context.set_keymap_from_x11(xcb);
                 self.xmodmap.reload_from_x_connection(&self.target.xconn);
+                self.update_keyboard_layouts(true, app);
+
                 let window_id = match self.active_window.map(mkwid) {
                     Some(window_id) => window_id,
                     None => return,
@@ -1636,30 +2140,44 @@ impl EventProcessor {
                     self.send_modifiers(window_id, mods, true, app);
                 }
             },
+            xlib::XkbControlsNotify => {
+                let xev = unsafe { &*(xev as *const _ as *const ffi::XkbControlsNotifyEvent) };
+
+                // Only the repeat controls are part of the system settings.
+                let repeat_changed = util::has_flag(xev.changed_ctrls, ffi::XkbRepeatKeysMask)
+                    || util::has_flag(xev.enabled_ctrl_changes, ffi::XkbRepeatKeysMask);
+                if xev.device == self.xkb_context.core_keyboard_id && repeat_changed {
+                    app.system_settings_changed(&self.target);
+                }
+            },
             xlib::XkbStateNotify => {
                 let xev = unsafe { &*(xev as *const _ as *const xlib::XkbStateNotifyEvent) };
 
                 // Set the timestamp.
                 self.target.xconn.set_timestamp(xev.time as xproto::Timestamp);
 
-                if let Some(state) = self.xkb_context.state_mut() {
-                    state.update_modifiers(
-                        xev.base_mods,
-                        xev.latched_mods,
-                        xev.locked_mods,
-                        xev.base_group as u32,
-                        xev.latched_group as u32,
-                        xev.locked_group as u32,
-                    );
+                let Some(state) = self.xkb_context.state_mut() else {
+                    return;
+                };
 
-                    let window_id = match self.active_window.map(mkwid) {
-                        Some(window_id) => window_id,
-                        None => return,
-                    };
+                state.update_modifiers(
+                    xev.base_mods,
+                    xev.latched_mods,
+                    xev.locked_mods,
+                    xev.base_group as u32,
+                    xev.latched_group as u32,
+                    xev.locked_group as u32,
+                );
+                let mods = state.modifiers().into();
 
-                    let mods = state.modifiers().into();
-                    self.send_modifiers(window_id, mods, true, app);
-                }
+                self.update_keyboard_layouts(false, app);
+
+                let window_id = match self.active_window.map(mkwid) {
+                    Some(window_id) => window_id,
+                    None => return,
+                };
+
+                self.send_modifiers(window_id, mods, true, app);
             },
             _ => {},
         }
@@ -1672,25 +2190,52 @@ impl EventProcessor {
         force: bool,
         app: &mut dyn ApplicationHandler,
     ) {
-        if let Some(state) = self.xkb_context.state_mut() {
-            state.update_modifiers(
-                mods.base as u32,
-                mods.latched as u32,
-                mods.locked as u32,
-                group.base as u32,
-                group.latched as u32,
-                group.locked as u32,
-            );
+        let Some(state) = self.xkb_context.state_mut() else {
+            return;
+        };
 
-            // NOTE: we use active window since generally sub windows don't have keyboard input,
-            // and winit assumes that unfocused window doesn't have modifiers.
-            let window_id = match self.active_window.map(mkwid) {
-                Some(window_id) => window_id,
-                None => return,
-            };
+        state.update_modifiers(
+            mods.base as u32,
+            mods.latched as u32,
+            mods.locked as u32,
+            group.base as u32,
+            group.latched as u32,
+            group.locked as u32,
+        );
+        let mods = state.modifiers();
+
+        self.update_keyboard_layouts(false, app);
+
+        // NOTE: we use active window since generally sub windows don't have keyboard input,
+        // and winit assumes that unfocused window doesn't have modifiers.
+        let window_id = match self.active_window.map(mkwid) {
+            Some(window_id) => window_id,
+            None => return,
+        };
+
+        self.send_modifiers(window_id, mods.into(), force, app);
+    }
+
+    /// Update the keyboard layouts after a change of the keymap or of the active layout, reporting
+    /// their change to the active window.
+    fn update_keyboard_layouts(&self, keymap_changed: bool, app: &mut dyn ApplicationHandler) {
+        // This runs for every XInput event, only look at the keymap when the group changed.
+        let active = self.target.keyboard_layouts.borrow().as_ref().map(|layouts| layouts.active);
+        if !keymap_changed && active == self.xkb_context.active_layout() {
+            return;
+        }
+
+        self.target.key_lookup.replace(self.xkb_context.key_lookup());
+
+        let layouts = self.xkb_context.keyboard_layouts();
+        if *self.target.keyboard_layouts.borrow() == layouts {
+            return;
+        }
 
-            let mods = state.modifiers();
-            self.send_modifiers(window_id, mods.into(), force, app);
+        self.target.keyboard_layouts.replace(layouts.clone());
+        if let (Some(layouts), Some(window)) = (layouts, self.active_window) {
+            let event = WindowEvent::KeyboardLayoutChanged(layouts);
+            app.window_event(&self.target, mkwid(window), event);
         }
     }
 
@@ -1800,6 +2345,37 @@ impl EventProcessor {
         depressed
     }
 
+    /// The core state of the current XKB modifiers and group, the inverse of
+    /// [`Self::xkb_mod_mask_from_core`].
+    fn core_state_from_xkb(&mut self) -> c_uint {
+        let Some(mods_indices) = self.xkb_context.keymap_mut().map(|keymap| keymap.mods_indices())
+        else {
+            return 0;
+        };
+        let Some(xkb_state) = self.xkb_context.state_mut() else {
+            return 0;
+        };
+
+        let mask = xkb_state.depressed_modifiers()
+            | xkb_state.latched_modifiers()
+            | xkb_state.locked_modifiers();
+        let state = [
+            (mods_indices.shift, ModMask::SHIFT),
+            (mods_indices.caps, ModMask::LOCK),
+            (mods_indices.ctrl, ModMask::CONTROL),
+            (mods_indices.alt, ModMask::M1),
+            (mods_indices.num, ModMask::M2),
+            (mods_indices.mod3, ModMask::M3),
+            (mods_indices.logo, ModMask::M4),
+            (mods_indices.mod5, ModMask::M5),
+        ]
+        .into_iter()
+        .filter(|(index, _)| index.is_some_and(|index| mask & 1 << index != 0))
+        .fold(0, |state, (_, core)| state | u16::from(core));
+
+        c_uint::from(state) | xkb_state.active_layout() << 13
+    }
+
     /// Send modifiers for the active window.
     ///
     /// The event won't be sent when the `modifiers` match the previously `sent` modifiers value,
@@ -1807,14 +2383,14 @@ impl EventProcessor {
     fn send_modifiers(
         &self,
         window_id: winit_core::window::WindowId,
-        modifiers: ModifiersState,
+        modifiers: Modifiers,
         force: bool,
         app: &mut dyn ApplicationHandler,
     ) {
         // NOTE: Always update the modifiers to account for case when they've changed
         // and forced was `true`.
         if self.modifiers.replace(modifiers) != modifiers || force {
-            let event = WindowEvent::ModifiersChanged(self.modifiers.get().into());
+            let event = WindowEvent::ModifiersChanged(self.modifiers.get());
             app.window_event(&self.target, window_id, event);
         }
     }
@@ -1846,13 +2422,20 @@ impl EventProcessor {
 
         for keycode in target.xconn.query_keymap().into_iter().filter(|k| *k >= KEYCODE_OFFSET) {
             let event = key_processor.process_key_event(keycode as u32, state, false);
-            let event = WindowEvent::KeyboardInput { device_id: None, event, is_synthetic: true };
+            let event = WindowEvent::KeyboardInput {
+                device_id: None,
+                event,
+                is_synthetic: true,
+                timestamp: None,
+            };
             app.window_event(target, window_id, event);
         }
     }
 
     fn process_dpi_change(&self, app: &mut dyn ApplicationHandler) {
-        self.target.xconn.reload_database().expect("failed to reload Xft database");
+        if let Err(err) = self.target.xconn.reload_database() {
+            warn!("failed to reload Xft database: {err}");
+        }
 
         // In the future, it would be quite easy to emit monitor hotplug events.
         let prev_list = {
@@ -1863,7 +2446,13 @@ impl EventProcessor {
             }
         };
 
-        let new_list = self.target.xconn.available_monitors().expect("Failed to get monitor list");
+        let new_list = match self.target.xconn.available_monitors() {
+            Ok(new_list) => new_list,
+            Err(err) => {
+                warn!("Failed to get monitor list: {err}");
+                return;
+            },
+        };
         for new_monitor in new_list {
             // Previous list may be empty, in case of disconnecting and
             // reconnecting the only one monitor. We still need to emit events in
@@ -1885,6 +2474,38 @@ impl EventProcessor {
         }
     }
 
+    fn process_xsettings_change(&self, app: &mut dyn ApplicationHandler) {
+        let prev_xsettings = match self.target.xconn.reload_xsettings() {
+            Ok(prev_xsettings) => prev_xsettings,
+            Err(err) => {
+                warn!("failed to reload XSETTINGS: {err}");
+                return;
+            },
+        };
+        let xsettings = self.target.xconn.xsettings();
+
+        if prev_xsettings.dpi != xsettings.dpi {
+            self.process_dpi_change(app);
+        }
+
+        if prev_xsettings.system_settings() != xsettings.system_settings() {
+            app.system_settings_changed(&self.target);
+        }
+
+        if let Some(theme) =
+            xsettings.theme().filter(|theme| prev_xsettings.theme() != Some(*theme))
+        {
+            let windows: Vec<_> =
+                self.target.windows.borrow().values().filter_map(|w| w.upgrade()).collect();
+            for window in windows {
+                if let Some(theme) = window.system_theme_changed(theme) {
+                    let event = WindowEvent::ThemeChanged(theme);
+                    app.window_event(&self.target, window.id(), event);
+                }
+            }
+        }
+    }
+
     fn window_exists(&self, window_id: xproto::Window) -> bool {
         self.with_window(window_id, |_| ()).is_some()
     }
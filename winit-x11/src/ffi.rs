@@ -2,3 +2,125 @@ pub use x11_dl::error::OpenError;
 pub use x11_dl::xinput2::*;
 pub use x11_dl::xlib::*;
 pub use x11_dl::xlib_xcb::*;
+
+pub use self::xinput2_4::*;
+pub use self::xkb_controls::{XkbControlsNotifyEvent, XkbRepeatKeysMask};
+
+/// The mode of `XEventsQueued` counting the events already read, which is not exposed by `x11-dl`.
+#[allow(non_upper_case_globals)]
+pub const QueuedAlready: std::os::raw::c_int = 0;
+
+/// XInput 2.4 touchpad gesture events, which are not exposed by `x11-dl` yet.
+///
+/// The layout mirrors `XIGesturePinchEvent` and `XIGestureSwipeEvent` from `XInput2.h`.
+#[allow(non_upper_case_globals)]
+mod xinput2_4 {
+    use std::os::raw::{c_double, c_int, c_ulong};
+
+    use x11_dl::xinput2::{XIGroupState, XIModifierState};
+    use x11_dl::xlib::{Bool, Display, Time, Window};
+
+    pub const XI_GesturePinchBegin: c_int = 27;
+    pub const XI_GesturePinchUpdate: c_int = 28;
+    pub const XI_GesturePinchEnd: c_int = 29;
+    pub const XI_GestureSwipeBegin: c_int = 30;
+    pub const XI_GestureSwipeUpdate: c_int = 31;
+    pub const XI_GestureSwipeEnd: c_int = 32;
+
+    pub const XIGesturePinchEventCancelled: c_int = 1 << 0;
+    pub const XIGestureSwipeEventCancelled: c_int = 1 << 0;
+
+    #[derive(Debug, Clone, Copy)]
+    #[repr(C)]
+    pub struct XIGesturePinchEvent {
+        pub _type: c_int,
+        pub serial: c_ulong,
+        pub send_event: Bool,
+        pub display: *mut Display,
+        pub extension: c_int,
+        pub evtype: c_int,
+        pub time: Time,
+        pub deviceid: c_int,
+        pub sourceid: c_int,
+        /// The number of touches in the gesture.
+        pub detail: c_int,
+        pub root: Window,
+        pub event: Window,
+        pub child: Window,
+        pub root_x: c_double,
+        pub root_y: c_double,
+        pub event_x: c_double,
+        pub event_y: c_double,
+        pub delta_x: c_double,
+        pub delta_y: c_double,
+        pub delta_unaccel_x: c_double,
+        pub delta_unaccel_y: c_double,
+        pub scale: c_double,
+        pub delta_scale: c_double,
+        pub delta_angle: c_double,
+        pub flags: c_int,
+        pub mods: XIModifierState,
+        pub group: XIGroupState,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    #[repr(C)]
+    pub struct XIGestureSwipeEvent {
+        pub _type: c_int,
+        pub serial: c_ulong,
+        pub send_event: Bool,
+        pub display: *mut Display,
+        pub extension: c_int,
+        pub evtype: c_int,
+        pub time: Time,
+        pub deviceid: c_int,
+        pub sourceid: c_int,
+        /// The number of touches in the gesture.
+        pub detail: c_int,
+        pub root: Window,
+        pub event: Window,
+        pub child: Window,
+        pub root_x: c_double,
+        pub root_y: c_double,
+        pub event_x: c_double,
+        pub event_y: c_double,
+        pub delta_x: c_double,
+        pub delta_y: c_double,
+        pub delta_unaccel_x: c_double,
+        pub delta_unaccel_y: c_double,
+        pub flags: c_int,
+        pub mods: XIModifierState,
+        pub group: XIGroupState,
+    }
+}
+
+/// The `XkbControlsNotifyEvent` layout from `XKBlib.h`, which `x11-dl` only exposes as an opaque
+/// type.
+#[allow(non_upper_case_globals)]
+mod xkb_controls {
+    use std::os::raw::{c_char, c_int, c_uint, c_ulong};
+
+    use x11_dl::xlib::{Bool, Display, KeyCode, Time};
+
+    pub const XkbRepeatKeysMask: c_uint = 1 << 0;
+
+    #[derive(Debug, Clone, Copy)]
+    #[repr(C)]
+    pub struct XkbControlsNotifyEvent {
+        pub _type: c_int,
+        pub serial: c_ulong,
+        pub send_event: Bool,
+        pub display: *mut Display,
+        pub time: Time,
+        pub xkb_type: c_int,
+        pub device: c_int,
+        pub changed_ctrls: c_uint,
+        pub enabled_ctrls: c_uint,
+        pub enabled_ctrl_changes: c_uint,
+        pub num_groups: c_int,
+        pub keycode: KeyCode,
+        pub event_type: c_char,
+        pub req_major: c_char,
+        pub req_minor: c_char,
+    }
+}
@@ -1,8 +1,10 @@
 use std::error::Error;
 use std::ffi::CStr;
+use std::ops::Range;
 use std::sync::Arc;
-use std::{fmt, mem, ptr};
+use std::{fmt, mem, ptr, slice};
 
+use winit_core::event::PreeditStyle;
 use x11_dl::xlib::{XIMCallback, XIMPreeditCaretCallbackStruct, XIMPreeditDrawCallbackStruct};
 
 use super::input_method::{InputMethod, Style, XIMStyle};
@@ -32,6 +34,11 @@ impl fmt::Display for ImeContextCreationError {
 
 impl Error for ImeContextCreationError {}
 
+// The `XIMFeedback` flags, missing from `x11_dl`.
+const XIM_REVERSE: ffi::XIMFeedback = 1;
+const XIM_UNDERLINE: ffi::XIMFeedback = 1 << 1;
+const XIM_HIGHLIGHT: ffi::XIMFeedback = 1 << 2;
+
 /// The callback used by XIM preedit functions.
 type XIMProcNonnull = unsafe extern "C" fn(ffi::XIM, ffi::XPointer, ffi::XPointer);
 
@@ -50,6 +57,7 @@ extern "C" fn preedit_start_callback(
     let client_data = unsafe { &mut *(client_data as *mut ImeContextClientData) };
 
     client_data.text.clear();
+    client_data.feedback.clear();
     client_data.cursor_pos = 0;
     client_data
         .event_sender
@@ -68,6 +76,7 @@ extern "C" fn preedit_done_callback(
 
     // Drop text buffer and reset cursor position on done.
     client_data.text = Vec::new();
+    client_data.feedback = Vec::new();
     client_data.cursor_pos = 0;
 
     client_data
@@ -80,6 +89,41 @@ fn calc_byte_position(text: &[char], pos: usize) -> usize {
     text.iter().take(pos).fold(0, |byte_pos, text| byte_pos + text.len_utf8())
 }
 
+/// Group the characters with the same feedback into styled byte ranges.
+fn calc_preedit_styles(
+    text: &[char],
+    feedback: &[ffi::XIMFeedback],
+) -> Vec<(Range<usize>, PreeditStyle)> {
+    let mut styles: Vec<(Range<usize>, PreeditStyle)> = Vec::new();
+    let mut byte_pos = 0;
+    for (ch, &feedback) in text.iter().zip(feedback) {
+        let start = byte_pos;
+        byte_pos += ch.len_utf8();
+
+        let style = preedit_style(feedback);
+        if style.is_empty() {
+            continue;
+        }
+
+        match styles.last_mut() {
+            Some((range, last_style)) if range.end == start && *last_style == style => {
+                range.end = byte_pos;
+            },
+            _ => styles.push((start..byte_pos, style)),
+        }
+    }
+
+    styles
+}
+
+fn preedit_style(feedback: ffi::XIMFeedback) -> PreeditStyle {
+    let mut style = PreeditStyle::empty();
+    style.set(PreeditStyle::REVERSE, feedback & XIM_REVERSE != 0);
+    style.set(PreeditStyle::UNDERLINE, feedback & XIM_UNDERLINE != 0);
+    style.set(PreeditStyle::HIGHLIGHT, feedback & XIM_HIGHLIGHT != 0);
+    style
+}
+
 /// Preedit text information to be drawn inline by the client.
 extern "C" fn preedit_draw_callback(
     _xim: ffi::XIM,
@@ -103,8 +147,8 @@ extern "C" fn preedit_draw_callback(
     }
 
     // NULL indicate text deletion
-    let mut new_chars = if call_data.text.is_null() {
-        Vec::new()
+    let (mut new_chars, mut new_feedback) = if call_data.text.is_null() {
+        (Vec::new(), Vec::new())
     } else {
         let xim_text = unsafe { &mut *(call_data.text) };
         if xim_text.encoding_is_wchar > 0 {
@@ -113,26 +157,58 @@ extern "C" fn preedit_draw_callback(
 
         let new_text = unsafe { xim_text.string.multi_byte };
 
+        // NULL string with feedback only restyles the characters, e.g. when the clause moves.
         if new_text.is_null() {
+            if xim_text.feedback.is_null() {
+                return;
+            }
+
+            let feedback =
+                unsafe { slice::from_raw_parts(xim_text.feedback, xim_text.length as usize) };
+            let start = chg_range.start.min(client_data.feedback.len());
+            let end = (start + feedback.len()).min(client_data.feedback.len());
+            client_data.feedback[start..end].copy_from_slice(&feedback[..end - start]);
+
+            client_data
+                .event_sender
+                .send((client_data.window, client_data.update_event()))
+                .expect("failed to send preedit update event");
             return;
         }
 
         let new_text = unsafe { CStr::from_ptr(new_text) };
 
-        String::from(new_text.to_str().expect("Invalid UTF-8 String from IME")).chars().collect()
+        let new_chars: Vec<char> =
+            String::from(new_text.to_str().expect("Invalid UTF-8 String from IME"))
+                .chars()
+                .collect();
+
+        // The feedback array, when provided, has one entry per character.
+        let new_feedback = if xim_text.feedback.is_null() {
+            vec![0; new_chars.len()]
+        } else {
+            let feedback =
+                unsafe { slice::from_raw_parts(xim_text.feedback, xim_text.length as usize) };
+            let mut feedback = feedback.to_vec();
+            feedback.resize(new_chars.len(), 0);
+            feedback
+        };
+
+        (new_chars, new_feedback)
     };
     let mut old_text_tail = client_data.text.split_off(chg_range.end);
     client_data.text.truncate(chg_range.start);
     client_data.text.append(&mut new_chars);
     client_data.text.append(&mut old_text_tail);
-    let cursor_byte_pos = calc_byte_position(&client_data.text, client_data.cursor_pos);
+
+    let mut old_feedback_tail = client_data.feedback.split_off(chg_range.end);
+    client_data.feedback.truncate(chg_range.start);
+    client_data.feedback.append(&mut new_feedback);
+    client_data.feedback.append(&mut old_feedback_tail);
 
     client_data
         .event_sender
-        .send((
-            client_data.window,
-            ImeEvent::Update(client_data.text.iter().collect(), cursor_byte_pos),
-        ))
+        .send((client_data.window, client_data.update_event()))
         .expect("failed to send preedit update event");
 }
 
@@ -147,14 +223,10 @@ extern "C" fn preedit_caret_callback(
 
     if call_data.direction == ffi::XIMCaretDirection::XIMAbsolutePosition {
         client_data.cursor_pos = call_data.position as usize;
-        let cursor_byte_pos = calc_byte_position(&client_data.text, client_data.cursor_pos);
 
         client_data
             .event_sender
-            .send((
-                client_data.window,
-                ImeEvent::Update(client_data.text.iter().collect(), cursor_byte_pos),
-            ))
+            .send((client_data.window, client_data.update_event()))
             .expect("failed to send preedit update event");
     }
 }
@@ -186,9 +258,19 @@ struct ImeContextClientData {
     window: ffi::Window,
     event_sender: ImeEventSender,
     text: Vec<char>,
+    /// The `XIMFeedback` of each character of the `text`.
+    feedback: Vec<ffi::XIMFeedback>,
     cursor_pos: usize,
 }
 
+impl ImeContextClientData {
+    fn update_event(&self) -> ImeEvent {
+        let cursor_byte_pos = calc_byte_position(&self.text, self.cursor_pos);
+        let styles = calc_preedit_styles(&self.text, &self.feedback);
+        ImeEvent::Update(self.text.iter().collect(), cursor_byte_pos, styles)
+    }
+}
+
 // XXX: this struct doesn't destroy its XIC resource when dropped.
 // This is intentional, as it doesn't have enough information to know whether or not the context
 // still exists on the server. Since `ImeInner` has that awareness, destruction must be handled
@@ -215,6 +297,7 @@ impl ImeContext {
             window,
             event_sender,
             text: Vec::new(),
+            feedback: Vec::new(),
             cursor_pos: 0,
         }));
 
@@ -403,3 +486,21 @@ impl ImeContext {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preedit_styles() {
+        let text: Vec<char> = "日本語ab".chars().collect();
+        let feedback = [XIM_REVERSE, XIM_REVERSE, XIM_UNDERLINE, 0, XIM_UNDERLINE];
+        assert_eq!(calc_preedit_styles(&text, &feedback), vec![
+            (0..6, PreeditStyle::REVERSE),
+            (6..9, PreeditStyle::UNDERLINE),
+            (10..11, PreeditStyle::UNDERLINE),
+        ]);
+
+        assert_eq!(calc_preedit_styles(&text, &[0; 5]), vec![]);
+    }
+}
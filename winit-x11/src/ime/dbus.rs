@@ -0,0 +1,275 @@
+//! Input method support through the D-Bus frontends of IBus and Fcitx 5, used instead of XIM when
+//! the user selected one of them.
+
+use std::collections::{HashMap, VecDeque};
+use std::os::fd::{AsFd, BorrowedFd};
+use std::sync::Arc;
+use std::time::Instant;
+
+use winit_common::dbus::{Error, ImeFramework, InputContext, InputContextEvent};
+use winit_core::event::Ime;
+use winit_core::window::{ImeHint, ImePurpose, ImeSurroundingText};
+use x11rb::protocol::xproto;
+
+use super::ImeRequest;
+use crate::ffi;
+use crate::xdisplay::XConnection;
+
+/// The IME state of a window allowing IME input.
+#[derive(Debug, Default)]
+struct WindowState {
+    /// The cursor area, in window coordinates.
+    area: Option<(i16, i16, u16, u16)>,
+    content_type: Option<(ImeHint, ImePurpose)>,
+    surrounding_text: Option<ImeSurroundingText>,
+}
+
+/// A key waiting for the input method to decide whether it consumes it.
+#[derive(Debug)]
+struct PendingKey {
+    /// The serial of the key sent to the input method, `None` when it wasn't sent.
+    serial: Option<u32>,
+    event: ffi::XKeyEvent,
+    consumed: Option<bool>,
+}
+
+/// An event resulting from the messages of the input method.
+#[derive(Debug)]
+pub(crate) enum DBusImeEvent {
+    Ime(ffi::Window, Ime),
+    /// A key the input method didn't consume, to handle as usual.
+    Key(ffi::XKeyEvent),
+}
+
+/// A single input context shared by the windows, following the keyboard focus.
+#[derive(Debug)]
+pub(crate) struct DBusIme {
+    xconn: Arc<XConnection>,
+    root: xproto::Window,
+    context: InputContext,
+    /// The windows allowing IME input.
+    windows: HashMap<ffi::Window, WindowState>,
+    focused: Option<ffi::Window>,
+    /// The keys waiting for the input method, or for the keys before them, in order.
+    pending_keys: VecDeque<PendingKey>,
+}
+
+impl DBusIme {
+    /// Connect to the input method framework selected in the environment, if any.
+    pub fn new(xconn: Arc<XConnection>, root: xproto::Window) -> Option<Self> {
+        let framework = ImeFramework::from_env()?;
+        match InputContext::new(framework) {
+            Ok(context) => Some(Self {
+                xconn,
+                root,
+                context,
+                windows: HashMap::new(),
+                focused: None,
+                pending_keys: VecDeque::new(),
+            }),
+            Err(err) => {
+                tracing::warn!("failed to connect to {framework:?}, falling back to XIM: {err}");
+                None
+            },
+        }
+    }
+
+    /// Apply a request of a window, returning the event to send to it.
+    pub fn request(&mut self, request: ImeRequest) -> Result<Option<(ffi::Window, Ime)>, Error> {
+        match request {
+            ImeRequest::Allow(window, true) => {
+                if self.windows.contains_key(&window) {
+                    return Ok(None);
+                }
+
+                self.windows.insert(window, WindowState::default());
+                if self.focused == Some(window) {
+                    self.focus_in(window)?;
+                }
+                Ok(Some((window, Ime::Enabled)))
+            },
+            ImeRequest::Allow(window, false) => {
+                if self.windows.remove(&window).is_none() {
+                    return Ok(None);
+                }
+
+                if self.focused == Some(window) {
+                    self.context.reset()?;
+                    self.context.focus_out()?;
+                }
+                Ok(Some((window, Ime::Disabled)))
+            },
+            ImeRequest::Area(window, x, y, width, height) => {
+                if let Some(state) = self.windows.get_mut(&window) {
+                    state.area = Some((x, y, width, height));
+                    if self.focused == Some(window) {
+                        self.send_area(window)?;
+                    }
+                }
+                Ok(None)
+            },
+            ImeRequest::ContentType(window, hint, purpose) => {
+                if let Some(state) = self.windows.get_mut(&window) {
+                    state.content_type = Some((hint, purpose));
+                    if self.focused == Some(window) {
+                        self.context.set_content_type(hint, purpose)?;
+                    }
+                }
+                Ok(None)
+            },
+            ImeRequest::SurroundingText(window, surrounding_text) => {
+                if let Some(state) = self.windows.get_mut(&window) {
+                    if self.focused == Some(window) {
+                        self.context.set_surrounding_text(&surrounding_text)?;
+                    }
+                    state.surrounding_text = Some(surrounding_text);
+                }
+                Ok(None)
+            },
+        }
+    }
+
+    pub fn focus(&mut self, window: ffi::Window) -> Result<(), Error> {
+        if self.focused == Some(window) {
+            return Ok(());
+        }
+
+        self.focused = Some(window);
+        if self.windows.contains_key(&window) { self.focus_in(window) } else { Ok(()) }
+    }
+
+    pub fn unfocus(&mut self, window: ffi::Window) -> Result<(), Error> {
+        if self.focused != Some(window) {
+            return Ok(());
+        }
+
+        self.focused = None;
+        if self.windows.contains_key(&window) { self.context.focus_out() } else { Ok(()) }
+    }
+
+    /// Forget a destroyed window.
+    pub fn remove_window(&mut self, window: ffi::Window) {
+        self.windows.remove(&window);
+        if self.focused == Some(window) {
+            self.focused = None;
+        }
+    }
+
+    /// Re-send the cursor area of a window which moved, since it's in root window coordinates.
+    pub fn window_moved(&mut self, window: ffi::Window) -> Result<(), Error> {
+        match self.focused {
+            Some(focused) if focused == window && self.windows.contains_key(&window) => {
+                self.send_area(window)
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Let the input method process a key of the focused window, returning whether the key is
+    /// held until it decides whether it consumes it, and delivered by [`Self::dispatch()`]
+    /// otherwise.
+    ///
+    /// `keysym` is the keysym of the key with the modifiers of the event applied.
+    pub fn process_key(&mut self, event: &ffi::XKeyEvent, keysym: u32) -> Result<bool, Error> {
+        let serial = match self.focused {
+            Some(window) if self.windows.contains_key(&window) => {
+                let pressed = event.type_ == ffi::KeyPress;
+                let (keycode, state, time) = (event.keycode, event.state, event.time as u32);
+                self.context.process_key(keysym, keycode, state, pressed, time)?
+            },
+            _ => None,
+        };
+
+        // Keep the keys in order behind the ones the input method is processing.
+        if serial.is_none() && self.pending_keys.is_empty() {
+            return Ok(false);
+        }
+        let consumed = if serial.is_none() { Some(false) } else { None };
+        self.pending_keys.push_back(PendingKey { serial, event: *event, consumed });
+        Ok(true)
+    }
+
+    /// When to [dispatch](Self::dispatch) to stop waiting for the input method to process a key.
+    pub fn key_deadline(&self) -> Option<Instant> {
+        self.context.key_deadline()
+    }
+
+    /// Process the messages of the input method, returning the events of the focused window and
+    /// the keys it didn't consume, in order.
+    pub fn dispatch(&mut self) -> Result<Vec<DBusImeEvent>, Error> {
+        let window = self.focused.filter(|window| self.windows.contains_key(window));
+        let mut events = Vec::new();
+        for event in self.context.dispatch()? {
+            match event {
+                InputContextEvent::Ime(event) => {
+                    if let Some(window) = window {
+                        events.push(DBusImeEvent::Ime(window, event));
+                    }
+                },
+                InputContextEvent::KeyProcessed { serial, consumed } => {
+                    let key = self.pending_keys.iter_mut().find(|key| key.serial == Some(serial));
+                    if let Some(key) = key {
+                        key.consumed = Some(consumed);
+                    }
+                    self.release_keys(&mut events);
+                },
+                _ => (),
+            }
+        }
+        Ok(events)
+    }
+
+    /// Take the keys still waiting for the input method, when it goes away.
+    pub fn take_pending_keys(&mut self) -> impl Iterator<Item = ffi::XKeyEvent> + '_ {
+        self.pending_keys.drain(..).map(|key| key.event)
+    }
+
+    /// The windows allowing IME input, which need to be told when the input method goes away.
+    pub fn allowed_windows(&self) -> impl Iterator<Item = ffi::Window> + '_ {
+        self.windows.keys().copied()
+    }
+
+    /// Send the state of the window before giving it the focus.
+    fn focus_in(&mut self, window: ffi::Window) -> Result<(), Error> {
+        let state = &self.windows[&window];
+        let (hint, purpose) = state.content_type.unwrap_or((ImeHint::NONE, ImePurpose::Normal));
+        self.context.set_content_type(hint, purpose)?;
+        if let Some(surrounding_text) = &state.surrounding_text {
+            self.context.set_surrounding_text(surrounding_text)?;
+        }
+        self.send_area(window)?;
+        self.context.focus_in()
+    }
+
+    /// Release the processed keys at the front of the queue.
+    fn release_keys(&mut self, events: &mut Vec<DBusImeEvent>) {
+        while let Some(consumed) = self.pending_keys.front().and_then(|key| key.consumed) {
+            let key = self.pending_keys.pop_front().unwrap();
+            if !consumed {
+                events.push(DBusImeEvent::Key(key.event));
+            }
+        }
+    }
+
+    fn send_area(&mut self, window: ffi::Window) -> Result<(), Error> {
+        let Some((x, y, width, height)) = self.windows[&window].area else {
+            return Ok(());
+        };
+
+        // The input methods place their popup in root window coordinates.
+        let (x, y) = match self.xconn.translate_coords(window as xproto::Window, self.root, x, y) {
+            Ok(reply) => (reply.dst_x, reply.dst_y),
+            Err(err) => {
+                tracing::warn!("failed to translate the IME cursor area: {err}");
+                return Ok(());
+            },
+        };
+        self.context.set_cursor_area(x.into(), y.into(), width.into(), height.into())
+    }
+}
+
+impl AsFd for DBusIme {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.context.as_fd()
+    }
+}
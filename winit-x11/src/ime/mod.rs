@@ -2,19 +2,24 @@
 
 mod callbacks;
 mod context;
+mod dbus;
 mod inner;
 mod input_method;
 
 use std::fmt;
+use std::ops::Range;
 use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Sender};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use winit_core::event::PreeditStyle;
+use winit_core::window::{ImeHint, ImePurpose, ImeSurroundingText};
 
 use self::callbacks::*;
 use self::context::ImeContext;
 pub use self::context::ImeContextCreationError;
+pub(crate) use self::dbus::{DBusIme, DBusImeEvent};
 use self::inner::{ImeInner, close_im};
 use self::input_method::PotentialInputMethods;
 use crate::xdisplay::{XConnection, XError};
@@ -25,7 +30,7 @@ use crate::{ffi, util};
 pub enum ImeEvent {
     Enabled,
     Start,
-    Update(String, usize),
+    Update(String, usize, Vec<(Range<usize>, PreeditStyle)>),
     End,
     Disabled,
 }
@@ -42,6 +47,16 @@ pub enum ImeRequest {
 
     /// Allow IME input for the given `window_id`.
     Allow(ffi::Window, bool),
+
+    /// Set the content type of the text input of the given `window_id`.
+    ///
+    /// Only used by the D-Bus input methods.
+    ContentType(ffi::Window, ImeHint, ImePurpose),
+
+    /// Set the text surrounding the cursor in the given `window_id`.
+    ///
+    /// Only used by the D-Bus input methods.
+    SurroundingText(ffi::Window, ImeSurroundingText),
 }
 
 #[derive(Debug)]
@@ -0,0 +1,153 @@
+//! The key repeat of winit, replacing the one of the server for [`KeyRepeat::Disabled`] and
+//! [`KeyRepeat::Custom`].
+//!
+//! The keys are tracked before the input method filters them, so its repeats of the held key go
+//! through the input method like the key presses, while the repeats of the server never reach it.
+
+use std::time::Instant;
+
+use winit_core::event_loop::KeyRepeat;
+use x11_dl::xlib::{self, Time, XKeyEvent};
+
+#[derive(Debug, Default)]
+pub struct KeyRepeater {
+    /// The latest repeatable key pressed, with the time of its press.
+    held_key: Option<(u32, Time)>,
+    /// The press of the held key replayed by winit, and when to replay it next.
+    repeat: Option<(XKeyEvent, Instant)>,
+}
+
+impl KeyRepeater {
+    /// Track a key event of the server, returning whether it's a repeat of the server to drop.
+    ///
+    /// The presses the input method forwards back, and the repeats of winit, keep the time of the
+    /// press, unlike the repeats of the server.
+    pub fn server_key(
+        &mut self,
+        key_repeat: KeyRepeat,
+        xev: &XKeyEvent,
+        key_repeats: bool,
+        now: Instant,
+    ) -> bool {
+        let keycode = xev.keycode;
+        if xev.type_ != xlib::KeyPress {
+            // Repeats continue for the latest key press if a previously pressed key is released.
+            if self.held_key.is_some_and(|(held, _)| held == keycode) {
+                self.held_key = None;
+                self.repeat = None;
+            }
+            return false;
+        }
+
+        if !key_repeats {
+            return false;
+        }
+
+        if let Some((_, time)) = self.held_key.filter(|&(held, _)| held == keycode) {
+            return time != xev.time && key_repeat != KeyRepeat::System;
+        }
+
+        self.held_key = Some((keycode, xev.time));
+        self.repeat = key_repeat.custom_interval().map(|(delay, _)| (*xev, now + delay));
+        false
+    }
+
+    /// The press of the held key to replay once its time came.
+    pub fn next_repeat(&mut self, key_repeat: KeyRepeat, now: Instant) -> Option<XKeyEvent> {
+        let (xev, deadline) = self.repeat?;
+        if now < deadline {
+            return None;
+        }
+
+        match key_repeat.custom_interval() {
+            Some((_, interval)) => {
+                self.repeat = Some((xev, now + interval));
+                Some(xev)
+            },
+            None => {
+                self.repeat = None;
+                None
+            },
+        }
+    }
+
+    /// When the held key is next repeated.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.repeat.map(|(_, deadline)| deadline)
+    }
+
+    /// Forget the held key, when the keyboard focus is lost.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn key(type_: i32, keycode: u32, time: Time) -> XKeyEvent {
+        // SAFETY: All the fields of the event are integers or pointers.
+        XKeyEvent { type_, keycode, time, ..unsafe { std::mem::zeroed() } }
+    }
+
+    #[test]
+    fn custom_repeat() {
+        let (delay, rate) = (Duration::from_millis(300), 25);
+        let key_repeat = KeyRepeat::Custom { delay, rate };
+        let mut repeater = KeyRepeater::default();
+        let now = Instant::now();
+
+        // The press arms the repeat.
+        assert!(!repeater.server_key(key_repeat, &key(xlib::KeyPress, 22, 10), true, now));
+        assert_eq!(repeater.deadline(), Some(now + delay));
+
+        // The repeats of the server are dropped, the press forwarded by the input method isn't.
+        assert!(repeater.server_key(key_repeat, &key(xlib::KeyPress, 22, 500), true, now));
+        assert!(!repeater.server_key(key_repeat, &key(xlib::KeyPress, 22, 10), true, now));
+        assert_eq!(repeater.deadline(), Some(now + delay));
+
+        // The press is replayed once the delay passed, then at the rate.
+        assert!(repeater.next_repeat(key_repeat, now).is_none());
+        let repeat = repeater.next_repeat(key_repeat, now + delay).unwrap();
+        assert_eq!((repeat.keycode, repeat.time), (22, 10));
+        assert_eq!(repeater.deadline(), Some(now + delay + Duration::from_millis(40)));
+
+        // Keys which don't repeat, like modifiers, don't stop the repeat.
+        assert!(!repeater.server_key(key_repeat, &key(xlib::KeyPress, 50, 600), false, now));
+        assert!(!repeater.server_key(key_repeat, &key(xlib::KeyRelease, 50, 700), false, now));
+        assert!(repeater.deadline().is_some());
+
+        // Releasing the key stops it.
+        assert!(!repeater.server_key(key_repeat, &key(xlib::KeyRelease, 22, 800), true, now));
+        assert_eq!(repeater.deadline(), None);
+        assert!(repeater.next_repeat(key_repeat, now + delay * 2).is_none());
+    }
+
+    #[test]
+    fn other_key_repeats() {
+        let now = Instant::now();
+
+        // The repeats of the server are kept for the system key repeat.
+        let mut repeater = KeyRepeater::default();
+        assert!(!repeater.server_key(KeyRepeat::System, &key(xlib::KeyPress, 22, 10), true, now));
+        assert!(!repeater.server_key(KeyRepeat::System, &key(xlib::KeyPress, 22, 500), true, now));
+        assert_eq!(repeater.deadline(), None);
+
+        // And dropped without a repeat of winit when disabled.
+        let mut repeater = KeyRepeater::default();
+        let key_repeat = KeyRepeat::Disabled;
+        assert!(!repeater.server_key(key_repeat, &key(xlib::KeyPress, 22, 10), true, now));
+        assert!(repeater.server_key(key_repeat, &key(xlib::KeyPress, 22, 500), true, now));
+        assert_eq!(repeater.deadline(), None);
+
+        // Switching to the system key repeat stops the repeat of winit.
+        let key_repeat = KeyRepeat::Custom { delay: Duration::ZERO, rate: 1 };
+        let mut repeater = KeyRepeater::default();
+        assert!(!repeater.server_key(key_repeat, &key(xlib::KeyPress, 22, 10), true, now));
+        assert!(repeater.next_repeat(KeyRepeat::System, now).is_none());
+        assert_eq!(repeater.deadline(), None);
+    }
+}
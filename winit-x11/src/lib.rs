@@ -2,10 +2,14 @@
 
 #![warn(clippy::exhaustive_enums)]
 
+use std::os::fd::BorrowedFd;
+
 use dpi::Size;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use winit_core::event_loop::ActiveEventLoop as CoreActiveEventLoop;
+pub use winit_common::xkb::KeymapSource;
+use winit_core::error::{NotSupportedError, RequestError};
+use winit_core::event_loop::{ActiveEventLoop as CoreActiveEventLoop, FdReadiness, FdRegistration};
 use winit_core::window::{ActivationToken, PlatformWindowAttributes, Window as CoreWindow};
 
 pub use crate::event_loop::{ActiveEventLoop, EventLoop};
@@ -22,6 +26,7 @@ mod event_loop;
 mod event_processor;
 pub mod ffi;
 mod ime;
+mod key_repeat;
 mod monitor;
 mod util;
 mod window;
@@ -113,6 +118,20 @@ pub fn register_xlib_error_hook(hook: XlibErrorHook) {
 pub trait ActiveEventLoopExtX11 {
     /// True if the event loop uses X11.
     fn is_x11(&self) -> bool;
+
+    /// Watch a file descriptor with the event loop, reporting its readiness with
+    /// [`ApplicationHandler::fd_ready()`] until the returned registration is dropped.
+    ///
+    /// `interest` selects whether to watch for [`FdReadiness::READABLE`] and
+    /// [`FdReadiness::WRITABLE`]. The file descriptor is duplicated, so the original may be
+    /// closed independently.
+    ///
+    /// [`ApplicationHandler::fd_ready()`]: winit_core::application::ApplicationHandler::fd_ready
+    fn register_fd(
+        &self,
+        fd: BorrowedFd<'_>,
+        interest: FdReadiness,
+    ) -> Result<FdRegistration, RequestError>;
 }
 
 impl ActiveEventLoopExtX11 for dyn CoreActiveEventLoop + '_ {
@@ -120,6 +139,17 @@ impl ActiveEventLoopExtX11 for dyn CoreActiveEventLoop + '_ {
     fn is_x11(&self) -> bool {
         self.cast_ref::<ActiveEventLoop>().is_some()
     }
+
+    fn register_fd(
+        &self,
+        fd: BorrowedFd<'_>,
+        interest: FdReadiness,
+    ) -> Result<FdRegistration, RequestError> {
+        match self.cast_ref::<ActiveEventLoop>() {
+            Some(event_loop) => event_loop.register_fd(fd, interest),
+            None => Err(NotSupportedError::new("the event loop doesn't use X11").into()),
+        }
+    }
 }
 
 /// Additional methods on [`EventLoop`] that are specific to X11.
@@ -138,6 +168,12 @@ pub trait EventLoopBuilderExtX11 {
     /// By default, the window is only allowed to be created on the main
     /// thread, to make platform compatibility easier.
     fn with_any_thread(&mut self, any_thread: bool) -> &mut Self;
+
+    /// Interpret the keys with the given keymap instead of the one of the X server.
+    ///
+    /// The X server still provides the modifier and layout state, whose masks are applied to the
+    /// keymap as is. The keymap should thus map the real modifiers the usual way.
+    fn with_keymap(&mut self, keymap: KeymapSource) -> &mut Self;
 }
 
 /// Additional methods on [`Window`] that are specific to X11.
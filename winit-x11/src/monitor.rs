@@ -152,6 +152,12 @@ impl MonitorHandle {
         // Zero is an invalid XID value; no real monitor will have it
         self.id == 0
     }
+
+    /// The refresh rate of the current video mode.
+    pub(crate) fn refresh_rate_millihertz(&self) -> Option<NonZeroU32> {
+        let mode = self.video_modes.iter().find(|mode| mode.current)?;
+        mode.mode.refresh_rate_millihertz()
+    }
 }
 
 impl XConnection {
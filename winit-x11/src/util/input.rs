@@ -18,12 +18,21 @@ impl XConnection {
         window: xproto::Window,
         device_id: u16,
         mask: xinput::XIEventMask,
+    ) -> Result<VoidCookie<'_>, X11Error> {
+        self.select_xinput_event_masks(window, device_id, vec![mask])
+    }
+
+    /// Like [`Self::select_xinput_events`], but for masks spanning more than 32 event types.
+    ///
+    /// Each item of `mask` covers the next 32 XInput2 event types.
+    pub fn select_xinput_event_masks(
+        &self,
+        window: xproto::Window,
+        device_id: u16,
+        mask: Vec<xinput::XIEventMask>,
     ) -> Result<VoidCookie<'_>, X11Error> {
         self.xcb_connection()
-            .xinput_xi_select_events(window, &[xinput::EventMask {
-                deviceid: device_id,
-                mask: vec![mask],
-            }])
+            .xinput_xi_select_events(window, &[xinput::EventMask { deviceid: device_id, mask }])
             .map_err(Into::into)
     }
 
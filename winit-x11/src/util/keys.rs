@@ -1,5 +1,8 @@
 use std::iter::Enumerate;
 use std::slice::Iter;
+use std::time::Duration;
+
+use x11rb::protocol::xkb::{self, ConnectionExt as _};
 
 use super::*;
 
@@ -70,6 +73,23 @@ impl XConnection {
 
         Keymap { keys }
     }
+
+    /// Query the key repeat delay and interval of the core keyboard.
+    ///
+    /// The interval is zero when key repeat is disabled.
+    pub fn query_key_repeat(&self) -> Result<(Duration, Duration), X11Error> {
+        let controls =
+            self.xcb_connection().xkb_get_controls(xkb::ID::USE_CORE_KBD.into())?.reply()?;
+
+        let delay = Duration::from_millis(controls.repeat_delay.into());
+        let interval = if controls.enabled_controls.contains(xkb::BoolCtrl::REPEAT_KEYS) {
+            Duration::from_millis(controls.repeat_interval.into())
+        } else {
+            Duration::ZERO
+        };
+
+        Ok((delay, interval))
+    }
 }
 
 fn first_bit(b: u8) -> u8 {
@@ -35,6 +35,17 @@ impl XConnection {
         *WM_NAME.lock().unwrap() = self.get_wm_name(root);
     }
 
+    /// Whether a compositing manager runs on the screen.
+    pub fn is_composited(&self, screen_id: i32) -> bool {
+        let owner = || -> Result<xproto::Window, X11Error> {
+            let selection = format!("_NET_WM_CM_S{screen_id}");
+            let selection =
+                self.xcb_connection().intern_atom(false, selection.as_bytes())?.reply()?;
+            Ok(self.xcb_connection().get_selection_owner(selection.atom)?.reply()?.owner)
+        };
+        owner().is_ok_and(|owner| owner != x11rb::NONE)
+    }
+
     fn get_supported_hints(&self, root: xproto::Window) -> Vec<xproto::Atom> {
         let atoms = self.atoms();
         let supported_atom = atoms[_NET_SUPPORTED];
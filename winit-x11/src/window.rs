@@ -20,9 +20,9 @@ use winit_core::monitor::{
     Fullscreen, MonitorHandle as CoreMonitorHandle, MonitorHandleProvider, VideoMode,
 };
 use winit_core::window::{
-    CursorGrabMode, ImeCapabilities, ImeRequest as CoreImeRequest, ImeRequestError,
+    CursorGrabMode, ImeCapabilities, ImeRequest as CoreImeRequest, ImeRequestError, Rect,
     ResizeDirection, Theme, UserAttentionType, Window as CoreWindow, WindowAttributes,
-    WindowButtons, WindowId, WindowLevel,
+    WindowButtons, WindowId, WindowLevel, WindowStates,
 };
 use x11rb::connection::{Connection, RequestConnection};
 use x11rb::properties::{WmHints, WmSizeHints, WmSizeHintsSpecification};
@@ -32,12 +32,13 @@ use x11rb::protocol::xproto::{self, ClipOrdering, ConnectionExt as _, Rectangle}
 use x11rb::protocol::{randr, xinput};
 
 use crate::atoms::{
-    _GTK_THEME_VARIANT, _NET_ACTIVE_WINDOW, _NET_WM_ICON, _NET_WM_MOVERESIZE, _NET_WM_NAME,
-    _NET_WM_PID, _NET_WM_PING, _NET_WM_STATE, _NET_WM_STATE_ABOVE, _NET_WM_STATE_BELOW,
-    _NET_WM_STATE_FULLSCREEN, _NET_WM_STATE_HIDDEN, _NET_WM_STATE_MAXIMIZED_HORZ,
-    _NET_WM_STATE_MAXIMIZED_VERT, _NET_WM_SYNC_REQUEST, _NET_WM_SYNC_REQUEST_COUNTER,
-    _NET_WM_WINDOW_TYPE, _XEMBED, AtomName, CARD32, UTF8_STRING, WM_CHANGE_STATE,
-    WM_CLIENT_MACHINE, WM_DELETE_WINDOW, WM_PROTOCOLS, WM_STATE, XdndAware,
+    _GTK_THEME_VARIANT, _NET_ACTIVE_WINDOW, _NET_WM_FRAME_DRAWN, _NET_WM_ICON, _NET_WM_MOVERESIZE,
+    _NET_WM_NAME, _NET_WM_PID, _NET_WM_PING, _NET_WM_STATE, _NET_WM_STATE_ABOVE,
+    _NET_WM_STATE_BELOW, _NET_WM_STATE_FOCUSED, _NET_WM_STATE_FULLSCREEN, _NET_WM_STATE_HIDDEN,
+    _NET_WM_STATE_MAXIMIZED_HORZ, _NET_WM_STATE_MAXIMIZED_VERT, _NET_WM_STATE_SHADED,
+    _NET_WM_STATE_STICKY, _NET_WM_SYNC_REQUEST, _NET_WM_SYNC_REQUEST_COUNTER, _NET_WM_WINDOW_TYPE,
+    _XEMBED, AtomName, CARD32, UTF8_STRING, WM_CHANGE_STATE, WM_CLIENT_MACHINE, WM_DELETE_WINDOW,
+    WM_PROTOCOLS, WM_STATE, XdndAware,
 };
 use crate::event_loop::{
     ALL_MASTER_DEVICES, ActivationItem, ActiveEventLoop, CookieResultExt, ICONIC_STATE, VoidCookie,
@@ -106,6 +107,10 @@ impl CoreWindow for Window {
         winit_common::xkb::reset_dead_keys();
     }
 
+    fn cancel_compose(&self) {
+        self.0.cancel_compose()
+    }
+
     fn surface_position(&self) -> PhysicalPosition<i32> {
         self.0.surface_position()
     }
@@ -202,6 +207,10 @@ impl CoreWindow for Window {
         self.0.is_maximized()
     }
 
+    fn states(&self) -> WindowStates {
+        self.0.states()
+    }
+
     fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
         self.0.set_fullscreen(fullscreen)
     }
@@ -298,6 +307,10 @@ impl CoreWindow for Window {
         self.0.set_cursor_hittest(hittest)
     }
 
+    fn set_input_region(&self, region: Option<&[Rect]>) -> Result<(), RequestError> {
+        self.0.set_input_region(region)
+    }
+
     fn current_monitor(&self) -> Option<CoreMonitorHandle> {
         self.0.current_monitor().map(|monitor| CoreMonitorHandle(Arc::new(monitor)))
     }
@@ -382,8 +395,12 @@ pub struct SharedState {
     pub base_size: Option<Size>,
     pub visibility: Visibility,
     pub has_focus: bool,
-    // Use `Option` to not apply hittest logic when it was never requested.
-    pub cursor_hittest: Option<bool>,
+    /// The region set with `set_input_region`, kept to follow scale factor changes.
+    pub input_region: Option<Vec<Rect>>,
+    /// The theme set with `set_theme`, overriding the system theme.
+    pub theme: Option<Theme>,
+    /// The last states reported with `WindowEvent::StateChanged`.
+    pub states: WindowStates,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -422,7 +439,9 @@ impl SharedState {
             surface_resize_increments: None,
             base_size: None,
             has_focus: false,
-            cursor_hittest: None,
+            input_region: None,
+            theme: window_attributes.preferred_theme,
+            states: WindowStates::empty(),
         })
     }
 }
@@ -440,6 +459,7 @@ pub struct UnownedWindow {
     #[allow(dead_code)]
     screen_id: i32, // never changes
     sync_counter_id: Option<NonZeroU32>, // never changes
+    frame_counter: Option<FrameCounter>, // never changes
     selected_cursor: Mutex<SelectedCursor>,
     cursor_grabbed_mode: Mutex<CursorGrabMode>,
     #[allow(clippy::mutex_atomic)]
@@ -448,7 +468,43 @@ pub struct UnownedWindow {
     pub shared_state: Mutex<SharedState>,
     redraw_sender: WakeSender<WindowId>,
     activation_sender: WakeSender<ActivationItem>,
+    cancel_compose_sender: WakeSender<WindowId>,
 }
+/// The extended `_NET_WM_SYNC_REQUEST_COUNTER`, odd while a frame is being drawn.
+#[derive(Debug)]
+struct FrameCounter {
+    id: u32,
+    sync: Mutex<FrameSync>,
+}
+
+#[derive(Debug, Default)]
+struct FrameSync {
+    value: u64,
+    /// The value requested with `_NET_WM_SYNC_REQUEST` while a frame was being drawn.
+    requested: Option<u64>,
+}
+
+impl FrameCounter {
+    fn new(id: u32) -> Self {
+        Self { id, sync: Default::default() }
+    }
+
+    /// Update the counter, returning its new value.
+    fn update(&self, xconn: &XConnection, update: impl FnOnce(&mut FrameSync)) -> u64 {
+        let mut sync = self.sync.lock().unwrap();
+        let old = sync.value;
+        update(&mut sync);
+        if sync.value != old {
+            let value = Int64 { hi: (sync.value >> 32) as i32, lo: sync.value as u32 };
+            xconn
+                .xcb_connection()
+                .sync_set_counter(self.id, value)
+                .expect_then_ignore_error("Failed to set XSync counter.");
+        }
+        sync.value
+    }
+}
+
 macro_rules! leap {
     ($e:expr) => {
         $e.map_err(|err| os_error!(err))?
@@ -666,6 +722,7 @@ impl UnownedWindow {
             root,
             screen_id,
             sync_counter_id: None,
+            frame_counter: None,
             selected_cursor: Default::default(),
             cursor_grabbed_mode: Mutex::new(CursorGrabMode::None),
             cursor_visible: Mutex::new(true),
@@ -673,6 +730,7 @@ impl UnownedWindow {
             shared_state: SharedState::new(guessed_monitor, &window_attrs),
             redraw_sender: event_loop.redraw_sender.clone(),
             activation_sender: event_loop.activation_sender.clone(),
+            cancel_compose_sender: event_loop.cancel_compose_sender.clone(),
         };
 
         // Title must be set before mapping. Some tiling window managers (i.e. i3) use the window
@@ -823,14 +881,31 @@ impl UnownedWindow {
                 )
                 .ignore_error();
 
+                // The extended counter tells the compositor when the frames are drawn, so it tells
+                // back when it drew them to pace `ControlFlow::Animate`.
+                let mut counters = vec![sync_counter_id];
+                if util::hint_is_supported(atoms[_NET_WM_FRAME_DRAWN])
+                    && xconn.is_composited(screen_id)
+                {
+                    let frame_counter_id = leap!(xconn.xcb_connection().generate_id());
+                    leap!(
+                        xconn
+                            .xcb_connection()
+                            .sync_create_counter(frame_counter_id, Int64::default())
+                    )
+                    .ignore_error();
+                    window.frame_counter = Some(FrameCounter::new(frame_counter_id));
+                    counters.push(frame_counter_id);
+                }
+
                 let result = xconn.xcb_connection().change_property(
                     xproto::PropMode::REPLACE,
                     window.xwindow,
                     atoms[_NET_WM_SYNC_REQUEST_COUNTER],
                     xproto::AtomEnum::CARDINAL,
                     32,
-                    1,
-                    bytemuck::cast_slice::<u32, u8>(&[sync_counter_id]),
+                    counters.len() as u32,
+                    bytemuck::cast_slice::<u32, u8>(&counters),
                 );
                 leap!(result).ignore_error();
             }
@@ -846,7 +921,21 @@ impl UnownedWindow {
                 | xinput::XIEventMask::TOUCH_BEGIN
                 | xinput::XIEventMask::TOUCH_UPDATE
                 | xinput::XIEventMask::TOUCH_END;
-            leap!(xconn.select_xinput_events(window.xwindow, ALL_MASTER_DEVICES, mask))
+            let mut mask = vec![mask];
+            if event_loop.xi2_gestures {
+                // The gesture event types don't fit into the first mask word, and there are no
+                // constants for them in `XIEventMask`.
+                let gesture_mask = |ty: c_int| 1u64 << ty;
+                let gestures = gesture_mask(ffi::XI_GesturePinchBegin)
+                    | gesture_mask(ffi::XI_GesturePinchUpdate)
+                    | gesture_mask(ffi::XI_GesturePinchEnd)
+                    | gesture_mask(ffi::XI_GestureSwipeBegin)
+                    | gesture_mask(ffi::XI_GestureSwipeUpdate)
+                    | gesture_mask(ffi::XI_GestureSwipeEnd);
+                mask[0] |= xinput::XIEventMask::from(gestures as u32);
+                mask.push(xinput::XIEventMask::from((gestures >> 32) as u32));
+            }
+            leap!(xconn.select_xinput_event_masks(window.xwindow, ALL_MASTER_DEVICES, mask))
                 .ignore_error();
 
             // Set visibility (map window)
@@ -981,7 +1070,7 @@ impl UnownedWindow {
         let atoms = self.xconn.atoms();
         let hint_atom = atoms[_GTK_THEME_VARIANT];
         let utf8_atom = atoms[UTF8_STRING];
-        let variant = match theme {
+        let variant = match theme.or_else(|| self.xconn.xsettings().theme()) {
             Some(Theme::Dark) => "dark",
             Some(Theme::Light) => "light",
             None => "dark",
@@ -998,6 +1087,7 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_theme(&self, theme: Option<Theme>) {
+        self.shared_state_lock().theme = theme;
         self.set_theme_inner(theme).expect("Failed to change window theme").ignore_error();
 
         self.xconn.flush_requests().expect("Failed to change window theme");
@@ -1201,6 +1291,11 @@ impl UnownedWindow {
         Some(self.shared_state_lock().last_monitor.clone())
     }
 
+    /// The refresh rate of the monitor the window was last seen on.
+    pub(crate) fn refresh_rate_millihertz(&self) -> Option<NonZeroU32> {
+        self.shared_state_lock().last_monitor.refresh_rate_millihertz()
+    }
+
     pub fn available_monitors(&self) -> Vec<X11MonitorHandle> {
         self.xconn.available_monitors().expect("Failed to get available monitors")
     }
@@ -1211,21 +1306,7 @@ impl UnownedWindow {
 
     #[inline]
     pub fn is_minimized(&self) -> Option<bool> {
-        let atoms = self.xconn.atoms();
-        let state_atom = atoms[_NET_WM_STATE];
-        let state = self.xconn.get_property(
-            self.xwindow,
-            state_atom,
-            xproto::Atom::from(xproto::AtomEnum::ATOM),
-        );
-        let hidden_atom = atoms[_NET_WM_STATE_HIDDEN];
-
-        Some(match state {
-            Ok(atoms) => {
-                atoms.iter().any(|atom: &xproto::Atom| *atom as xproto::Atom == hidden_atom)
-            },
-            _ => false,
-        })
+        Some(self.states().contains(WindowStates::MINIMIZED))
     }
 
     /// Refresh the API for the given monitor.
@@ -1310,23 +1391,41 @@ impl UnownedWindow {
 
     #[inline]
     pub fn is_maximized(&self) -> bool {
+        self.states().contains(WindowStates::MAXIMIZED)
+    }
+
+    /// Read the window states from `_NET_WM_STATE`.
+    pub fn states(&self) -> WindowStates {
         let atoms = self.xconn.atoms();
-        let state_atom = atoms[_NET_WM_STATE];
-        let state = self.xconn.get_property(
+        let state = self.xconn.get_property::<xproto::Atom>(
             self.xwindow,
-            state_atom,
+            atoms[_NET_WM_STATE],
             xproto::Atom::from(xproto::AtomEnum::ATOM),
         );
-        let horz_atom = atoms[_NET_WM_STATE_MAXIMIZED_HORZ];
-        let vert_atom = atoms[_NET_WM_STATE_MAXIMIZED_VERT];
-        match state {
-            Ok(atoms) => {
-                let horz_maximized = atoms.contains(&horz_atom);
-                let vert_maximized = atoms.contains(&vert_atom);
-                horz_maximized && vert_maximized
-            },
-            _ => false,
+        let Ok(state) = state else {
+            return WindowStates::empty();
+        };
+
+        let flags = [
+            (_NET_WM_STATE_FULLSCREEN, WindowStates::FULLSCREEN),
+            (_NET_WM_STATE_HIDDEN, WindowStates::MINIMIZED),
+            (_NET_WM_STATE_FOCUSED, WindowStates::ACTIVATED),
+            (_NET_WM_STATE_STICKY, WindowStates::STICKY),
+            (_NET_WM_STATE_SHADED, WindowStates::SHADED),
+            (_NET_WM_STATE_ABOVE, WindowStates::ABOVE),
+            (_NET_WM_STATE_BELOW, WindowStates::BELOW),
+        ];
+        let mut states = WindowStates::empty();
+        for (name, flag) in flags {
+            states.set(flag, state.contains(&atoms[name]));
         }
+
+        // Only being maximized in both directions counts as maximized.
+        let maximized = state.contains(&atoms[_NET_WM_STATE_MAXIMIZED_HORZ])
+            && state.contains(&atoms[_NET_WM_STATE_MAXIMIZED_VERT]);
+        states.set(WindowStates::MAXIMIZED, maximized);
+
+        states
     }
 
     fn set_maximized_inner(&self, maximized: bool) -> Result<VoidCookie<'_>, X11Error> {
@@ -1630,10 +1729,6 @@ impl UnownedWindow {
             )
             .expect_then_ignore_error("Failed to call `xcb_configure_window`");
         self.xconn.flush_requests().expect("Failed to call XResizeWindow");
-        // cursor_hittest needs to be reapplied after each window resize.
-        if self.shared_state_lock().cursor_hittest.unwrap_or(false) {
-            let _ = self.set_cursor_hittest(true);
-        }
     }
 
     #[inline]
@@ -1984,38 +2079,49 @@ impl UnownedWindow {
 
     #[inline]
     pub fn set_cursor_hittest(&self, hittest: bool) -> Result<(), RequestError> {
-        // Implement cursor hittest for X11 by either setting an empty or full window input shape.
+        self.set_input_region(if hittest { None } else { Some(&[]) })
+    }
 
+    pub fn set_input_region(&self, region: Option<&[Rect]>) -> Result<(), RequestError> {
         // In X11, every window has two "shapes":
         //   * Bounding shape: defines the visible outline of the window.
         //   * Input shape: defines the region of the window that receives pointer/keyboard events.
-        // If the input shape is the full window rectangle, the window behaves normally.
-        // If the input shape is empty, the window is completely click‑through.
-        // Here, we implement hit test by mapping `hittest = true` to "restore a full input shape"
-        // and `hittest = false` to "clear the input shape" (empty list of rectangles).
-        let mut rectangles: Vec<Rectangle> = Vec::new();
-        if hittest {
-            let size = self.surface_size();
-            rectangles.push(Rectangle {
-                x: 0,
-                y: 0,
-                width: size.width as u16,
-                height: size.height as u16,
-            })
-        }
-        self.xconn
-            .xcb_connection()
-            .shape_rectangles(
-                SO::SET,
-                SK::INPUT,
-                ClipOrdering::UNSORTED,
-                self.xwindow,
-                0,
-                0,
-                &rectangles,
-            )
-            .map_err(|_e| RequestError::Ignored)?;
-        self.shared_state_lock().cursor_hittest = Some(hittest);
+        // Resetting the input shape makes it follow the window size again, while an empty list of
+        // rectangles makes the window completely click-through.
+        let xcb = self.xconn.xcb_connection();
+        let cookie = match region {
+            None => xcb.shape_mask(SO::SET, SK::INPUT, self.xwindow, 0, 0, x11rb::NONE),
+            Some(region) => {
+                let scale_factor = self.scale_factor();
+                let rectangles: Vec<Rectangle> = region
+                    .iter()
+                    .map(|rect| {
+                        let position = rect.position.to_physical::<i32>(scale_factor);
+                        let size = rect.size.to_physical::<u32>(scale_factor);
+                        Rectangle {
+                            x: position.x.clamp(i16::MIN.into(), i16::MAX.into()) as i16,
+                            y: position.y.clamp(i16::MIN.into(), i16::MAX.into()) as i16,
+                            width: size.width.min(u16::MAX.into()) as u16,
+                            height: size.height.min(u16::MAX.into()) as u16,
+                        }
+                    })
+                    .collect();
+
+                xcb.shape_rectangles(
+                    SO::SET,
+                    SK::INPUT,
+                    ClipOrdering::UNSORTED,
+                    self.xwindow,
+                    0,
+                    0,
+                    &rectangles,
+                )
+            },
+        };
+        cookie.map_err(|err| os_error!(X11Error::from(err)))?;
+        self.xconn.flush_requests().map_err(|err| os_error!(X11Error::Xlib(err)))?;
+
+        self.shared_state_lock().input_region = region.map(<[Rect]>::to_vec);
         Ok(())
     }
 
@@ -2153,6 +2259,29 @@ impl UnownedWindow {
             }
         }
 
+        if let Some((hint, purpose)) = state.hint_and_purpose {
+            if capabilities.hint_and_purpose() {
+                let _ = self.ime_sender.lock().unwrap().send(ImeRequest::ContentType(
+                    self.xwindow as ffi::Window,
+                    hint,
+                    purpose,
+                ));
+            } else {
+                warn!("discarding IME hint and purpose update without capability enabled.");
+            }
+        }
+
+        if let Some(surrounding_text) = state.surrounding_text {
+            if capabilities.surrounding_text() {
+                let _ = self.ime_sender.lock().unwrap().send(ImeRequest::SurroundingText(
+                    self.xwindow as ffi::Window,
+                    surrounding_text,
+                ));
+            } else {
+                warn!("discarding IME surrounding text update without capability enabled.");
+            }
+        }
+
         // Pretend that there is always some input method available.
         // Better to make an application think it has an input method and send more events when it
         // doesn't than think there is no input method and not send any IME events.
@@ -2253,11 +2382,60 @@ impl UnownedWindow {
         self.sync_counter_id
     }
 
+    /// Whether the compositor tells when it drew the frames, with `_NET_WM_FRAME_DRAWN`.
+    pub(crate) fn has_frame_counter(&self) -> bool {
+        self.frame_counter.is_some()
+    }
+
+    /// Tell the compositor a frame is being drawn, so it waits for its end.
+    pub(crate) fn begin_frame(&self) {
+        if let Some(counter) = &self.frame_counter {
+            counter.update(&self.xconn, |sync| {
+                if sync.value % 2 == 0 {
+                    sync.value += 1;
+                }
+            });
+        }
+    }
+
+    /// Tell the compositor the frame was drawn, returning the value of the counter it reports
+    /// with `_NET_WM_FRAME_DRAWN`.
+    pub(crate) fn end_frame(&self) -> Option<u64> {
+        let counter = self.frame_counter.as_ref()?;
+        Some(counter.update(&self.xconn, |sync| {
+            if sync.value % 2 == 1 {
+                sync.value += 1;
+            }
+            sync.value = sync.value.max(sync.requested.take().unwrap_or(0));
+        }))
+    }
+
+    /// Answer a `_NET_WM_SYNC_REQUEST` using the extended counter, once the current frame is
+    /// drawn.
+    pub(crate) fn frame_sync_request(&self, value: u64) {
+        if let Some(counter) = &self.frame_counter {
+            // The values of the frames being drawn are odd.
+            let value = value.next_multiple_of(2);
+            counter.update(&self.xconn, |sync| {
+                if sync.value % 2 == 0 {
+                    sync.value = sync.value.max(value);
+                } else {
+                    sync.requested = Some(value);
+                }
+            });
+        }
+    }
+
     #[inline]
     pub fn request_redraw(&self) {
         self.redraw_sender.send(WindowId::from_raw(self.xwindow as _));
     }
 
+    #[inline]
+    pub fn cancel_compose(&self) {
+        self.cancel_compose_sender.send(WindowId::from_raw(self.xwindow as _));
+    }
+
     #[inline]
     pub fn pre_present_notify(&self) {
         // TODO timer
@@ -2286,7 +2464,19 @@ impl UnownedWindow {
 
     #[inline]
     pub fn theme(&self) -> Option<Theme> {
-        None
+        self.shared_state_lock().theme.or_else(|| self.xconn.xsettings().theme())
+    }
+
+    /// Follow a change of the system theme, unless the theme was overridden by the user.
+    ///
+    /// Returns the new theme when it should be reported to the user.
+    pub(crate) fn system_theme_changed(&self, theme: Theme) -> Option<Theme> {
+        if self.shared_state_lock().theme.is_some() {
+            return None;
+        }
+
+        self.set_theme_inner(None).expect("Failed to change window theme").ignore_error();
+        Some(theme)
     }
 
     pub fn set_content_protected(&self, _protected: bool) {}
@@ -1,9 +1,9 @@
 use std::collections::HashMap;
 use std::error::Error;
-use std::ffi::c_int;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::ffi::{c_int, c_void};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
-use std::{fmt, ptr};
+use std::{fmt, mem, ptr};
 
 use rwh_06::HasDisplayHandle;
 use winit_core::cursor::CursorIcon;
@@ -18,6 +18,7 @@ use super::atoms::Atoms;
 use super::ffi;
 use super::monitor::MonitorHandle;
 use crate::event_loop::X11Error;
+use crate::xsettings::XSettings;
 
 /// A connection to an X server.
 pub struct XConnection {
@@ -58,11 +59,21 @@ pub struct XConnection {
     /// Atom for the XSettings screen.
     xsettings_screen: Option<xproto::Atom>,
 
+    /// The window of the current XSettings manager, which owns the XSettings selection.
+    xsettings_owner: Mutex<Option<xproto::Window>>,
+
+    /// The last settings read from the XSettings manager.
+    xsettings: Mutex<XSettings>,
+
     /// XRender format information.
     render_formats: render::QueryPictFormatsReply,
 
     pub latest_error: Mutex<Option<XError>>,
     pub cursor_cache: Mutex<HashMap<Option<CursorIcon>, xproto::Cursor>>,
+
+    /// Set by Xlib once the connection to the server is lost, boxed for its address to be given
+    /// to the I/O error exit handler.
+    disconnected: Box<AtomicBool>,
 }
 
 impl HasDisplayHandle for XConnection {
@@ -97,6 +108,12 @@ impl XConnection {
             display
         };
 
+        // Keep Xlib from exiting the process when the connection is lost.
+        let disconnected = Box::new(AtomicBool::new(false));
+        if !unsafe { set_io_error_exit_handler(display, &disconnected) } {
+            tracing::debug!("XSetIOErrorExitHandler is unavailable, losing the X server will exit");
+        }
+
         // Open the x11rb XCB connection.
         let xcb = {
             // Get a pointer to the underlying XCB connection
@@ -144,7 +161,7 @@ impl XConnection {
         let formats =
             formats_cookie.reply().map_err(|e| XNotSupported::XcbConversionError(Arc::new(e)))?;
 
-        Ok(XConnection {
+        let xconn = XConnection {
             xlib,
             xinput2,
             display,
@@ -159,7 +176,17 @@ impl XConnection {
             randr_version: (randr_version.major_version, randr_version.minor_version),
             render_formats: formats,
             xsettings_screen,
-        })
+            xsettings_owner: Mutex::new(None),
+            xsettings: Default::default(),
+            disconnected,
+        };
+
+        // Start tracking the XSettings manager.
+        if let Err(err) = xconn.reload_xsettings() {
+            tracing::warn!("error reading XSETTINGS: {err}")
+        }
+
+        Ok(xconn)
     }
 
     fn new_xsettings_screen(xcb: &XCBConnection, default_screen: usize) -> Option<xproto::Atom> {
@@ -171,15 +198,13 @@ impl XConnection {
             .ok()?
             .atom;
 
-        // Get PropertyNotify events from the XSETTINGS window.
-        // TODO: The XSETTINGS window here can change. In the future, listen for DestroyNotify on
-        // this window in order to accommodate for a changed window here.
-        let selector_window = xcb.get_selection_owner(xsettings_screen).ok()?.reply().ok()?.owner;
-
+        // Get notified when a new XSettings manager takes over the selection. Managers announce
+        // themselves with a `MANAGER` client message sent to the root window.
+        let root = xcb.setup().roots[default_screen].root;
         xcb.change_window_attributes(
-            selector_window,
+            root,
             &xproto::ChangeWindowAttributesAux::new()
-                .event_mask(xproto::EventMask::PROPERTY_CHANGE),
+                .event_mask(xproto::EventMask::STRUCTURE_NOTIFY),
         )
         .ok()?
         .check()
@@ -188,6 +213,57 @@ impl XConnection {
         Some(xsettings_screen)
     }
 
+    /// Re-read the XSettings, following a change of the XSettings manager.
+    ///
+    /// Returns the previous settings.
+    pub fn reload_xsettings(&self) -> Result<XSettings, X11Error> {
+        let xsettings_screen = match self.xsettings_screen {
+            Some(xsettings_screen) => xsettings_screen,
+            None => return Ok(self.xsettings()),
+        };
+
+        let owner = self.xcb_connection().get_selection_owner(xsettings_screen)?.reply()?.owner;
+        let owner = (owner != x11rb::NONE).then_some(owner);
+
+        let mut xsettings_owner = self.xsettings_owner.lock().unwrap_or_else(|e| e.into_inner());
+        if *xsettings_owner != owner {
+            // Get PropertyNotify events when settings change, and DestroyNotify events when the
+            // manager goes away.
+            if let Some(owner) = owner {
+                self.xcb_connection()
+                    .change_window_attributes(
+                        owner,
+                        &xproto::ChangeWindowAttributesAux::new().event_mask(
+                            xproto::EventMask::PROPERTY_CHANGE
+                                | xproto::EventMask::STRUCTURE_NOTIFY,
+                        ),
+                    )?
+                    .check()?;
+            }
+
+            *xsettings_owner = owner;
+        }
+        drop(xsettings_owner);
+
+        let xsettings = self.fetch_xsettings(xsettings_screen)?;
+        Ok(std::mem::replace(
+            &mut self.xsettings.lock().unwrap_or_else(|e| e.into_inner()),
+            xsettings,
+        ))
+    }
+
+    /// The window of the current XSettings manager.
+    #[inline]
+    pub fn xsettings_owner(&self) -> Option<xproto::Window> {
+        *self.xsettings_owner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The last settings read from the XSettings manager.
+    #[inline]
+    pub fn xsettings(&self) -> XSettings {
+        self.xsettings.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
     /// Checks whether an error has been triggered by the previous function calls.
     #[inline]
     pub fn check_errors(&self) -> Result<(), XError> {
@@ -206,6 +282,11 @@ impl XConnection {
         self.xcb.as_ref().expect("xcb_connection somehow called after drop?")
     }
 
+    /// Whether the connection to the X server was lost, after which no request succeeds.
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected.load(Ordering::Relaxed) || self.xcb_connection().has_error().is_some()
+    }
+
     /// Get the list of atoms.
     #[inline]
     pub fn atoms(&self) -> &Atoms {
@@ -309,6 +390,34 @@ impl fmt::Debug for XConnection {
     }
 }
 
+/// Make Xlib flag the connection as lost instead of exiting the process when it fails to talk to
+/// the server, returning whether `XSetIOErrorExitHandler`, added in libX11 1.7, is available.
+unsafe fn set_io_error_exit_handler(display: *mut ffi::Display, disconnected: &AtomicBool) -> bool {
+    type ExitHandler = unsafe extern "C" fn(*mut ffi::Display, *mut c_void);
+    type SetExitHandler = unsafe extern "C" fn(*mut ffi::Display, Option<ExitHandler>, *mut c_void);
+
+    unsafe extern "C" fn exit_handler(_display: *mut ffi::Display, disconnected: *mut c_void) {
+        unsafe { (*disconnected.cast::<AtomicBool>()).store(true, Ordering::Relaxed) };
+    }
+
+    // Look the function up in the libX11 already loaded by `ffi::Xlib::open()`.
+    let library =
+        unsafe { libc::dlopen(c"libX11.so.6".as_ptr(), libc::RTLD_LAZY | libc::RTLD_NOLOAD) };
+    if library.is_null() {
+        return false;
+    }
+
+    let symbol = unsafe { libc::dlsym(library, c"XSetIOErrorExitHandler".as_ptr()) };
+    if !symbol.is_null() {
+        let set_exit_handler = unsafe { mem::transmute::<*mut c_void, SetExitHandler>(symbol) };
+        let disconnected = disconnected as *const AtomicBool as *mut c_void;
+        unsafe { set_exit_handler(display, Some(exit_handler), disconnected) };
+    }
+
+    unsafe { libc::dlclose(library) };
+    !symbol.is_null()
+}
+
 impl Drop for XConnection {
     #[inline]
     fn drop(&mut self) {
@@ -6,7 +6,10 @@
 
 use std::iter;
 use std::num::NonZeroUsize;
+use std::time::Duration;
 
+use winit_core::event_loop::SystemSettings;
+use winit_core::window::Theme;
 use x11rb::protocol::xproto::{self, ConnectionExt};
 
 use super::atoms::*;
@@ -16,6 +19,14 @@ use crate::xdisplay::XConnection;
 type Result<T> = core::result::Result<T, ParserError>;
 
 const DPI_NAME: &[u8] = b"Xft/DPI";
+const THEME_NAME: &[u8] = b"Net/ThemeName";
+const DOUBLE_CLICK_TIME_NAME: &[u8] = b"Net/DoubleClickTime";
+const DOUBLE_CLICK_DISTANCE_NAME: &[u8] = b"Net/DoubleClickDistance";
+const DRAG_THRESHOLD_NAME: &[u8] = b"Net/DndDragThreshold";
+const CURSOR_BLINK_NAME: &[u8] = b"Net/CursorBlink";
+const CURSOR_BLINK_TIME_NAME: &[u8] = b"Net/CursorBlinkTime";
+const CURSOR_SIZE_NAME: &[u8] = b"Gtk/CursorThemeSize";
+const ENABLE_ANIMATIONS_NAME: &[u8] = b"Gtk/EnableAnimations";
 const DPI_MULTIPLIER: f64 = 1024.0;
 const LITTLE_ENDIAN: u8 = b'l';
 const BIG_ENDIAN: u8 = b'B';
@@ -26,33 +37,124 @@ impl XConnection {
         &self,
         xsettings_screen: xproto::Atom,
     ) -> core::result::Result<Option<f64>, X11Error> {
+        Ok(self.fetch_xsettings(xsettings_screen)?.dpi)
+    }
+
+    /// Read and parse the settings of the current XSettings manager.
+    pub(crate) fn fetch_xsettings(
+        &self,
+        xsettings_screen: xproto::Atom,
+    ) -> core::result::Result<XSettings, X11Error> {
         let atoms = self.atoms();
 
         // Get the current owner of the screen's settings.
         let owner = self.xcb_connection().get_selection_owner(xsettings_screen)?.reply()?;
+        if owner.owner == x11rb::NONE {
+            return Ok(XSettings::default());
+        }
 
         // Read the _XSETTINGS_SETTINGS property.
         let data: Vec<u8> =
             self.get_property(owner.owner, atoms[_XSETTINGS_SETTINGS], atoms[_XSETTINGS_SETTINGS])?;
 
         // Parse the property.
-        let dpi_setting = read_settings(&data)?
-            .find(|res| res.as_ref().map_or(true, |s| s.name == DPI_NAME))
-            .transpose()?;
-        if let Some(dpi_setting) = dpi_setting {
-            let base_dpi = match dpi_setting.data {
-                SettingData::Integer(dpi) => dpi as f64,
-                SettingData::String(_) => {
-                    return Err(ParserError::BadType(SettingType::String).into());
+        Ok(XSettings::parse(&data)?)
+    }
+}
+
+/// The XSettings values winit cares about.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct XSettings {
+    /// `Xft/DPI`, in dots per inch.
+    pub dpi: Option<f64>,
+
+    /// `Net/ThemeName`.
+    pub theme_name: Option<String>,
+
+    /// `Net/DoubleClickTime`, in milliseconds.
+    pub double_click_time: Option<i32>,
+
+    /// `Net/DoubleClickDistance`, in pixels.
+    pub double_click_distance: Option<i32>,
+
+    /// `Net/DndDragThreshold`, in pixels.
+    pub drag_threshold: Option<i32>,
+
+    /// `Net/CursorBlink`.
+    pub cursor_blink: Option<bool>,
+
+    /// `Net/CursorBlinkTime`, the length of a whole blink cycle in milliseconds.
+    pub cursor_blink_time: Option<i32>,
+
+    /// `Gtk/CursorThemeSize`, in pixels.
+    pub cursor_size: Option<i32>,
+
+    /// `Gtk/EnableAnimations`.
+    pub enable_animations: Option<bool>,
+}
+
+impl XSettings {
+    /// Parse the settings from the `_XSETTINGS_SETTINGS` property data.
+    fn parse(data: &[u8]) -> Result<Self> {
+        let mut settings = Self::default();
+        for setting in read_settings(data)? {
+            let setting = setting?;
+            match setting.name {
+                DPI_NAME => settings.dpi = Some(setting.integer()? as f64 / DPI_MULTIPLIER),
+                THEME_NAME => settings.theme_name = Some(setting.string()?),
+                DOUBLE_CLICK_TIME_NAME => settings.double_click_time = Some(setting.integer()?),
+                DOUBLE_CLICK_DISTANCE_NAME => {
+                    settings.double_click_distance = Some(setting.integer()?)
                 },
-                SettingData::Color(_) => {
-                    return Err(ParserError::BadType(SettingType::Color).into());
+                DRAG_THRESHOLD_NAME => settings.drag_threshold = Some(setting.integer()?),
+                CURSOR_BLINK_NAME => settings.cursor_blink = Some(setting.integer()? != 0),
+                CURSOR_BLINK_TIME_NAME => settings.cursor_blink_time = Some(setting.integer()?),
+                CURSOR_SIZE_NAME => settings.cursor_size = Some(setting.integer()?),
+                ENABLE_ANIMATIONS_NAME => {
+                    settings.enable_animations = Some(setting.integer()? != 0)
                 },
-            };
+                _ => (),
+            }
+        }
 
-            Ok(Some(base_dpi / DPI_MULTIPLIER))
+        Ok(settings)
+    }
+
+    /// The system theme, derived from the GTK theme name.
+    ///
+    /// Dark variants of themes are conventionally suffixed with `-dark` or `-Dark`.
+    pub fn theme(&self) -> Option<Theme> {
+        let theme_name = self.theme_name.as_ref()?;
+        if theme_name.to_lowercase().contains("dark") {
+            Some(Theme::Dark)
         } else {
-            Ok(None)
+            Some(Theme::Light)
+        }
+    }
+
+    /// The settings exposed through [`SystemSettings`].
+    ///
+    /// Key repeat is not part of XSETTINGS and is left unset.
+    pub fn system_settings(&self) -> SystemSettings {
+        let millis = |value: Option<i32>| {
+            value.and_then(|value| u64::try_from(value).ok()).map(Duration::from_millis)
+        };
+        let pixels = |value: Option<i32>| value.and_then(|value| u32::try_from(value).ok());
+
+        // A blink cycle is made of an "on" and an "off" phase.
+        let caret_blink_interval = match self.cursor_blink {
+            Some(false) => Some(Duration::ZERO),
+            _ => millis(self.cursor_blink_time).map(|cycle| cycle / 2),
+        };
+
+        SystemSettings {
+            double_click_time: millis(self.double_click_time),
+            double_click_distance: pixels(self.double_click_distance),
+            drag_threshold: pixels(self.drag_threshold),
+            caret_blink_interval,
+            cursor_size: pixels(self.cursor_size),
+            reduce_motion: self.enable_animations.map(|enabled| !enabled),
+            ..Default::default()
         }
     }
 }
@@ -82,11 +184,37 @@ struct Setting<'a> {
 /// The data contained in a setting.
 enum SettingData<'a> {
     Integer(i32),
-    String(#[allow(dead_code)] &'a [u8]),
+    String(&'a [u8]),
     Color(#[allow(dead_code)] [i16; 4]),
 }
 
+impl SettingData<'_> {
+    fn ty(&self) -> SettingType {
+        match self {
+            SettingData::Integer(_) => SettingType::Integer,
+            SettingData::String(_) => SettingType::String,
+            SettingData::Color(_) => SettingType::Color,
+        }
+    }
+}
+
 impl<'a> Setting<'a> {
+    /// Get the data of an integer setting.
+    fn integer(&self) -> Result<i32> {
+        match self.data {
+            SettingData::Integer(value) => Ok(value),
+            ref data => Err(ParserError::BadType(data.ty())),
+        }
+    }
+
+    /// Get the data of a string setting.
+    fn string(&self) -> Result<String> {
+        match self.data {
+            SettingData::String(value) => Ok(String::from_utf8_lossy(value).into_owned()),
+            ref data => Err(ParserError::BadType(data.ty())),
+        }
+    }
+
     /// Parse a new `SettingData`.
     fn parse(parser: &mut Parser<'a>) -> Result<Self> {
         // Read the type.
@@ -310,6 +438,38 @@ mod tests {
         assert_string(&lcd.data, "lcddefault");
     }
 
+    #[test]
+    fn parse_known_settings() {
+        let data = XSETTINGS
+            .trim()
+            .split(',')
+            .map(|tok| {
+                let val = tok.strip_prefix("0x").unwrap();
+                u8::from_str_radix(val, 16).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let settings = XSettings::parse(&data).unwrap();
+        assert_eq!(settings.dpi, Some(96.0));
+        assert_eq!(settings.theme_name.as_deref(), Some("Greybird"));
+        assert_eq!(settings.theme(), Some(Theme::Light));
+
+        let dark = XSettings { theme_name: Some("Adwaita-dark".into()), ..settings };
+        assert_eq!(dark.theme(), Some(Theme::Dark));
+        assert_eq!(XSettings::default().theme(), None);
+
+        let system = settings.system_settings();
+        assert_eq!(system.double_click_time, Some(Duration::from_millis(400)));
+        assert_eq!(system.double_click_distance, Some(5));
+        assert_eq!(system.drag_threshold, Some(8));
+        assert_eq!(system.caret_blink_interval, Some(Duration::from_millis(600)));
+        assert_eq!(system.cursor_size, Some(24));
+        assert_eq!(system.reduce_motion, None);
+
+        let no_blink = XSettings { cursor_blink: Some(false), ..settings };
+        assert_eq!(no_blink.system_settings().caret_blink_interval, Some(Duration::ZERO));
+    }
+
     fn assert_string(dat: &SettingData<'_>, s: &str) {
         match dat {
             SettingData::String(left) => assert_eq!(*left, s.as_bytes()),
@@ -567,6 +567,12 @@ impl ApplicationHandler for Application {
         }
     }
 
+    fn connection_lost(&mut self, _event_loop: &dyn ActiveEventLoop) {
+        // The event loop exits right after, this is where the state of the application is saved.
+        error!("Lost the connection to the display server with {} windows", self.windows.len());
+        self.windows.clear();
+    }
+
     fn macos_handler(&mut self) -> Option<&mut dyn ApplicationHandlerExtMacOS> {
         Some(self)
     }
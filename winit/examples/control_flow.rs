@@ -28,6 +28,7 @@ enum Mode {
     Wait,
     WaitUntil,
     Poll,
+    Animate,
 }
 
 fn main() -> Result<(), impl std::error::Error> {
@@ -39,6 +40,7 @@ fn main() -> Result<(), impl std::error::Error> {
     info!("Press '1' to switch to Wait mode.");
     info!("Press '2' to switch to WaitUntil mode.");
     info!("Press '3' to switch to Poll mode.");
+    info!("Press '4' to switch to Animate mode.");
     info!("Press 'R' to toggle request_redraw() calls.");
     info!("Press 'Esc' to close the window.");
 
@@ -68,7 +70,7 @@ impl ApplicationHandler for ControlFlowDemo {
 
     fn can_create_surfaces(&mut self, event_loop: &dyn ActiveEventLoop) {
         let window_attributes = WindowAttributes::default().with_title(
-            "Press 1, 2, 3 to change control flow mode. Press R to toggle redraw requests.",
+            "Press 1, 2, 3, 4 to change control flow mode. Press R to toggle redraw requests.",
         );
         let window = event_loop.create_window(window_attributes).unwrap();
         let context = Context::new(event_loop.owned_display_handle()).unwrap();
@@ -106,6 +108,10 @@ impl ApplicationHandler for ControlFlowDemo {
                     self.mode = Mode::Poll;
                     warn!("mode: {:?}", self.mode);
                 },
+                Key::Character("4") => {
+                    self.mode = Mode::Animate;
+                    warn!("mode: {:?}", self.mode);
+                },
                 Key::Character("r") => {
                     self.request_redraw = !self.request_redraw;
                     warn!("request_redraw: {}", self.request_redraw);
@@ -141,6 +147,7 @@ impl ApplicationHandler for ControlFlowDemo {
                 thread::sleep(POLL_SLEEP_TIME);
                 event_loop.set_control_flow(ControlFlow::Poll);
             },
+            Mode::Animate => event_loop.set_control_flow(ControlFlow::Animate),
         };
 
         if self.close_requested {
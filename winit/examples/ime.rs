@@ -218,7 +218,9 @@ impl App {
         let surface = self.surface.as_ref().expect("IME request without a window");
         match event {
             Ime::Enabled => info!("IME enabled for Window={:?}", surface.window().id()),
-            Ime::Preedit(text, caret_pos) => info!("Preedit: {text}, with caret at {caret_pos:?}"),
+            Ime::Preedit(text, caret_pos, styles) => {
+                info!("Preedit: {text}, with caret at {caret_pos:?} and styles {styles:?}")
+            },
             Ime::Commit(text) => {
                 self.input_state.append_text(&text);
                 let request_data = self.get_ime_update();
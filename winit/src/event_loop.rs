@@ -411,6 +411,12 @@ impl winit_wayland::EventLoopBuilderExtWayland for EventLoopBuilder {
         self.platform_specific.any_thread = any_thread;
         self
     }
+
+    #[inline]
+    fn with_keymap(&mut self, keymap: winit_wayland::KeymapSource) -> &mut Self {
+        self.platform_specific.keymap = Some(keymap);
+        self
+    }
 }
 
 #[cfg(web_platform)]
@@ -489,6 +495,12 @@ impl winit_x11::EventLoopBuilderExtX11 for EventLoopBuilder {
         self.platform_specific.any_thread = any_thread;
         self
     }
+
+    #[inline]
+    fn with_keymap(&mut self, keymap: winit_x11::KeymapSource) -> &mut Self {
+        self.platform_specific.keymap = Some(keymap);
+        self
+    }
 }
 
 /// ```compile_error
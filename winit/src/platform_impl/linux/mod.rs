@@ -7,6 +7,7 @@ use std::env;
 use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
 use std::time::Duration;
 
+use winit_common::xkb::KeymapSource;
 pub(crate) use winit_common::xkb::{physicalkey_to_scancode, scancode_to_physicalkey};
 use winit_core::application::ApplicationHandler;
 use winit_core::cursor::{CustomCursor, CustomCursorSource};
@@ -29,10 +30,11 @@ pub(crate) enum Backend {
     Wayland,
 }
 
-#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct PlatformSpecificEventLoopAttributes {
     pub(crate) forced_backend: Option<Backend>,
     pub(crate) any_thread: bool,
+    pub(crate) keymap: Option<KeymapSource>,
 }
 
 /// `x11_or_wayland!(match expr; Enum(foo) => foo.something())`
@@ -124,20 +126,20 @@ impl EventLoop {
         // Create the display based on the backend.
         match backend {
             #[cfg(wayland_platform)]
-            Backend::Wayland => EventLoop::new_wayland_any_thread(),
+            Backend::Wayland => EventLoop::new_wayland_any_thread(attributes.keymap.clone()),
             #[cfg(x11_platform)]
-            Backend::X => EventLoop::new_x11_any_thread(),
+            Backend::X => EventLoop::new_x11_any_thread(attributes.keymap.clone()),
         }
     }
 
     #[cfg(wayland_platform)]
-    fn new_wayland_any_thread() -> Result<EventLoop, EventLoopError> {
-        wayland::EventLoop::new().map(|evlp| EventLoop::Wayland(Box::new(evlp)))
+    fn new_wayland_any_thread(keymap: Option<KeymapSource>) -> Result<EventLoop, EventLoopError> {
+        wayland::EventLoop::new(keymap).map(|evlp| EventLoop::Wayland(Box::new(evlp)))
     }
 
     #[cfg(x11_platform)]
-    fn new_x11_any_thread() -> Result<EventLoop, EventLoopError> {
-        x11::EventLoop::new().map(EventLoop::X)
+    fn new_x11_any_thread(keymap: Option<KeymapSource>) -> Result<EventLoop, EventLoopError> {
+        x11::EventLoop::new(keymap).map(EventLoop::X)
     }
 
     #[inline]